@@ -0,0 +1,362 @@
+//!
+//! Achievements unlocked by the player of the first board. Achievements are not unlocked in a
+//! local two player game or while the AI is playing. Unlocked achievements are saved to disk.
+//!
+use crate::ai::AiPlayer;
+use crate::audio::{PlaySfxEvent, Sfx};
+use crate::board::{BoardId, PerBoard};
+use crate::constants::prelude::*;
+use crate::controls::MenuAction;
+use crate::fraction::Fraction;
+use crate::in_game::{
+    BlockClearedEvent, Chain, Level, Operation, PerformCalculationEvent, SpawnSolidBlockEvent,
+    StartLevel,
+};
+use crate::localization::{Language, TextKey};
+use crate::prelude::*;
+use crate::rules::Versus;
+use crate::stats::is_tracked;
+use crate::storage::{load_values, save_values};
+use crate::toast::ShowToastEvent;
+use crate::view::BoardArea;
+use bevy::prelude::*;
+
+/// Name of the file holding the unlocked achievements
+const ACHIEVEMENTS_FILE: &str = "achievements.txt";
+
+/// Achievements of the game
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Achievement {
+    /// Clear a block with number 99
+    Clear99,
+    /// Clear three blocks with one drop
+    TripleClear,
+    /// Combine only with addition for a whole game
+    OnlyAddition,
+    /// Reach level 10
+    Level10,
+    /// Clear blocks with five drops in a row
+    Chain5,
+}
+
+impl Achievement {
+    pub const ALL: [Achievement; 5] = [
+        Achievement::Clear99,
+        Achievement::TripleClear,
+        Achievement::OnlyAddition,
+        Achievement::Level10,
+        Achievement::Chain5,
+    ];
+
+    /// Returns the name of the achievement
+    pub fn name(&self) -> &'static str {
+        match self {
+            Achievement::Clear99 => "Ninety-nine",
+            Achievement::TripleClear => "Hat trick",
+            Achievement::OnlyAddition => "Keep adding",
+            Achievement::Level10 => "Double digits",
+            Achievement::Chain5 => "Chain reaction",
+        }
+    }
+
+    /// Returns the description of how the achievement is unlocked
+    pub fn description(&self) -> &'static str {
+        match self {
+            Achievement::Clear99 => "Clear a 99",
+            Achievement::TripleClear => "Clear three blocks with one drop",
+            Achievement::OnlyAddition => "Use only addition for a whole game",
+            Achievement::Level10 => "Reach level 10",
+            Achievement::Chain5 => "Clear blocks with 5 drops in a row",
+        }
+    }
+
+    /// Returns the key of the achievement in the saved file
+    fn key(&self) -> String {
+        format!("{:?}", self).to_lowercase()
+    }
+}
+
+/// This resource holds the unlocked achievements
+pub struct Achievements(Vec<Achievement>);
+
+impl Achievements {
+    /// Returns `true` if the achievement has been unlocked
+    pub fn is_unlocked(&self, achievement: Achievement) -> bool {
+        self.0.contains(&achievement)
+    }
+
+    /// Loads the unlocked achievements from disk
+    fn load() -> Achievements {
+        let values = load_values(ACHIEVEMENTS_FILE);
+        Achievements(
+            Achievement::ALL
+                .into_iter()
+                .filter(|achievement| values.contains_key(&achievement.key()))
+                .collect(),
+        )
+    }
+
+    /// Saves the unlocked achievements to disk
+    fn save(&self) {
+        let values: Vec<(String, String)> = self
+            .0
+            .iter()
+            .map(|achievement| (achievement.key(), "1".to_string()))
+            .collect();
+        save_values(ACHIEVEMENTS_FILE, &values);
+    }
+}
+
+/// This `Event` is sent when the player has done something which unlocks the achievement
+pub struct UnlockAchievementEvent(pub Achievement);
+
+/// This resource tracks the progress of the achievements during the game
+#[derive(Default)]
+struct AchievementProgress {
+    /// Blocks cleared since the latest drop
+    cleared_by_drop: u32,
+    /// Number of combinations during the game
+    combinations: u32,
+    /// Number of combinations performed with addition
+    addition_combinations: u32,
+}
+
+impl AchievementProgress {
+    /// Returns `true` if blocks were combined during the game, and only with addition
+    fn used_only_addition(&self) -> bool {
+        self.combinations > 0 && self.combinations == self.addition_combinations
+    }
+}
+
+/// Bevy Plugin for the achievements
+pub struct AchievementsPlugin;
+impl Plugin for AchievementsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(GameState::InGame).with_system(on_game_start))
+            .add_system_set(
+                SystemSet::on_update(GameState::InGame)
+                    .with_system(track_drops)
+                    .with_system(track_clears)
+                    .with_system(track_combinations)
+                    .with_system(track_level_and_chain),
+            )
+            .add_system_set(SystemSet::on_enter(GameState::GameOver).with_system(on_game_over))
+            .add_system(unlock_achievements)
+            .add_system_set(SystemSet::on_enter(GameState::Achievements).with_system(on_enter))
+            .add_system_set(SystemSet::on_exit(GameState::Achievements).with_system(on_exit))
+            .add_system_set(SystemSet::on_update(GameState::Achievements).with_system(back_to_menu))
+            .add_event::<UnlockAchievementEvent>()
+            .insert_resource(AchievementProgress::default())
+            .insert_resource(Achievements::load());
+    }
+}
+
+/// Called once as the game is started
+fn on_game_start(mut progress: ResMut<AchievementProgress>) {
+    *progress = AchievementProgress::default();
+}
+
+/// System for resetting the blocks cleared by the drop each time a block lands
+fn track_drops(
+    mut events: EventReader<SpawnSolidBlockEvent>,
+    mut progress: ResMut<AchievementProgress>,
+) {
+    for ev in events.iter() {
+        if ev.board == 0 && !ev.inserted {
+            progress.cleared_by_drop = 0;
+        }
+    }
+}
+
+/// System for checking the cleared blocks
+fn track_clears(
+    mut events: EventReader<BlockClearedEvent>,
+    mut progress: ResMut<AchievementProgress>,
+    mut unlock_events: EventWriter<UnlockAchievementEvent>,
+) {
+    for ev in events.iter().filter(|ev| ev.board == 0) {
+        if ev.number == Fraction::from(99) {
+            unlock_events.send(UnlockAchievementEvent(Achievement::Clear99));
+        }
+
+        progress.cleared_by_drop += 1;
+        if progress.cleared_by_drop == 3 {
+            unlock_events.send(UnlockAchievementEvent(Achievement::TripleClear));
+        }
+    }
+}
+
+/// System for counting the combinations and their operations
+fn track_combinations(
+    mut events: EventReader<PerformCalculationEvent>,
+    query: Query<&BoardId>,
+    mut progress: ResMut<AchievementProgress>,
+) {
+    for ev in events.iter() {
+        let board = query.get(ev.entity).map(|board| board.0).unwrap_or(0);
+        if board == 0 && !ev.neighbors.is_empty() {
+            progress.combinations += 1;
+            if ev.operation == Operation::ADD {
+                progress.addition_combinations += 1;
+            }
+        }
+    }
+}
+
+/// System for checking the level and the chain length
+fn track_level_and_chain(
+    levels: Res<PerBoard<Level>>,
+    chains: Res<PerBoard<Chain>>,
+    start_level: Res<StartLevel>,
+    mut unlock_events: EventWriter<UnlockAchievementEvent>,
+) {
+    // Level has to be reached by playing, not by starting from it
+    if levels.is_changed() && levels[0].number >= 10 && levels[0].number > start_level.0 {
+        unlock_events.send(UnlockAchievementEvent(Achievement::Level10));
+    }
+    if chains.is_changed() && chains[0].length >= 5 {
+        unlock_events.send(UnlockAchievementEvent(Achievement::Chain5));
+    }
+}
+
+/// Called once when the game is over. The game counts as a whole game once the first level has
+/// been completed.
+fn on_game_over(
+    progress: Res<AchievementProgress>,
+    levels: Res<PerBoard<Level>>,
+    mut unlock_events: EventWriter<UnlockAchievementEvent>,
+) {
+    if levels[0].cleared_blocks >= BLOCKS_PER_LEVEL && progress.used_only_addition() {
+        unlock_events.send(UnlockAchievementEvent(Achievement::OnlyAddition));
+    }
+}
+
+/// System for unlocking the achievements. Shows a toast for each new achievement.
+fn unlock_achievements(
+    mut events: EventReader<UnlockAchievementEvent>,
+    mut achievements: ResMut<Achievements>,
+    mut toast_events: EventWriter<ShowToastEvent>,
+    mut audio_events: EventWriter<PlaySfxEvent>,
+    ai: Res<AiPlayer>,
+    versus: Res<Versus>,
+    language: Res<Language>,
+) {
+    for ev in events.iter() {
+        if !is_tracked(*versus) || ai.assisted || achievements.is_unlocked(ev.0) {
+            continue;
+        }
+
+        println!("Achievement unlocked: {}", ev.0.name());
+        achievements.0.push(ev.0);
+        achievements.save();
+        toast_events.send(ShowToastEvent(language.format(
+            TextKey::AchievementUnlocked,
+            &[&language.get(TextKey::AchievementName(ev.0))],
+        )));
+        audio_events.send(PlaySfxEvent(Sfx::LevelUp));
+    }
+}
+
+/// Called once when switching to `GameState::Achievements`
+fn on_enter(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    achievements: Res<Achievements>,
+    language: Res<Language>,
+) {
+    println!("Enter GameState::Achievements");
+
+    let node = commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            color: UiColor(Color::NONE),
+            ..default()
+        })
+        .insert(MenuNode)
+        .insert(BoardArea)
+        .id();
+
+    let mut lines = vec![
+        language.format(
+            TextKey::Achievements,
+            &[&achievements.0.len(), &Achievement::ALL.len()],
+        ),
+        String::new(),
+    ];
+    for achievement in Achievement::ALL {
+        let mark = if achievements.is_unlocked(achievement) {
+            "[x]"
+        } else {
+            "[ ]"
+        };
+        lines.push(format!(
+            "{} {}",
+            mark,
+            language.get(TextKey::AchievementName(achievement))
+        ));
+        lines.push(language.get(TextKey::AchievementDescription(achievement)));
+        lines.push(String::new());
+    }
+    lines.push(language.get(TextKey::Back));
+
+    let text = commands
+        .spawn_bundle(
+            TextBundle::from_section(
+                lines.join("\n"),
+                TextStyle {
+                    font: asset_server.load("fonts/04b_30.ttf"),
+                    font_size: 16.0,
+                    color: Color::BLACK,
+                },
+            )
+            .with_text_alignment(TextAlignment::CENTER),
+        )
+        .id();
+
+    commands.entity(node).push_children(&[text]);
+}
+
+/// Called once when switching from `GameState::Achievements`
+fn on_exit(mut commands: Commands, query: Query<Entity, With<MenuNode>>) {
+    println!("Exit GameState::Achievements");
+
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Switch back to `GameState::Menu` if Esc or Return key is pressed
+fn back_to_menu(mut actions: ResMut<Input<MenuAction>>, mut game_state: ResMut<State<GameState>>) {
+    if actions.just_pressed(MenuAction::Back) || actions.just_pressed(MenuAction::Select) {
+        game_state
+            .set(GameState::Menu)
+            .expect("Failed to change GameState::Menu");
+        actions.reset(MenuAction::Back);
+        actions.reset(MenuAction::Select);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_addition_needs_combinations() {
+        let progress = |combinations, addition_combinations| AchievementProgress {
+            combinations,
+            addition_combinations,
+            ..default()
+        };
+        assert!(progress(3, 3).used_only_addition());
+        assert!(!progress(3, 2).used_only_addition());
+
+        // Game without any combinations, e.g. with only bombs, doesn't count
+        assert!(!progress(0, 0).used_only_addition());
+    }
+}
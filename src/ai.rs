@@ -0,0 +1,676 @@
+//!
+//! Built-in AI player which controls the dropping block through the same `PlayerAction`s as the
+//! keyboard. Used as an autopilot, as an opponent in a versus game, in the attract mode of the
+//! main menu and for benchmarking the game rules.
+//!
+use crate::board::{find_drop_neighbors, BlockMap, BoardId, PerBoard};
+use crate::constants::prelude::*;
+use crate::controls::{MenuAction, PlayerAction, PlayerInputLabel};
+use crate::fraction::Fraction;
+use crate::in_game::{
+    BlockColor, BlockKind, BlockParams, BlockPosition, DroppingBlock, Level, NextBlock, Number,
+    Operation, SolidBlock,
+};
+use crate::localization::{Language, TextKey};
+use crate::prelude::*;
+use crate::rules::{GameRules, Versus};
+use crate::toast::ShowToastEvent;
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+
+/// Time the main menu waits for input before starting a demo game (in seconds)
+const ATTRACT_MODE_DELAY: f32 = 20.0;
+
+/// Time the game over screen of a demo game is shown (in seconds)
+const DEMO_GAME_OVER_TIME: f32 = 5.0;
+
+/// Value of each block cleared by the placement
+const CLEAR_VALUE: f32 = 10.0;
+
+/// Value of leaving a number which the next block can clear
+const SETUP_VALUE: f32 = 3.0;
+
+/// Penalty for each row the block lands above the bottom
+const HEIGHT_PENALTY: f32 = 0.5;
+
+/// Penalty for landing on the top row, which ends the game
+const GAME_OVER_PENALTY: f32 = 1000.0;
+
+/// Skill level of the AI player
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum AiSkill {
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl AiSkill {
+    /// All the skill levels in the order they are cycled
+    pub const ALL: [AiSkill; 3] = [AiSkill::Easy, AiSkill::Normal, AiSkill::Hard];
+
+    /// Returns the name of the skill level
+    pub fn name(&self) -> &'static str {
+        match self {
+            AiSkill::Easy => "Easy",
+            AiSkill::Normal => "Normal",
+            AiSkill::Hard => "Hard",
+        }
+    }
+
+    /// Returns the next skill level (wraps around)
+    pub fn next(&self) -> AiSkill {
+        let index = AiSkill::ALL.iter().position(|s| s == self).unwrap_or(0);
+        AiSkill::ALL[(index + 1) % AiSkill::ALL.len()]
+    }
+
+    /// Time between two actions (in seconds)
+    fn action_interval(&self) -> f32 {
+        match self {
+            AiSkill::Easy => 0.35,
+            AiSkill::Normal => 0.2,
+            AiSkill::Hard => 0.08,
+        }
+    }
+
+    /// Chance (0.0 - 1.0) of choosing a random placement instead of the best one
+    fn mistake_chance(&self) -> f32 {
+        match self {
+            AiSkill::Easy => 0.25,
+            AiSkill::Normal => 0.08,
+            AiSkill::Hard => 0.0,
+        }
+    }
+
+    /// Returns `true` if the next block is taken into account
+    fn uses_next_block(&self) -> bool {
+        *self != AiSkill::Easy
+    }
+
+    /// Returns `true` if the block is dropped fast once it's in place
+    fn soft_drops(&self) -> bool {
+        *self == AiSkill::Hard
+    }
+}
+
+/// Column and color chosen for a dropping block
+struct Plan {
+    block: Entity,
+    column: i32,
+    color: BlockColor,
+}
+
+/// This resource holds the state of the AI player
+pub struct AiPlayer {
+    /// AI controls the dropping block
+    pub enabled: bool,
+    /// Demo game started from the main menu (attract mode)
+    pub demo: bool,
+    /// AI has controlled the current game at some point. High score is not kept for these games.
+    pub assisted: bool,
+    /// Board controlled by the AI
+    pub board: usize,
+    pub skill: AiSkill,
+    plan: Option<Plan>,
+    action_timer: Timer,
+    drops: u32,
+    /// Random number generator for the mistakes
+    rng: StdRng,
+}
+
+impl Default for AiPlayer {
+    fn default() -> Self {
+        AiPlayer {
+            enabled: false,
+            demo: false,
+            assisted: false,
+            board: 0,
+            skill: AiSkill::Normal,
+            plan: None,
+            action_timer: Timer::from_seconds(AiSkill::Normal.action_interval(), false),
+            drops: 0,
+            rng: StdRng::from_entropy(),
+        }
+    }
+}
+
+/// Timer for starting the attract mode from the main menu
+struct AttractTimer(Timer);
+
+/// Timer for returning from the game over screen of a demo game
+struct DemoGameOverTimer(Timer);
+
+/// Bevy Plugin for the AI player
+pub struct AiPlugin;
+impl Plugin for AiPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(GameState::Menu).with_system(reset_attract_mode))
+            .add_system_set(SystemSet::on_update(GameState::Menu).with_system(start_attract_mode))
+            .add_system_set(SystemSet::on_enter(GameState::InGame).with_system(on_game_start))
+            .add_system_set(
+                SystemSet::on_update(GameState::InGame)
+                    .with_system(toggle_autopilot)
+                    .with_system(play.label(PlayerInputLabel)),
+            )
+            .add_system_set(SystemSet::on_exit(GameState::InGame).with_system(on_game_end))
+            // Demo game ends after the other systems so that it overrides their state changes
+            .add_system_to_stage(CoreStage::PostUpdate, exit_demo_on_key)
+            .add_system_to_stage(CoreStage::PostUpdate, demo_back_to_menu)
+            .insert_resource(AiPlayer::default())
+            .insert_resource(AttractTimer(Timer::from_seconds(ATTRACT_MODE_DELAY, false)))
+            .insert_resource(DemoGameOverTimer(Timer::from_seconds(
+                DEMO_GAME_OVER_TIME,
+                false,
+            )));
+    }
+}
+
+/// Returns the position where a block moved from `from` into `column` lands. `None` if the
+/// column can't be reached.
+pub fn landing_position(block_map: &BlockMap, from: &Coords, column: i32) -> Option<Coords> {
+    let step = (column - from.x).signum();
+    let mut pos = *from;
+    while pos.x != column {
+        pos.x += step;
+        if !block_map.is_none(&pos) {
+            return None;
+        }
+    }
+    while block_map.is_none(&Coords::new(pos.x, pos.y - 1)) {
+        pos.y -= 1;
+    }
+    Some(pos)
+}
+
+/// State of the game which the placements are evaluated against
+pub struct Situation<'a> {
+    pub block_map: &'a BlockMap,
+    /// Numbers of the solid blocks
+    pub numbers: &'a HashMap<Entity, Fraction>,
+    pub rules: &'a GameRules,
+    pub level: u32,
+    /// Block which is dropped next, if it's taken into account
+    pub next: Option<&'a BlockParams>,
+}
+
+impl<'a> Situation<'a> {
+    /// Returns the value of dropping the block with given color at `pos`. Higher is better.
+    pub fn evaluate(&self, block: &BlockParams, color: BlockColor, pos: &Coords) -> f32 {
+        let mut value = -(pos.y as f32) * HEIGHT_PENALTY;
+        if pos.y >= self.block_map.size().initial_position().y - 1 {
+            value -= GAME_OVER_PENALTY;
+        }
+
+        let neighbors =
+            find_drop_neighbors(self.block_map, pos, color, block.kind, self.rules.adjacency);
+        match block.kind {
+            BlockKind::STONE => return value,
+            BlockKind::BOMB => return value + neighbors.len() as f32 * CLEAR_VALUE,
+            BlockKind::NORMAL | BlockKind::WILDCARD => {}
+        }
+
+        let neighbor_numbers: Vec<Fraction> = neighbors
+            .iter()
+            .filter_map(|(_, entity)| self.numbers.get(entity).copied())
+            .collect();
+        let (results, dropped) =
+            self.rules
+                .combine(block.number, block.operation, &neighbor_numbers);
+
+        let target = self.rules.target_rule(self.level);
+        let mut remaining = Vec::new();
+        for number in results.iter().copied().chain(dropped) {
+            if target.is_target(number, self.level) {
+                value += CLEAR_VALUE;
+            } else {
+                value += target.closeness(number, self.level);
+                remaining.push(number);
+            }
+        }
+        if dropped.is_none() {
+            remaining.push(block.number);
+        }
+
+        // Prefer leaving numbers which the next block is able to clear
+        if let Some(next) = self.next {
+            let divides_by_zero = (next.operation == Operation::DIVIDE
+                || next.operation == Operation::MODULO)
+                && next.number == Fraction::ZERO;
+            if next.kind != BlockKind::STONE
+                && next.kind != BlockKind::BOMB
+                && !divides_by_zero
+                && remaining.iter().any(|number| {
+                    let result = self.rules.calculate(*number, next.operation, next.number);
+                    target.is_target(result, self.level)
+                })
+            {
+                value += SETUP_VALUE;
+            }
+        }
+        value
+    }
+
+    /// Returns the value, column and color of each reachable placement for the block dropping
+    /// at `from`
+    fn candidates(&self, block: &BlockParams, from: &Coords) -> Vec<(f32, i32, BlockColor)> {
+        let colors = match block.kind {
+            BlockKind::NORMAL | BlockKind::BOMB => vec![
+                BlockColor::BLUE,
+                BlockColor::YELLOW,
+                BlockColor::PINK,
+                BlockColor::GREEN,
+            ],
+            BlockKind::WILDCARD | BlockKind::STONE => vec![block.color],
+        };
+
+        let mut candidates = Vec::new();
+        for column in 0..self.block_map.size().width as i32 {
+            if let Some(pos) = landing_position(self.block_map, from, column) {
+                for color in colors.iter() {
+                    candidates.push((self.evaluate(block, *color, &pos), column, *color));
+                }
+            }
+        }
+
+        candidates
+    }
+
+    /// Returns the column and color of the best placement for the block dropping at `from`.
+    /// Returns `None` if no column can be reached.
+    pub fn best_placement(&self, block: &BlockParams, from: &Coords) -> Option<(i32, BlockColor)> {
+        self.candidates(block, from)
+            .iter()
+            .max_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal))
+            .map(|(_, column, color)| (*column, *color))
+    }
+
+    /// Chooses the column and color for the block dropping at `from`. The AI makes mistakes
+    /// every now and then depending on its skill level.
+    pub fn choose_placement(
+        &self,
+        block: &BlockParams,
+        from: &Coords,
+        skill: AiSkill,
+        rng: &mut StdRng,
+    ) -> Option<(i32, BlockColor)> {
+        if rng.gen::<f32>() < skill.mistake_chance() {
+            self.candidates(block, from)
+                .choose(rng)
+                .map(|(_, column, color)| (*column, *color))
+        } else {
+            self.best_placement(block, from)
+        }
+    }
+}
+
+/// Actions which the AI presses only for a single frame
+const TAP_ACTIONS: [PlayerAction; 7] = [
+    PlayerAction::MoveLeft,
+    PlayerAction::MoveRight,
+    PlayerAction::SwitchColor,
+    PlayerAction::SelectColor(BlockColor::BLUE),
+    PlayerAction::SelectColor(BlockColor::YELLOW),
+    PlayerAction::SelectColor(BlockColor::PINK),
+    PlayerAction::SelectColor(BlockColor::GREEN),
+];
+
+/// Releases all the actions pressed by the AI
+fn release_actions(actions: &mut Input<PlayerAction>) {
+    for action in TAP_ACTIONS {
+        actions.release(action);
+    }
+    actions.release(PlayerAction::SoftDrop);
+}
+
+/// System for controlling the dropping block by the AI
+fn play(
+    time: Res<Time>,
+    mut ai: ResMut<AiPlayer>,
+    mut actions: ResMut<PerBoard<Input<PlayerAction>>>,
+    block_maps: Res<PerBoard<BlockMap>>,
+    rules: Res<GameRules>,
+    levels: Res<PerBoard<Level>>,
+    next_blocks: Res<PerBoard<NextBlock>>,
+    dropping_query: Query<
+        (
+            Entity,
+            &BlockPosition,
+            &Number,
+            &BlockColor,
+            &BlockKind,
+            &Operation,
+            &BoardId,
+        ),
+        With<DroppingBlock>,
+    >,
+    solid_query: Query<(Entity, &Number, &BoardId), With<SolidBlock>>,
+) {
+    if !ai.enabled {
+        return;
+    }
+
+    let board = ai.board;
+    let actions = &mut actions[board];
+    let block_map = &block_maps[board];
+    for action in TAP_ACTIONS {
+        actions.release(action);
+    }
+
+    let dropping_block = dropping_query
+        .iter()
+        .find(|(.., dropping_board)| dropping_board.0 == board);
+    if let Some((entity, pos, number, color, kind, operation, _)) = dropping_block {
+        let block = BlockParams {
+            number: number.0,
+            color: *color,
+            operation: *operation,
+            kind: *kind,
+        };
+
+        // Plan the placement once for each block, or again if the planned column got blocked
+        let replan = match &ai.plan {
+            Some(plan) => {
+                plan.block != entity || landing_position(block_map, &pos.0, plan.column).is_none()
+            }
+            None => true,
+        };
+        if replan {
+            let numbers: HashMap<Entity, Fraction> = solid_query
+                .iter()
+                .filter(|(.., solid_board)| solid_board.0 == board)
+                .map(|(entity, number, _)| (entity, number.0))
+                .collect();
+            let next = if ai.skill.uses_next_block() {
+                next_blocks[board].0.as_ref()
+            } else {
+                None
+            };
+            let situation = Situation {
+                block_map,
+                numbers: &numbers,
+                rules: &rules,
+                level: levels[board].number,
+                next,
+            };
+            let skill = ai.skill;
+            let is_new_block = ai.plan.as_ref().map(|p| p.block) != Some(entity);
+            ai.plan = situation
+                .choose_placement(&block, &pos.0, skill, &mut ai.rng)
+                .map(|(column, color)| Plan {
+                    block: entity,
+                    column,
+                    color,
+                });
+
+            // Give the AI a moment to react to the new block
+            if is_new_block {
+                ai.drops += 1;
+                ai.action_timer = Timer::from_seconds(skill.action_interval(), false);
+            }
+        }
+
+        let (column, planned_color) = match &ai.plan {
+            Some(plan) => (plan.column, plan.color),
+            None => {
+                actions.release(PlayerAction::SoftDrop);
+                return;
+            }
+        };
+
+        let in_place = pos.0.x == column && *color == planned_color;
+        if in_place && ai.skill.soft_drops() {
+            actions.press(PlayerAction::SoftDrop);
+        } else {
+            actions.release(PlayerAction::SoftDrop);
+        }
+
+        if !ai.action_timer.tick(time.delta()).finished() || in_place {
+            return;
+        }
+        if *color != planned_color {
+            actions.press(PlayerAction::SelectColor(planned_color));
+        } else if pos.0.x < column {
+            actions.press(PlayerAction::MoveRight);
+        } else if pos.0.x > column {
+            actions.press(PlayerAction::MoveLeft);
+        }
+        ai.action_timer.reset();
+    }
+}
+
+/// System for toggling the autopilot with F1 and changing its skill level with F2. Autopilot is
+/// available only in a single player game.
+fn toggle_autopilot(
+    input: Res<Input<KeyCode>>,
+    mut ai: ResMut<AiPlayer>,
+    mut actions: ResMut<PerBoard<Input<PlayerAction>>>,
+    mut toast_events: EventWriter<ShowToastEvent>,
+    versus: Res<Versus>,
+    language: Res<Language>,
+) {
+    if ai.demo {
+        return;
+    }
+
+    if input.just_pressed(KeyCode::F1) && *versus == Versus::Off {
+        ai.enabled = !ai.enabled;
+        ai.plan = None;
+        release_actions(&mut actions[0]);
+        if ai.enabled {
+            ai.assisted = true;
+        }
+        toast_events.send(ShowToastEvent(language.get(if ai.enabled {
+            TextKey::AutopilotOn
+        } else {
+            TextKey::AutopilotOff
+        })));
+    } else if input.just_pressed(KeyCode::F2) {
+        ai.skill = ai.skill.next();
+        toast_events.send(ShowToastEvent(language.format(
+            TextKey::AutopilotSkill,
+            &[&language.get(TextKey::AiSkill(ai.skill))],
+        )));
+    }
+}
+
+/// Called once as the game is started. AI plays the right board in a versus game against it.
+fn on_game_start(mut ai: ResMut<AiPlayer>, versus: Res<Versus>) {
+    match *versus {
+        Versus::Off => ai.board = 0,
+        Versus::TwoPlayers | Versus::Network => ai.enabled = false,
+        Versus::Computer => {
+            ai.enabled = true;
+            ai.board = 1;
+        }
+    }
+    ai.assisted = ai.enabled && *versus == Versus::Off;
+    ai.plan = None;
+    ai.drops = 0;
+}
+
+/// Called once after game has ended
+fn on_game_end(mut ai: ResMut<AiPlayer>, mut actions: ResMut<PerBoard<Input<PlayerAction>>>) {
+    ai.plan = None;
+    for actions in actions.0.iter_mut() {
+        release_actions(actions);
+    }
+}
+
+/// System for starting a demo game once the main menu has been idle long enough
+fn start_attract_mode(
+    time: Res<Time>,
+    input: Res<Input<KeyCode>>,
+    actions: Res<Input<MenuAction>>,
+    mut timer: ResMut<AttractTimer>,
+    mut ai: ResMut<AiPlayer>,
+    mut game_state: ResMut<State<GameState>>,
+    versus: Res<Versus>,
+) {
+    // Demo game is a single player game
+    if input.get_just_pressed().next().is_some()
+        || actions.get_just_pressed().next().is_some()
+        || *versus != Versus::Off
+    {
+        timer.0.reset();
+    }
+
+    if timer.0.tick(time.delta()).just_finished() {
+        ai.enabled = true;
+        ai.demo = true;
+        game_state
+            .set(GameState::InGame)
+            .expect("Failed to change GameState::InGame");
+    }
+}
+
+/// Called once when returning to the main menu. Ends the demo game and the versus game against
+/// the AI.
+fn reset_attract_mode(
+    mut timer: ResMut<AttractTimer>,
+    mut demo_timer: ResMut<DemoGameOverTimer>,
+    mut ai: ResMut<AiPlayer>,
+) {
+    timer.0.reset();
+    demo_timer.0.reset();
+    if ai.demo || ai.board != 0 {
+        ai.demo = false;
+        ai.enabled = false;
+        ai.board = 0;
+    }
+}
+
+/// System for returning to the main menu from a demo game on any key or gamepad button press
+fn exit_demo_on_key(
+    mut commands: Commands,
+    mut input: ResMut<Input<KeyCode>>,
+    buttons: Res<Input<GamepadButton>>,
+    ai: Res<AiPlayer>,
+    mut game_state: ResMut<State<GameState>>,
+    query: Query<Entity, With<GameObject>>,
+) {
+    let in_game = *game_state.current() == GameState::InGame;
+    let pressed =
+        input.get_just_pressed().next().is_some() || buttons.get_just_pressed().next().is_some();
+    if ai.demo && in_game && pressed {
+        for entity in query.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+        game_state
+            .overwrite_set(GameState::Menu)
+            .expect("Failed to change GameState::Menu");
+        input.clear();
+    }
+}
+
+/// System for returning to the main menu after the game over screen of a demo game
+fn demo_back_to_menu(
+    time: Res<Time>,
+    ai: Res<AiPlayer>,
+    mut timer: ResMut<DemoGameOverTimer>,
+    mut game_state: ResMut<State<GameState>>,
+) {
+    if !ai.demo || *game_state.current() != GameState::GameOver {
+        return;
+    }
+    if timer.0.tick(time.delta()).just_finished() {
+        game_state
+            .overwrite_set(GameState::Menu)
+            .expect("Failed to change GameState::Menu");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::headless::HeadlessGame;
+    use crate::rules::GameMode;
+    use std::collections::BTreeMap;
+
+    /// Number of games played by the benchmark for each skill level and game mode
+    const BENCHMARK_GAMES: u64 = 10;
+
+    /// Longest game played by the benchmark (in frames). Zen mode games may never end.
+    const MAX_FRAMES: u32 = 60 * 60 * 20;
+
+    /// Result of a game played by the AI
+    #[derive(PartialEq, Eq, Debug)]
+    struct GameResult {
+        score: i32,
+        level: u32,
+        drops: u32,
+        frames: u32,
+    }
+
+    /// Plays a seeded game with the AI until the game is over or `max_frames` have been played
+    fn play_game(seed: u64, skill: AiSkill, mode: GameMode, max_frames: u32) -> GameResult {
+        let mut game = HeadlessGame::new(seed, mode, Versus::Off);
+        let mut ai = game.app.world.resource_mut::<AiPlayer>();
+        ai.enabled = true;
+        ai.skill = skill;
+        ai.rng = StdRng::seed_from_u64(seed);
+
+        let mut frames = 0;
+        while frames < max_frames && !game.is_over() {
+            game.update();
+            frames += 1;
+        }
+
+        let world = &game.app.world;
+        GameResult {
+            score: world.resource::<PerBoard<Score>>()[0].0,
+            level: world.resource::<PerBoard<Level>>()[0].number,
+            drops: world.resource::<AiPlayer>().drops,
+            frames,
+        }
+    }
+
+    #[test]
+    fn seeded_game_is_repeatable() {
+        let first = play_game(7, AiSkill::Easy, GameMode::Advanced, 60 * 60);
+        let second = play_game(7, AiSkill::Easy, GameMode::Advanced, 60 * 60);
+        assert!(first.drops > 0);
+        assert_eq!(first, second);
+    }
+
+    /// Plays `BENCHMARK_GAMES` seeded games with each skill level in each game mode and prints
+    /// the distribution of the scores and levels. Run with
+    /// `cargo test --release benchmark -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn benchmark() {
+        for mode in GameMode::ALL {
+            for skill in AiSkill::ALL {
+                let results: Vec<GameResult> = (0..BENCHMARK_GAMES)
+                    .map(|seed| play_game(seed, skill, mode, MAX_FRAMES))
+                    .collect();
+
+                let mut scores: Vec<i32> = results.iter().map(|result| result.score).collect();
+                scores.sort_unstable();
+                let mut levels = BTreeMap::new();
+                for result in results.iter() {
+                    *levels.entry(result.level).or_insert(0) += 1;
+                }
+                let unfinished = results
+                    .iter()
+                    .filter(|result| result.frames == MAX_FRAMES)
+                    .count();
+
+                println!(
+                    "{} / {}: score min {}, median {}, max {}, mean {:.0}; levels {:?}; \
+                     unfinished {}/{}",
+                    mode.name(),
+                    skill.name(),
+                    scores[0],
+                    scores[scores.len() / 2],
+                    scores[scores.len() - 1],
+                    scores.iter().sum::<i32>() as f32 / scores.len() as f32,
+                    levels,
+                    unfinished,
+                    results.len()
+                );
+            }
+        }
+    }
+}
@@ -1,135 +1,640 @@
-use bevy::audio::{Audio, AudioSink};
+use crate::localization::{Language, TextKey};
+use crate::prelude::*;
+use crate::synth::{Note, Tone, Waveform};
+use crate::toast::ShowToastEvent;
+use bevy::asset::{AssetLoader, LoadContext, LoadedAsset};
+use bevy::audio::{play_queued_audio_system, Audio, AudioOutput, AudioSink};
+use bevy::ecs::schedule::ShouldRun;
 use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy::time::Stopwatch;
+use bevy::utils::BoxedFuture;
+use rand::seq::SliceRandom;
+use std::collections::HashMap;
+use std::mem::Discriminant;
+
+/// Background music volume
+const MUSIC_VOLUME: f32 = 0.8;
+
+/// Duration of the crossfade between two tracks (in seconds)
+const CROSSFADE_TIME: f32 = 3.0;
+
+/// Music playback speed-up at the highest intensity
+const MAX_MUSIC_SPEEDUP: f32 = 0.15;
+
+/// Music volume multiplier while ducked
+const DUCK_VOLUME: f32 = 0.4;
+
+/// Time it takes for the music to recover from ducking (in seconds)
+const DUCK_TIME: f32 = 0.8;
+
+/// Path to the music playlist manifest
+const PLAYLIST_PATH: &str = "sounds/music.playlist";
+
+/// This resource holds the music and sound effect volumes (in percents) set in the options
+#[derive(Copy, Clone)]
+pub struct Volumes {
+    pub music: u32,
+    pub sfx: u32,
+}
+
+impl Default for Volumes {
+    fn default() -> Self {
+        Volumes {
+            music: 100,
+            sfx: 100,
+        }
+    }
+}
 
 /// Enum for PlaySfxEvent
 pub enum Sfx {
     BlockDropped,
     BlocksCleared,
+    BlockMoved,
+    MoveBlocked,
+    ColorSwitched,
+    /// Block number changed without reaching the target. Value tells how close (0.0 - 1.0) the
+    /// result is to the target.
+    Combination(f32),
+    /// Consecutive drop which cleared blocks
+    ChainStep(u32),
+    LevelUp,
+    Danger,
+    GameOver,
+    /// Focus moved or a value changed in a menu
+    MenuMove,
+    /// Menu entry selected
+    MenuSelect,
 }
 
 /// Event for playing sfx
 pub struct PlaySfxEvent(pub Sfx);
 
+/// Event for changing the background music
+pub enum MusicEvent {
+    /// Crossfade into the track with given index in the playlist
+    Play(usize),
+    /// Fade out the background music
+    Stop,
+}
+
+/// Resource for controlling how intense the background music is. Value ranges from 0.0 (calm)
+/// to 1.0 (intense) and it's set by the gameplay.
+#[derive(Default)]
+pub struct MusicIntensity(pub f32);
+
+/// Information about a single track in the playlist manifest
+#[derive(Clone)]
+pub struct TrackInfo {
+    pub path: String,
+    pub title: String,
+    pub artist: String,
+    /// Time the track is played before advancing to the next one (in seconds)
+    pub play_time: f32,
+}
+
+/// Music playlist manifest loaded from `*.playlist` file.
+///
+/// Each non-empty line which doesn't start with `#` defines one track:
+/// `path | title | artist | play time in seconds`
+#[derive(TypeUuid)]
+#[uuid = "4f3b7c2a-9d51-4e8a-b6f0-1c2d3e4f5a6b"]
+pub struct PlaylistManifest {
+    pub tracks: Vec<TrackInfo>,
+}
+
+impl PlaylistManifest {
+    /// Parses the manifest from its text representation
+    fn parse(text: &str) -> Result<PlaylistManifest, String> {
+        let mut tracks = Vec::new();
+        for (line_num, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let fields: Vec<&str> = line.split('|').map(|field| field.trim()).collect();
+            if fields.len() != 4 {
+                return Err(format!("Invalid playlist entry on line {}", line_num + 1));
+            }
+            let play_time = fields[3]
+                .parse::<f32>()
+                .map_err(|_| format!("Invalid play time on line {}", line_num + 1))?;
+
+            tracks.push(TrackInfo {
+                path: fields[0].to_string(),
+                title: fields[1].to_string(),
+                artist: fields[2].to_string(),
+                play_time,
+            });
+        }
+        Ok(PlaylistManifest { tracks })
+    }
+}
+
+/// Asset loader for the `*.playlist` files
+#[derive(Default)]
+struct PlaylistLoader;
+impl AssetLoader for PlaylistLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let manifest = PlaylistManifest::parse(std::str::from_utf8(bytes)?)
+                .map_err(bevy::asset::Error::msg)?;
+            load_context.set_default_asset(LoadedAsset::new(manifest));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["playlist"]
+    }
+}
+
+/// Defines what happens once the playlist has reached its end
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum RepeatMode {
+    /// Start again from the beginning
+    All,
+    /// Keep looping the current track
+    One,
+    /// Fade out after the last track
+    Off,
+}
+
+/// Holds the state of the background music playlist
+struct Playlist {
+    manifest: Handle<PlaylistManifest>,
+    tracks: Vec<(TrackInfo, Handle<AudioSource>)>,
+    /// Playing order as indices into `tracks`
+    order: Vec<usize>,
+    /// Current position in `order`
+    position: usize,
+    shuffle: bool,
+    repeat: RepeatMode,
+    playing: bool,
+    /// Time the current track has been playing
+    play_time: Stopwatch,
+}
+
+impl Default for Playlist {
+    fn default() -> Self {
+        Playlist {
+            manifest: Handle::default(),
+            tracks: Vec::new(),
+            order: Vec::new(),
+            position: 0,
+            shuffle: false,
+            repeat: RepeatMode::All,
+            playing: false,
+            play_time: Stopwatch::new(),
+        }
+    }
+}
+
+impl Playlist {
+    /// Returns the index of the current track
+    fn current(&self) -> usize {
+        self.order[self.position]
+    }
+
+    /// Moves the position to given track
+    fn select(&mut self, track: usize) {
+        if let Some(position) = self.order.iter().position(|t| *t == track) {
+            self.position = position;
+        }
+    }
+
+    /// Steps to the next track in the playing order. Returns `None` if the end was reached and
+    /// `wrap` is not set.
+    fn step(&mut self, wrap: bool) -> Option<usize> {
+        if self.position + 1 < self.order.len() {
+            self.position += 1;
+        } else if wrap {
+            if self.shuffle {
+                self.order.shuffle(&mut rand::thread_rng());
+            }
+            self.position = 0;
+        } else {
+            return None;
+        }
+        Some(self.current())
+    }
+
+    /// Turns shuffle on/off. Current track stays as the current track.
+    fn set_shuffle(&mut self, shuffle: bool) {
+        let current = self.current();
+        self.shuffle = shuffle;
+        self.order = (0..self.tracks.len()).collect();
+        if shuffle {
+            self.order.shuffle(&mut rand::thread_rng());
+            self.order.retain(|t| *t != current);
+            self.order.insert(0, current);
+        }
+        self.select(current);
+    }
+}
+
 /// Holds handles to game music and sfx samples
 #[derive(Default, Clone)]
 struct AudioResources {
     current_track: Handle<AudioSink>,
-    tracks: Vec<Handle<AudioSource>>,
+    /// Previous track which is being faded out
+    fading_track: Handle<AudioSink>,
+    crossfade: Timer,
+    /// Music is ducked (volume lowered) until this timer finishes
+    duck: Timer,
+    /// Current (smoothed) playback speed of the music
+    music_speed: f32,
+    muted: bool,
     sfx_drop: Handle<AudioSource>,
     sfx_clear: Handle<AudioSource>,
+    sfx_move: Handle<Tone>,
+    sfx_blocked: Handle<Tone>,
+    sfx_level_up: Handle<Tone>,
+    sfx_danger: Handle<Tone>,
 }
 
+/// Sample of a sound effect, either loaded from a file or synthesized
+enum SfxSample {
+    File(Handle<AudioSource>),
+    Synth(Handle<Tone>),
+}
+
+/// Holds the time (seconds since startup) each sound effect was last played
+#[derive(Default)]
+struct SfxCooldowns(HashMap<Discriminant<Sfx>, f64>);
+
+/// Systems handling the audio hotkeys are labeled with this. Systems taking keys as text or as
+/// key bindings should be run before it and consume the keys they use.
+#[derive(SystemLabel, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct AudioHotkeysLabel;
+
 /// Bevy Plugin for handling music and sfx in the game
 pub struct AudioPlugin;
 impl Plugin for AudioPlugin {
     fn build(&self, app: &mut App) {
-        app.add_startup_system(setup)
-            .add_system(mute_on_m_key)
+        app.add_asset::<PlaylistManifest>()
+            .init_asset_loader::<PlaylistLoader>()
+            .add_asset::<Tone>()
+            .init_resource::<Audio<Tone>>()
+            .init_non_send_resource::<AudioOutput<Tone>>()
+            .add_system_to_stage(CoreStage::PostUpdate, play_queued_audio_system::<Tone>)
+            .add_startup_system(setup)
+            .add_system(init_playlist)
+            .add_system_set(
+                SystemSet::new()
+                    .label(AudioHotkeysLabel)
+                    .with_run_criteria(hotkeys_enabled)
+                    .with_system(mute_on_m_key)
+                    .with_system(playlist_controls),
+            )
+            .add_system(advance_playlist)
             .add_system(change_background_track)
+            .add_system(update_music_volume)
             .add_system(play_sfx)
             .insert_resource(AudioResources::default())
-            .add_event::<PlaySfxEvent>();
+            .insert_resource(Playlist::default())
+            .insert_resource(SfxCooldowns::default())
+            .insert_resource(MusicIntensity::default())
+            .add_event::<PlaySfxEvent>()
+            .add_event::<MusicEvent>();
     }
 }
 
 /// Startup system for loading and preparing audio assets
 fn setup(
     asset_server: Res<AssetServer>,
-    audio: Res<Audio>,
+    mut tones: ResMut<Assets<Tone>>,
     mut audio_res: ResMut<AudioResources>,
-    sinks: Res<Assets<AudioSink>>,
+    mut playlist: ResMut<Playlist>,
 ) {
     // Load sound effects
     audio_res.sfx_drop = asset_server.load("sounds/drop.ogg");
     audio_res.sfx_clear = asset_server.load("sounds/clear.ogg");
 
-    // Load background music
-    let track1 = asset_server.load("sounds/pixel-drama.ogg");
-    audio_res.tracks.push(track1.clone());
+    // Synthesize the sound effects which have no sample
+    let note = |from, to, duration| Note { from, to, duration };
+    audio_res.sfx_move = tones.add(Tone::new(Waveform::Triangle, &[note(1200.0, 1000.0, 0.03)]));
+    audio_res.sfx_blocked = tones.add(Tone::new(Waveform::Square, &[note(140.0, 90.0, 0.1)]));
+    audio_res.sfx_level_up = tones.add(Tone::new(
+        Waveform::Square,
+        &[
+            note(523.0, 523.0, 0.08),
+            note(659.0, 659.0, 0.08),
+            note(784.0, 784.0, 0.08),
+            note(1047.0, 1047.0, 0.2),
+        ],
+    ));
+    audio_res.sfx_danger = tones.add(Tone::new(
+        Waveform::Sine,
+        &[note(880.0, 660.0, 0.2), note(880.0, 660.0, 0.2)],
+    ));
 
-    let track2 = asset_server.load("sounds/the-triumph-of-the-clockmaker.ogg");
-    audio_res.tracks.push(track2);
+    audio_res.crossfade = Timer::from_seconds(CROSSFADE_TIME, false);
+    audio_res.music_speed = 1.0;
 
-    let track3 = asset_server.load("sounds/chamber-of-jewels.ogg");
-    audio_res.tracks.push(track3);
+    // Ducking timer starts as finished
+    audio_res.duck = Timer::from_seconds(DUCK_TIME, false);
+    audio_res
+        .duck
+        .tick(std::time::Duration::from_secs_f32(DUCK_TIME));
 
-    // play function returns weak handle which needs to be changed into strong handle which then
-    // can be used for controlling the playback
-    let track1_weak_handle =
-        audio.play_with_settings(track1, PlaybackSettings::LOOP.with_volume(0.8));
-    audio_res.current_track = sinks.get_handle(track1_weak_handle);
+    // Background music tracks are listed in the playlist manifest
+    playlist.manifest = asset_server.load(PLAYLIST_PATH);
+}
+
+/// Loads the tracks and starts the music once the playlist manifest is available
+fn init_playlist(
+    asset_server: Res<AssetServer>,
+    manifests: Res<Assets<PlaylistManifest>>,
+    mut playlist: ResMut<Playlist>,
+    mut music_events: EventWriter<MusicEvent>,
+) {
+    if !playlist.tracks.is_empty() {
+        return;
+    }
+
+    if let Some(manifest) = manifests.get(&playlist.manifest) {
+        playlist.tracks = manifest
+            .tracks
+            .iter()
+            .map(|track| (track.clone(), asset_server.load(track.path.as_str())))
+            .collect();
+        playlist.order = (0..playlist.tracks.len()).collect();
+
+        if !playlist.tracks.is_empty() {
+            music_events.send(MusicEvent::Play(0));
+        }
+    }
 }
 
 /// Toggle mute with M key
-fn mute_on_m_key(
+/// Run criteria for the audio hotkeys. In the lobby the keys are typed into the address of the
+/// host instead.
+fn hotkeys_enabled(state: Res<State<GameState>>) -> ShouldRun {
+    if *state.current() == GameState::Lobby {
+        ShouldRun::No
+    } else {
+        ShouldRun::Yes
+    }
+}
+
+fn mute_on_m_key(input: Res<Input<KeyCode>>, mut audio_res: ResMut<AudioResources>) {
+    if input.just_pressed(KeyCode::M) {
+        audio_res.muted = !audio_res.muted;
+    }
+}
+
+/// Handle the playlist keys: number keys select the track, N skips to the next track,
+/// S toggles shuffle and T changes the repeat mode
+fn playlist_controls(
     input: Res<Input<KeyCode>>,
-    sinks: Res<Assets<AudioSink>>,
-    audio_res: Res<AudioResources>,
+    mut playlist: ResMut<Playlist>,
+    mut music_events: EventWriter<MusicEvent>,
+    mut toast_events: EventWriter<ShowToastEvent>,
+    language: Res<Language>,
 ) {
-    if input.just_pressed(KeyCode::M) {
-        if let Some(sink) = sinks.get(&audio_res.current_track) {
-            if sink.is_paused() {
-                sink.play();
-            } else {
-                sink.pause();
-            }
+    if playlist.tracks.is_empty() {
+        return;
+    }
+
+    let track_keys = [
+        KeyCode::Key1,
+        KeyCode::Key2,
+        KeyCode::Key3,
+        KeyCode::Key4,
+        KeyCode::Key5,
+        KeyCode::Key6,
+        KeyCode::Key7,
+        KeyCode::Key8,
+        KeyCode::Key9,
+    ];
+    for (track, key) in track_keys.iter().enumerate() {
+        if input.just_pressed(*key) && track < playlist.tracks.len() {
+            playlist.select(track);
+            music_events.send(MusicEvent::Play(track));
+        }
+    }
+
+    if input.just_pressed(KeyCode::N) {
+        if let Some(track) = playlist.step(true) {
+            music_events.send(MusicEvent::Play(track));
         }
     }
+
+    if input.just_pressed(KeyCode::S) {
+        let shuffle = !playlist.shuffle;
+        playlist.set_shuffle(shuffle);
+        toast_events.send(ShowToastEvent(language.get(if shuffle {
+            TextKey::ShuffleOn
+        } else {
+            TextKey::ShuffleOff
+        })));
+    }
+
+    if input.just_pressed(KeyCode::T) {
+        playlist.repeat = match playlist.repeat {
+            RepeatMode::All => RepeatMode::One,
+            RepeatMode::One => RepeatMode::Off,
+            RepeatMode::Off => RepeatMode::All,
+        };
+        toast_events.send(ShowToastEvent(language.get(match playlist.repeat {
+            RepeatMode::All => TextKey::RepeatAll,
+            RepeatMode::One => TextKey::RepeatOne,
+            RepeatMode::Off => TextKey::RepeatOff,
+        })));
+    }
+}
+
+/// Advance to the next track once the current track has been played long enough
+fn advance_playlist(
+    time: Res<Time>,
+    audio_res: Res<AudioResources>,
+    mut playlist: ResMut<Playlist>,
+    mut music_events: EventWriter<MusicEvent>,
+) {
+    if !playlist.playing || audio_res.muted {
+        return;
+    }
+
+    // Start crossfading so that the next track is fully audible once the play time is up
+    let play_time = playlist.tracks[playlist.current()].0.play_time;
+    let elapsed = playlist.play_time.tick(time.delta()).elapsed_secs();
+    if elapsed < (play_time - CROSSFADE_TIME).max(CROSSFADE_TIME) {
+        return;
+    }
+
+    let repeat = playlist.repeat;
+    if repeat == RepeatMode::One {
+        // Tracks are looping anyway
+        playlist.play_time.reset();
+    } else if let Some(track) = playlist.step(repeat == RepeatMode::All) {
+        music_events.send(MusicEvent::Play(track));
+    } else {
+        music_events.send(MusicEvent::Stop);
+    }
 }
 
-/// Change the background track
+/// Change the background track by starting to crossfade from the current track into the new one
 fn change_background_track(
-    input: Res<Input<KeyCode>>,
     audio: Res<Audio>,
     sinks: Res<Assets<AudioSink>>,
     mut audio_res: ResMut<AudioResources>,
+    mut playlist: ResMut<Playlist>,
+    mut music_events: EventReader<MusicEvent>,
+    mut toast_events: EventWriter<ShowToastEvent>,
+    language: Res<Language>,
 ) {
-    if input.any_just_pressed([KeyCode::Key1, KeyCode::Key2, KeyCode::Key3]) {
-        if let Some(sink) = sinks.get(&audio_res.current_track) {
+    for ev in music_events.iter() {
+        // Track which is still fading out from the previous change is cut off immediately
+        if let Some(sink) = sinks.get(&audio_res.fading_track) {
             sink.pause();
         }
+        audio_res.fading_track = std::mem::take(&mut audio_res.current_track);
+        audio_res.crossfade.reset();
+        playlist.play_time.reset();
+
+        match ev {
+            MusicEvent::Play(track) => {
+                if let Some((info, source)) = playlist.tracks.get(*track) {
+                    // play function returns weak handle which needs to be changed into strong
+                    // handle which then can be used for controlling the playback
+                    let weak_handle = audio.play_with_settings(
+                        source.clone(),
+                        PlaybackSettings::LOOP.with_volume(0.0),
+                    );
+                    audio_res.current_track = sinks.get_handle(weak_handle);
 
-        if input.just_pressed(KeyCode::Key1) {
-            let track1_weak_handle = audio.play_with_settings(
-                audio_res.tracks.get(0).unwrap().clone(),
-                PlaybackSettings::LOOP.with_volume(0.8),
-            );
-            audio_res.current_track = sinks.get_handle(track1_weak_handle);
+                    toast_events.send(ShowToastEvent(format!(
+                        "{}\n{}\n{}",
+                        language.get(TextKey::NowPlaying),
+                        info.title,
+                        info.artist
+                    )));
+                }
+                playlist.playing = true;
+            }
+            MusicEvent::Stop => {
+                playlist.playing = false;
+            }
         }
-        if input.just_pressed(KeyCode::Key2) {
-            let track2_weak_handle = audio.play_with_settings(
-                audio_res.tracks.get(1).unwrap().clone(),
-                PlaybackSettings::LOOP.with_volume(0.8),
-            );
-            audio_res.current_track = sinks.get_handle(track2_weak_handle);
+    }
+}
+
+/// Updates the volume and speed of the music sinks according to the crossfade progress,
+/// ducking, music intensity and mute state
+fn update_music_volume(
+    time: Res<Time>,
+    sinks: Res<Assets<AudioSink>>,
+    intensity: Res<MusicIntensity>,
+    volumes: Res<Volumes>,
+    mut audio_res: ResMut<AudioResources>,
+) {
+    if !audio_res.muted {
+        audio_res.crossfade.tick(time.delta());
+    }
+    let fade = audio_res.crossfade.percent();
+
+    // Volume is lowered immediately when ducked and then it recovers gradually
+    audio_res.duck.tick(time.delta());
+    let volume = MUSIC_VOLUME
+        * (volumes.music as f32 / 100.0)
+        * (DUCK_VOLUME + (1.0 - DUCK_VOLUME) * audio_res.duck.percent());
+
+    // Speed follows the intensity smoothly instead of jumping
+    let target_speed = 1.0 + MAX_MUSIC_SPEEDUP * intensity.0.clamp(0.0, 1.0);
+    let speed = audio_res.music_speed
+        + (target_speed - audio_res.music_speed) * (time.delta_seconds() * 2.0).min(1.0);
+    audio_res.music_speed = speed;
+
+    if let Some(sink) = sinks.get(&audio_res.current_track) {
+        if audio_res.muted {
+            sink.pause();
+        } else {
+            sink.set_volume(volume * fade);
+            sink.set_speed(speed);
+            sink.play();
         }
-        if input.just_pressed(KeyCode::Key3) {
-            let track3_weak_handle = audio.play_with_settings(
-                audio_res.tracks.get(2).unwrap().clone(),
-                PlaybackSettings::LOOP.with_volume(0.8),
-            );
-            audio_res.current_track = sinks.get_handle(track3_weak_handle);
+    }
+
+    if let Some(sink) = sinks.get(&audio_res.fading_track) {
+        if audio_res.muted || audio_res.crossfade.finished() {
+            sink.pause();
+        } else {
+            sink.set_volume(volume * (1.0 - fade));
+            sink.set_speed(speed);
+            sink.play();
         }
     }
+    if audio_res.crossfade.finished() {
+        audio_res.fading_track = Handle::default();
+    }
 }
 
-/// Receives PlaySfx events and plays the sample
+/// Receives PlaySfx events and plays the sample. Same sound effect is not played again until
+/// its cooldown has passed. Music is ducked whenever blocks are cleared.
 fn play_sfx(
+    time: Res<Time>,
     mut events: EventReader<PlaySfxEvent>,
-    audio_samples: Res<AudioResources>,
+    mut audio_samples: ResMut<AudioResources>,
     audio: Res<Audio>,
+    synth_audio: Res<Audio<Tone>>,
+    mut cooldowns: ResMut<SfxCooldowns>,
+    volumes: Res<Volumes>,
 ) {
     for ev in events.iter() {
-        match &ev.0 {
-            Sfx::BlockDropped => {
-                audio.play(audio_samples.sfx_drop.clone());
-            }
-            Sfx::BlocksCleared => {
-                audio.play_with_settings(
-                    audio_samples.sfx_clear.clone(),
-                    PlaybackSettings::ONCE.with_volume(0.6),
-                );
+        // Sample, volume, speed (pitch) and cooldown (in seconds) for each sound effect
+        let file = |sample: &Handle<AudioSource>| SfxSample::File(sample.clone());
+        let synth = |sample: &Handle<Tone>| SfxSample::Synth(sample.clone());
+        let (sample, volume, speed, cooldown) = match &ev.0 {
+            Sfx::BlockDropped => (file(&audio_samples.sfx_drop), 1.0, 1.0, 0.0),
+            Sfx::BlocksCleared => (file(&audio_samples.sfx_clear), 0.6, 1.0, 0.05),
+            Sfx::BlockMoved => (synth(&audio_samples.sfx_move), 0.3, 1.0, 0.05),
+            Sfx::MoveBlocked => (synth(&audio_samples.sfx_blocked), 0.4, 1.0, 0.2),
+            Sfx::ColorSwitched => (file(&audio_samples.sfx_clear), 0.25, 2.0, 0.05),
+            Sfx::Combination(closeness) => (
+                file(&audio_samples.sfx_clear),
+                0.35,
+                0.8 + 0.6 * closeness,
+                0.05,
+            ),
+            Sfx::ChainStep(step) => (
+                file(&audio_samples.sfx_clear),
+                0.5,
+                1.0 + 0.1 * (*step).min(10) as f32,
+                0.0,
+            ),
+            Sfx::LevelUp => (synth(&audio_samples.sfx_level_up), 0.5, 1.0, 0.0),
+            Sfx::Danger => (synth(&audio_samples.sfx_danger), 0.4, 1.0, 2.0),
+            Sfx::GameOver => (file(&audio_samples.sfx_clear), 0.8, 0.5, 0.0),
+            Sfx::MenuMove => (file(&audio_samples.sfx_drop), 0.3, 1.6, 0.05),
+            Sfx::MenuSelect => (file(&audio_samples.sfx_clear), 0.4, 1.8, 0.1),
+        };
+
+        let now = time.seconds_since_startup();
+        let key = std::mem::discriminant(&ev.0);
+        if let Some(last_played) = cooldowns.0.get(&key) {
+            if now - last_played < cooldown {
+                continue;
             }
         }
+        cooldowns.0.insert(key, now);
+
+        let settings = PlaybackSettings::ONCE
+            .with_volume(volume * volumes.sfx as f32 / 100.0)
+            .with_speed(speed);
+        match sample {
+            SfxSample::File(sample) => audio.play_with_settings(sample, settings),
+            SfxSample::Synth(sample) => synth_audio.play_with_settings(sample, settings),
+        };
+
+        if let Sfx::BlocksCleared = ev.0 {
+            audio_samples.duck.reset();
+        }
     }
 }
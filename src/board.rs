@@ -1,34 +1,139 @@
 use crate::constants::prelude::*;
 use crate::in_game::{
-    BlockColor, BlockPosition, LastDroppedBlock, Number, PerformCalculationEvent, SolidBlock,
+    get_board_translation, on_simulation_update, BlockColor, BlockKind, BlockPosition,
+    ClearBlockEvent, Inserted, LastDroppedBlock, Number, PerformCalculationEvent, SolidBlock,
 };
 use crate::prelude::*;
+use crate::rules::{Adjacency, GameRules, Versus};
+use crate::view::ViewLayout;
 use bevy::prelude::*;
+use bevy::window::WindowMode;
 use std::collections::HashMap;
+use std::ops::{Index, IndexMut};
 
 /// Type definition for block information
-type BlockInfo = Option<(Entity, BlockColor)>;
+type BlockInfo = Option<(Entity, BlockColor, BlockKind)>;
+
+/// This `Component` tells which board the block belongs to. Boards are numbered from left to
+/// right starting from zero.
+#[derive(Component, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct BoardId(pub usize);
+
+/// Resource holding a separate value for each board, indexed by the `BoardId`
+pub struct PerBoard<T>(pub Vec<T>);
+
+impl<T: Default> PerBoard<T> {
+    /// Resets the value of each board for a game with given number of boards
+    pub fn reset(&mut self, boards: usize) {
+        self.0 = (0..boards).map(|_| T::default()).collect();
+    }
+}
+
+impl<T: Default> Default for PerBoard<T> {
+    fn default() -> Self {
+        PerBoard(vec![T::default()])
+    }
+}
+
+impl<T> Index<usize> for PerBoard<T> {
+    type Output = T;
+
+    fn index(&self, board: usize) -> &T {
+        &self.0[board]
+    }
+}
+
+impl<T> IndexMut<usize> for PerBoard<T> {
+    fn index_mut(&mut self, board: usize) -> &mut T {
+        &mut self.0[board]
+    }
+}
+
+/// Board size presets selectable in the options. The selected size is taken into use as the next
+/// game starts.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum BoardPreset {
+    Small,
+    Medium,
+    Classic,
+}
+
+impl BoardPreset {
+    /// All the presets in the order they are shown in the options
+    pub const ALL: [BoardPreset; 3] = [
+        BoardPreset::Small,
+        BoardPreset::Medium,
+        BoardPreset::Classic,
+    ];
+
+    /// Returns the name of the preset
+    pub fn name(&self) -> &'static str {
+        match self {
+            BoardPreset::Small => "Small",
+            BoardPreset::Medium => "Medium",
+            BoardPreset::Classic => "Classic",
+        }
+    }
+
+    /// Returns the next preset (wraps around)
+    pub fn next(&self) -> BoardPreset {
+        let index = BoardPreset::ALL.iter().position(|p| p == self).unwrap_or(0);
+        BoardPreset::ALL[(index + 1) % BoardPreset::ALL.len()]
+    }
+
+    /// Returns the previous preset (wraps around)
+    pub fn previous(&self) -> BoardPreset {
+        let index = BoardPreset::ALL.iter().position(|p| p == self).unwrap_or(0);
+        BoardPreset::ALL[(index + BoardPreset::ALL.len() - 1) % BoardPreset::ALL.len()]
+    }
+
+    /// Returns the size of the board (in blocks)
+    pub fn size(&self) -> BoardSize {
+        match self {
+            BoardPreset::Small => BoardSize {
+                width: 5,
+                height: 12,
+            },
+            BoardPreset::Medium => BoardSize {
+                width: 6,
+                height: 14,
+            },
+            BoardPreset::Classic => MAX_BOARD_SIZE,
+        }
+    }
+}
 
 /// This event can be sent for moving a block in a map
 pub struct MoveBlockEvent {
+    pub board: usize,
     pub old_pos: Coords,
     pub new_pos: Coords,
 }
 
-/// Represents the block map of all the dropped blocks
-pub struct BlockMap(HashMap<Coords, BlockInfo>);
+/// Represents the block map of all the dropped blocks on a board of the given size
+pub struct BlockMap(HashMap<Coords, BlockInfo>, BoardSize);
+
+impl Default for BlockMap {
+    fn default() -> Self {
+        BlockMap::new_empty(BoardSize::default())
+    }
+}
+
 impl BlockMap {
-    /// Returns new empty `BlockMap`
-    pub fn new_empty() -> BlockMap {
+    /// Returns new empty `BlockMap` of the given board size
+    pub fn new_empty(size: BoardSize) -> BlockMap {
         let block_map = HashMap::<Coords, BlockInfo>::new();
-        BlockMap(block_map)
+        BlockMap(block_map, size)
+    }
+
+    /// Returns the size of the board
+    pub fn size(&self) -> BoardSize {
+        self.1
     }
 
     /// Retruns `true` if no block found from position
     pub fn is_none(&self, pos: &Coords) -> bool {
-        if (0..BOARD_SIZE.width).contains(&(pos.x as u32))
-            && (0..BOARD_SIZE.height).contains(&(pos.y as u32))
-        {
+        if self.1.contains(pos) {
             if let Some(value) = self.0.get(pos) {
                 return value.is_none();
             } else {
@@ -55,10 +160,32 @@ impl BlockMap {
         }
     }
 
+    /// Moves the blocks on the board up by given number of rows. Edges stay in place and the
+    /// blocks pushed over the top are removed.
+    pub fn shift_up(&mut self, rows: i32) {
+        let size = self.1;
+        let blocks: Vec<(Coords, BlockInfo)> = self
+            .0
+            .iter()
+            .filter(|(pos, _)| size.contains(pos))
+            .map(|(pos, block)| (*pos, *block))
+            .collect();
+        for (pos, _) in blocks.iter() {
+            self.0.remove(pos);
+        }
+        for (pos, block) in blocks {
+            let new_pos = Coords::new(pos.x, pos.y + rows);
+            if size.contains(&new_pos) {
+                self.0.insert(new_pos, block);
+            }
+        }
+    }
+
+    #[cfg(debug_assertions)]
     #[allow(dead_code)]
     pub fn debug_draw(&self) {
-        for y in (0..BOARD_SIZE.height).rev() {
-            for x in 0..BOARD_SIZE.width {
+        for y in (0..self.1.height).rev() {
+            for x in 0..self.1.width {
                 if self.get_block(&Coords::new(x as i32, y as i32)).is_none() {
                     print!(". ");
                 } else {
@@ -74,98 +201,352 @@ impl BlockMap {
 pub struct BoardPlugin;
 impl Plugin for BoardPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system_set(SystemSet::on_enter(GameState::InGame).with_system(on_enter))
-            .add_system_set(SystemSet::on_exit(GameState::InGame).with_system(on_exit))
-            .add_system_set(
-                SystemSet::on_update(GameState::InGame)
-                    .with_system(handle_block_dropped)
-                    .with_system(handle_moved_block),
-            )
-            .insert_resource(BlockMap::new_empty())
-            .add_event::<MoveBlockEvent>();
+        app.add_system_set(
+            SystemSet::on_enter(GameState::InGame)
+                .with_system(on_enter)
+                .with_system(layout_game_boards),
+        )
+        .add_system_set(SystemSet::on_exit(GameState::InGame).with_system(on_exit))
+        .add_system_set(SystemSet::on_enter(GameState::Menu).with_system(layout_menu))
+        .add_system_set(
+            on_simulation_update()
+                .with_system(handle_block_dropped)
+                .with_system(handle_moved_block),
+        )
+        .insert_resource(PerBoard::<BlockMap>::default())
+        .insert_resource(BoardSize::default())
+        .add_event::<MoveBlockEvent>();
     }
 }
 
+/// Called once as the game is started. Takes the selected board size into use and puts the edges
+/// of each board into its `BlockMap`.
 fn on_enter(
-    mut block_map: ResMut<BlockMap>,
-    query: Query<(Entity, &BlockPosition), With<EdgeBlock>>,
+    mut commands: Commands,
+    my_assets: Res<MyAssets>,
+    preset: Res<BoardPreset>,
+    mut size: ResMut<BoardSize>,
+    mut block_maps: ResMut<PerBoard<BlockMap>>,
+    versus: Res<Versus>,
+    edges: Query<Entity, With<EdgeBlock>>,
 ) {
-    block_map.0.clear();
+    *size = preset.size();
+    block_maps.0 = (0..versus.boards())
+        .map(|_| BlockMap::new_empty(*size))
+        .collect();
+
+    let spawned = spawn_edges(&mut commands, &my_assets, *size, versus.boards(), &edges);
+    for (board, pos, entity) in spawned {
+        block_maps[board]
+            .0
+            .insert(pos, Some((entity, BlockColor::NONE, BlockKind::NORMAL)));
+    }
+}
 
-    for (entity, pos) in query.iter() {
-        block_map.0.insert(pos.0, Some((entity, BlockColor::NONE)));
+/// Helper function to spawn the edges around each board. The edges of the previous layout are
+/// despawned, as the size or the number of the boards may have changed. Returns the board, the
+/// position and the entity of each spawned edge block.
+fn spawn_edges(
+    commands: &mut Commands,
+    my_assets: &MyAssets,
+    size: BoardSize,
+    boards: usize,
+    edges: &Query<Entity, With<EdgeBlock>>,
+) -> Vec<(usize, Coords, Entity)> {
+    for entity in edges.iter() {
+        commands.entity(entity).despawn();
     }
+
+    let mut spawned = vec![];
+    for board in 0..boards {
+        for y in -1..=size.height as i32 {
+            for x in -1..=size.width as i32 {
+                let pos = Coords::new(x, y);
+                if size.contains(&pos) {
+                    continue;
+                }
+                let entity = commands
+                    .spawn_bundle(SpriteBundle {
+                        texture: my_assets.edge_texture.clone(),
+                        transform: Transform::from_translation(get_board_translation(
+                            &size, board, &pos,
+                        )),
+                        ..default()
+                    })
+                    .insert(EdgeBlock)
+                    .insert(BoardId(board))
+                    .insert(BlockPosition(pos))
+                    .id();
+                spawned.push((board, pos, entity));
+            }
+        }
+    }
+    spawned
 }
 
-fn on_exit(mut block_map: ResMut<BlockMap>) {
-    block_map.0.clear();
+fn on_exit(mut block_maps: ResMut<PerBoard<BlockMap>>) {
+    for block_map in block_maps.0.iter_mut() {
+        block_map.0.clear();
+    }
 }
 
-/// Finds same colored neighbors by calling itself recursively for each found neighbor
+/// Helper function to lay out the boards side by side. Windowed window is widened or narrowed to
+/// keep the size of the boards.
+fn set_board_layout(boards: usize, panels: bool, windows: &mut Windows, view: &mut ViewLayout) {
+    if view.boards != boards {
+        if let Some(window) = windows.get_primary_mut() {
+            if matches!(window.mode(), WindowMode::Windowed) {
+                let width = window.width() / view.boards as f32 * boards as f32;
+                window.set_resolution(width, window.height());
+            }
+        }
+        view.boards = boards;
+    }
+    if view.panels != panels {
+        view.panels = panels;
+    }
+}
+
+/// Called once as the game is started. Makes room for the boards of a versus game.
+fn layout_game_boards(
+    versus: Res<Versus>,
+    mut windows: ResMut<Windows>,
+    mut view: ResMut<ViewLayout>,
+) {
+    set_board_layout(versus.boards(), true, &mut windows, &mut view);
+}
+
+/// Called once when switching to the main menu. Menu is shown with a single board of the selected
+/// size without the side panel.
+fn layout_menu(
+    mut commands: Commands,
+    my_assets: Res<MyAssets>,
+    preset: Res<BoardPreset>,
+    mut size: ResMut<BoardSize>,
+    mut windows: ResMut<Windows>,
+    mut view: ResMut<ViewLayout>,
+    edges: Query<Entity, With<EdgeBlock>>,
+) {
+    set_board_layout(1, false, &mut windows, &mut view);
+    *size = preset.size();
+    spawn_edges(&mut commands, &my_assets, *size, 1, &edges);
+}
+
+/// Returns the positions next to the given position (below, left, above, right)
+pub fn get_neighbor_positions(pos: &Coords) -> [Coords; 4] {
+    [
+        Coords::new(pos.x, pos.y - 1),
+        Coords::new(pos.x - 1, pos.y),
+        Coords::new(pos.x, pos.y + 1),
+        Coords::new(pos.x + 1, pos.y),
+    ]
+}
+
+/// Returns the positions around the given position including the diagonal ones
+fn get_surrounding_positions(pos: &Coords) -> [Coords; 8] {
+    [
+        Coords::new(pos.x, pos.y - 1),
+        Coords::new(pos.x - 1, pos.y - 1),
+        Coords::new(pos.x - 1, pos.y),
+        Coords::new(pos.x - 1, pos.y + 1),
+        Coords::new(pos.x, pos.y + 1),
+        Coords::new(pos.x + 1, pos.y + 1),
+        Coords::new(pos.x + 1, pos.y),
+        Coords::new(pos.x + 1, pos.y - 1),
+    ]
+}
+
+/// Returns `true` if the block combines with the given color. Wildcard blocks combine with every
+/// color, stones and edges with none.
+fn is_matching_block(color: BlockColor, kind: BlockKind, target: BlockColor) -> bool {
+    match kind {
+        BlockKind::WILDCARD => true,
+        BlockKind::STONE => false,
+        _ => color == target && target != BlockColor::NONE,
+    }
+}
+
+/// Finds same colored neighbors according to the adjacency rule. Flood rules call this
+/// recursively for each found neighbor.
 fn find_same_color_neighbors(
     block_map: &BlockMap,
     pos: &Coords,
     color: BlockColor,
+    adjacency: Adjacency,
     mut neighbors: &mut Vec<Entity>,
 ) {
-    // Check same color neighbors from each direction
-    let neighbor_positions = [
-        Coords::new(pos.x, pos.y - 1),
-        Coords::new(pos.x - 1, pos.y),
-        Coords::new(pos.x, pos.y + 1),
-        Coords::new(pos.x + 1, pos.y),
-    ];
-    for pos in neighbor_positions {
-        if let Some(block) = block_map.get_block(&pos) {
-            if block.1 == color {
-                if !neighbors.contains(&block.0) {
-                    neighbors.push(block.0);
-                    find_same_color_neighbors(block_map, &pos, color, &mut neighbors)
+    match adjacency {
+        Adjacency::Flood | Adjacency::EightWay => {
+            let positions = if adjacency == Adjacency::Flood {
+                get_neighbor_positions(pos).to_vec()
+            } else {
+                get_surrounding_positions(pos).to_vec()
+            };
+            for pos in positions {
+                if let Some(block) = block_map.get_block(&pos) {
+                    if is_matching_block(block.1, block.2, color) {
+                        if !neighbors.contains(&block.0) {
+                            neighbors.push(block.0);
+                            find_same_color_neighbors(
+                                block_map,
+                                &pos,
+                                color,
+                                adjacency,
+                                &mut neighbors,
+                            )
+                        }
+                    }
+                }
+            }
+        }
+        Adjacency::Direct => {
+            for pos in get_neighbor_positions(pos) {
+                if let Some(block) = block_map.get_block(&pos) {
+                    if is_matching_block(block.1, block.2, color) && !neighbors.contains(&block.0) {
+                        neighbors.push(block.0);
+                    }
+                }
+            }
+        }
+        Adjacency::LineOfSight => {
+            // Follow each direction until the line of same colored blocks breaks
+            for next_pos in get_neighbor_positions(pos) {
+                let step = Coords::new(next_pos.x - pos.x, next_pos.y - pos.y);
+                let mut line_pos = next_pos;
+                while let Some(block) = block_map.get_block(&line_pos) {
+                    if !is_matching_block(block.1, block.2, color) {
+                        break;
+                    }
+                    if !neighbors.contains(&block.0) {
+                        neighbors.push(block.0);
+                    }
+                    line_pos = Coords::new(line_pos.x + step.x, line_pos.y + step.y);
                 }
             }
         }
     }
 }
 
+/// Sorts the neighbors into the order they are combined with the block dropped at `origin`:
+/// nearest blocks first (Manhattan distance), and blocks at the same distance from bottom to
+/// top and from left to right. This keeps the outcome independent of the search order.
+pub fn order_neighbors(origin: &Coords, neighbors: &mut [(Coords, Entity)]) {
+    neighbors.sort_by_key(|(pos, _)| {
+        let distance = (pos.x - origin.x).abs() + (pos.y - origin.y).abs();
+        (distance, pos.y, pos.x)
+    });
+}
+
+/// Returns the blocks which are combined with a block of given color and kind dropped at `pos`,
+/// in the order they are processed (see `order_neighbors`)
+pub fn find_drop_neighbors(
+    block_map: &BlockMap,
+    pos: &Coords,
+    color: BlockColor,
+    kind: BlockKind,
+    adjacency: Adjacency,
+) -> Vec<(Coords, Entity)> {
+    let mut neighbors = Vec::<Entity>::new();
+    match kind {
+        BlockKind::NORMAL | BlockKind::BOMB => {
+            find_same_color_neighbors(block_map, pos, color, adjacency, &mut neighbors);
+        }
+        BlockKind::WILDCARD => {
+            // Wildcard combines with the neighbors of each color next to it
+            for neighbor_pos in get_neighbor_positions(pos) {
+                if let Some((_, neighbor_color, neighbor_kind)) = block_map.get_block(&neighbor_pos)
+                {
+                    if neighbor_kind != BlockKind::STONE && neighbor_color != BlockColor::NONE {
+                        find_same_color_neighbors(
+                            block_map,
+                            pos,
+                            neighbor_color,
+                            adjacency,
+                            &mut neighbors,
+                        );
+                    }
+                }
+            }
+        }
+        BlockKind::STONE => {}
+    }
+
+    let mut ordered: Vec<(Coords, Entity)> = block_map
+        .0
+        .iter()
+        .filter_map(|(pos, block)| match block {
+            Some((e, _, _)) if neighbors.contains(e) => Some((*pos, *e)),
+            _ => None,
+        })
+        .collect();
+    order_neighbors(pos, &mut ordered);
+    ordered
+}
+
 /// Update board whenever new solid block is spawned
 fn handle_block_dropped(
-    mut block_map: ResMut<BlockMap>,
-    mut query: Query<(Entity, &BlockPosition, &BlockColor, &mut Number), Added<SolidBlock>>,
+    mut block_maps: ResMut<PerBoard<BlockMap>>,
+    mut query: Query<
+        (
+            Entity,
+            &BlockPosition,
+            &BlockColor,
+            &BlockKind,
+            &mut Number,
+            &BoardId,
+            Option<&Inserted>,
+        ),
+        Added<SolidBlock>,
+    >,
     mut events: EventWriter<PerformCalculationEvent>,
-    mut last_dropped_block: ResMut<LastDroppedBlock>,
+    mut clear_events: EventWriter<ClearBlockEvent>,
+    last_dropped_blocks: Res<PerBoard<LastDroppedBlock>>,
+    rules: Res<GameRules>,
 ) {
-    for (entity, pos, color, number) in query.iter_mut() {
+    for (entity, pos, color, kind, number, board, inserted) in query.iter_mut() {
+        let block_map = &mut block_maps[board.0];
+
+        // Restored and garbage blocks are only added into the BlockMap
+        if inserted.is_some() {
+            block_map.0.insert(pos.0, Some((entity, *color, *kind)));
+            continue;
+        }
+
         // Find neighbors and send events to perform calculations in them
-        let mut neighbors = Vec::<Entity>::new();
-        find_same_color_neighbors(&block_map, &pos.0, color.clone(), &mut neighbors);
+        let neighbors = find_drop_neighbors(block_map, &pos.0, *color, *kind, rules.adjacency);
 
-        // Send events
-        for entity in &neighbors {
+        if *kind == BlockKind::BOMB {
+            // Bomb clears the neighbors regardless of their value and itself
+            for (_, entity) in &neighbors {
+                clear_events.send(ClearBlockEvent(*entity));
+            }
+            clear_events.send(ClearBlockEvent(entity));
+        } else if !neighbors.is_empty() {
             events.send(PerformCalculationEvent {
-                entity: *entity,
+                entity,
+                neighbors: neighbors.iter().map(|(_, e)| *e).collect(),
                 number: number.0,
-                operation: last_dropped_block.operation.unwrap(),
+                operation: last_dropped_blocks[board.0].operation.unwrap(),
             });
         }
 
-        // Added Solid block is always also last dropped block
-        last_dropped_block.entity = Some(entity);
-
         // Add the spawned solid block into the BlockMap
-        block_map.0.insert(pos.0, Some((entity, *color)));
+        block_map.0.insert(pos.0, Some((entity, *color, *kind)));
     }
 }
 
 fn handle_moved_block(
-    mut block_map: ResMut<BlockMap>,
+    mut block_maps: ResMut<PerBoard<BlockMap>>,
     mut event_reader: EventReader<MoveBlockEvent>,
 ) {
     for ev in event_reader.iter() {
+        let block_map = &mut block_maps[ev.board];
         if let Some(block) = block_map.get_block(&ev.old_pos) {
             block_map.0.remove(&ev.old_pos);
             if block_map.get_block(&ev.new_pos).is_none() {
                 block_map.0.insert(ev.new_pos, Some(block));
             } else {
+                #[cfg(debug_assertions)]
                 println!(
                     "Error!! Already occupied {}, {}",
                     ev.new_pos.x, ev.new_pos.y
@@ -174,3 +555,201 @@ fn handle_moved_block(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fraction::Fraction;
+    use crate::in_game::Operation;
+    use crate::rules::{GameMode, Resolution};
+
+    /// Dropped block position used by the adjacency tests
+    const DROP: Coords = Coords { x: 2, y: 1 };
+
+    /// Blue blocks around the drop position, blocked from above by a pink block:
+    ///
+    /// ```text
+    /// 3  . . B . .
+    /// 2  . B P . .
+    /// 1  . . * B B
+    /// 0  . B B . .
+    ///    0 1 2 3 4
+    /// ```
+    fn test_block_map() -> BlockMap {
+        let mut block_map = BlockMap::new_empty(MAX_BOARD_SIZE);
+        let blocks = [
+            (2, 0, BlockColor::BLUE),
+            (1, 0, BlockColor::BLUE),
+            (1, 2, BlockColor::BLUE),
+            (2, 2, BlockColor::PINK),
+            (2, 3, BlockColor::BLUE),
+            (3, 1, BlockColor::BLUE),
+            (4, 1, BlockColor::BLUE),
+        ];
+        for (index, (x, y, color)) in blocks.iter().enumerate() {
+            block_map.set_block(
+                &Coords::new(*x, *y),
+                Some((Entity::from_raw(index as u32), *color, BlockKind::NORMAL)),
+            );
+        }
+        block_map
+    }
+
+    /// Returns the positions of the neighbors found with the adjacency rule, in processing order
+    fn find_positions(adjacency: Adjacency) -> Vec<(i32, i32)> {
+        find_drop_neighbors(
+            &test_block_map(),
+            &DROP,
+            BlockColor::BLUE,
+            BlockKind::NORMAL,
+            adjacency,
+        )
+        .iter()
+        .map(|(pos, _)| (pos.x, pos.y))
+        .collect()
+    }
+
+    #[test]
+    fn flood_finds_connected_region() {
+        assert_eq!(
+            find_positions(Adjacency::Flood),
+            vec![(2, 0), (3, 1), (1, 0), (4, 1)]
+        );
+    }
+
+    #[test]
+    fn direct_finds_only_next_blocks() {
+        assert_eq!(find_positions(Adjacency::Direct), vec![(2, 0), (3, 1)]);
+    }
+
+    #[test]
+    fn eight_way_finds_diagonally_connected_region() {
+        assert_eq!(
+            find_positions(Adjacency::EightWay),
+            vec![(2, 0), (3, 1), (1, 0), (4, 1), (1, 2), (2, 3)]
+        );
+    }
+
+    #[test]
+    fn line_of_sight_stops_at_other_colors() {
+        assert_eq!(
+            find_positions(Adjacency::LineOfSight),
+            vec![(2, 0), (3, 1), (4, 1)]
+        );
+    }
+
+    /// Drops a blue 4 with `+` next to blue blocks numbered from 1 upwards and returns the new
+    /// numbers of the neighbors (in processing order) and of the dropped block
+    fn drop_block(
+        positions: &[(i32, i32)],
+        resolution: Resolution,
+    ) -> (Vec<Fraction>, Option<Fraction>) {
+        let mut block_map = BlockMap::new_empty(MAX_BOARD_SIZE);
+        let mut numbers = HashMap::new();
+        for (index, (x, y)) in positions.iter().enumerate() {
+            let entity = Entity::from_raw(index as u32);
+            block_map.set_block(
+                &Coords::new(*x, *y),
+                Some((entity, BlockColor::BLUE, BlockKind::NORMAL)),
+            );
+            numbers.insert(entity, Fraction::from(index as i64 + 1));
+        }
+        // Block of another color next to the dropped block is left out
+        block_map.set_block(
+            &Coords::new(DROP.x, DROP.y + 1),
+            Some((Entity::from_raw(99), BlockColor::PINK, BlockKind::NORMAL)),
+        );
+
+        let neighbors: Vec<Fraction> = find_drop_neighbors(
+            &block_map,
+            &DROP,
+            BlockColor::BLUE,
+            BlockKind::NORMAL,
+            Adjacency::Direct,
+        )
+        .iter()
+        .map(|(_, entity)| numbers[entity])
+        .collect();
+        GameRules::new(GameMode::Classic, Adjacency::Direct, resolution).combine(
+            Fraction::from(4),
+            Operation::ADD,
+            &neighbors,
+        )
+    }
+
+    #[test]
+    fn dropped_block_is_resolved_from_neighbors() {
+        // Neighbor below is processed first, then the left and the right one
+        let two = [(1, 1), (2, 0)];
+        let three = [(3, 1), (1, 1), (2, 0)];
+        let expected = [
+            (Resolution::First, Some(6), Some(7)),
+            (Resolution::Last, Some(5), Some(5)),
+            (Resolution::Sum, Some(11), Some(18)),
+            (Resolution::Keep, None, None),
+        ];
+        for (resolution, two_dropped, three_dropped) in expected {
+            let (results, dropped) = drop_block(&two, resolution);
+            assert_eq!(results, vec![Fraction::from(6), Fraction::from(5)]);
+            assert_eq!(dropped, two_dropped.map(Fraction::from), "{:?}", resolution);
+
+            let (results, dropped) = drop_block(&three, resolution);
+            assert_eq!(
+                results,
+                vec![Fraction::from(7), Fraction::from(6), Fraction::from(5)]
+            );
+            assert_eq!(
+                dropped,
+                three_dropped.map(Fraction::from),
+                "{:?}",
+                resolution
+            );
+        }
+    }
+
+    #[test]
+    fn block_map_follows_board_size() {
+        let size = BoardPreset::Small.size();
+        let mut block_map = BlockMap::new_empty(size);
+        assert!(block_map.is_none(&Coords::new(4, 11)));
+        assert!(!block_map.is_none(&Coords::new(5, 0)));
+        assert!(!block_map.is_none(&Coords::new(0, 12)));
+
+        // Blocks pushed over the top row of the board are removed
+        let entity = Entity::from_raw(0);
+        block_map.set_block(
+            &Coords::new(0, 10),
+            Some((entity, BlockColor::BLUE, BlockKind::NORMAL)),
+        );
+        block_map.shift_up(1);
+        assert!(block_map.get_block(&Coords::new(0, 11)).is_some());
+        block_map.shift_up(1);
+        assert!(block_map.get_block(&Coords::new(0, 12)).is_none());
+    }
+
+    #[test]
+    fn order_neighbors_is_independent_of_input_order() {
+        let origin = Coords::new(3, 3);
+        let positions = [(2, 2), (3, 4), (5, 3), (4, 3), (3, 2), (2, 3)];
+        let expected = vec![(3, 2), (2, 3), (4, 3), (3, 4), (2, 2), (5, 3)];
+
+        for rotation in 0..positions.len() {
+            for reverse in [false, true] {
+                let mut input = positions.to_vec();
+                input.rotate_left(rotation);
+                if reverse {
+                    input.reverse();
+                }
+                let mut neighbors: Vec<(Coords, Entity)> = input
+                    .iter()
+                    .enumerate()
+                    .map(|(i, (x, y))| (Coords::new(*x, *y), Entity::from_raw(i as u32)))
+                    .collect();
+                order_neighbors(&origin, &mut neighbors);
+                let ordered: Vec<(i32, i32)> =
+                    neighbors.iter().map(|(pos, _)| (pos.x, pos.y)).collect();
+                assert_eq!(ordered, expected);
+            }
+        }
+    }
+}
@@ -4,17 +4,39 @@
 use bevy::prelude::*;
 pub mod prelude {
     pub use super::{
-        BoardSize, Coords, ASPECT_RATIO, BACKGROUND_COLOR, BLOCK_SIZE, BOARD_SIZE, HALF_BLOCK,
-        INITIAL_DROP_SPEED, INITIAL_POSITION, INITIAL_TRANSFORM, WORLD_HEIGHT,
+        BoardSize, Coords, BACKGROUND_COLOR, BLOCKS_PER_LEVEL, BLOCK_SIZE, BOARD_AREA_WIDTH,
+        DANGER_ZONE_HEIGHT, FAST_DROP_SPEED, HALF_BLOCK, INITIAL_DROP_SPEED, INITIAL_TRANSFORM,
+        INTENSE_DROP_SPEED, MAX_BOARDS, MAX_BOARD_SIZE, NETWORK_TIME_STEP, PANEL_WIDTH,
+        SLIDE_DURATION, WINDOW_HEIGHT, WINDOW_WIDTH, WORLD_HEIGHT,
     };
 }
 
-/// Struct defining the board size (in blocks)
+/// Struct defining the board size (in blocks). As a resource this holds the size of the boards in
+/// the current game.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub struct BoardSize {
     pub width: u32,
     pub height: u32,
 }
 
+impl BoardSize {
+    /// Returns the initial dropping block position on the top row, right of the center
+    pub fn initial_position(&self) -> Coords {
+        Coords::new(self.width as i32 / 2 + 1, self.height as i32 - 1)
+    }
+
+    /// Returns `true` if the position is inside the board
+    pub fn contains(&self, pos: &Coords) -> bool {
+        (0..self.width as i32).contains(&pos.x) && (0..self.height as i32).contains(&pos.y)
+    }
+}
+
+impl Default for BoardSize {
+    fn default() -> Self {
+        MAX_BOARD_SIZE
+    }
+}
+
 /// Struct defining the block position as x, y coordinates
 #[derive(Clone, Copy, Eq, PartialEq, Hash)]
 pub struct Coords {
@@ -29,8 +51,9 @@ impl Coords {
     }
 }
 
-/// Size of the board (in blocks)
-pub const BOARD_SIZE: BoardSize = BoardSize {
+/// Size of the largest board (in blocks). The layout leaves room for it, and the smaller boards
+/// are centered in its place.
+pub const MAX_BOARD_SIZE: BoardSize = BoardSize {
     width: 7,
     height: 16,
 };
@@ -38,20 +61,48 @@ pub const BOARD_SIZE: BoardSize = BoardSize {
 /// Initial block dropping speed (in seconds)
 pub const INITIAL_DROP_SPEED: f32 = 1.0;
 
+/// Time the game advances on each tick of a network game (in seconds). Both instances advance by
+/// the same amount regardless of their frame rate.
+pub const NETWORK_TIME_STEP: f32 = 1.0 / 60.0;
+
+/// Number of cleared blocks needed for advancing to the next level
+pub const BLOCKS_PER_LEVEL: u32 = 10;
+
+/// Solid blocks this close (in blocks) to the dropping block initial position are in danger
+pub const DANGER_ZONE_HEIGHT: i32 = 3;
+
+/// Music gets more intense once the drop speed (in seconds) goes below these thresholds
+pub const FAST_DROP_SPEED: f32 = 0.5;
+pub const INTENSE_DROP_SPEED: f32 = 0.3;
+
+/// Duration of a block sliding the distance of one block (in seconds). Longer slides take more
+/// time, but less than linearly.
+pub const SLIDE_DURATION: f32 = 0.08;
+
 /// Initial transform for spawned blocks (Somewhere hidden)
-pub const INITIAL_TRANSFORM: Transform = Transform::from_xyz(0.0, 1000.0, 1.0);
+pub const INITIAL_TRANSFORM: Transform = Transform::from_xyz(0.0, 10000.0, 1.0);
 
-/// Initial dropping block position (in block size)
-pub const INITIAL_POSITION: Coords = Coords {
-    x: 4,
-    y: BOARD_SIZE.height as i32 - 1,
-};
+/// Aspect ratio of the area taken by a single board and its side panel
+pub const ASPECT_RATIO: f32 = 9.0 / 10.0;
+
+/// Default pixel height of the window. UI is designed for this size and scaled to the window.
+pub const WINDOW_HEIGHT: f32 = 720.0;
+
+/// Default pixel width of the window per board
+pub const WINDOW_WIDTH: f32 = WINDOW_HEIGHT * ASPECT_RATIO;
+
+/// Height of the game world shown in the window (in world units)
+pub const WORLD_HEIGHT: f32 = 960.0;
+
+/// Width of the area taken by a single board and its side panel (in world units). Boards of a
+/// versus game are side by side.
+pub const BOARD_AREA_WIDTH: f32 = WORLD_HEIGHT * ASPECT_RATIO;
 
-/// Window aspect ratio
-pub const ASPECT_RATIO: f32 = 9.0 / 12.0;
+/// Width of the side panel on the right side of each board (in world units)
+pub const PANEL_WIDTH: f32 = BOARD_AREA_WIDTH - (MAX_BOARD_SIZE.width + 2) as f32 * BLOCK_SIZE;
 
-/// Pixel height of the game world
-pub const WORLD_HEIGHT: f32 = 720.0;
+/// Maximum number of boards (players) in a game
+pub const MAX_BOARDS: usize = 2;
 
 /// Size of the game blocks
 pub const BLOCK_SIZE: f32 = 64.;
@@ -0,0 +1,348 @@
+use crate::ai::AiPlayer;
+use crate::board::PerBoard;
+use crate::in_game::BlockColor;
+use crate::prelude::*;
+use crate::rules::Versus;
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+
+/// Actions for controlling the dropping block. The actions of each board are held in
+/// `PerBoard<Input<PlayerAction>>` resource, which is driven either by the keyboard or by the AI
+/// player.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum PlayerAction {
+    MoveLeft,
+    MoveRight,
+    SoftDrop,
+    SwitchColor,
+    SelectColor(BlockColor),
+    /// Puts the dropping block on hold and takes the held block in its place
+    Hold,
+}
+
+/// Actions for navigating the menus. The actions are held in `Input<MenuAction>` resource, which
+/// is driven by both the keyboard and the gamepads.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum MenuAction {
+    Up,
+    Down,
+    Left,
+    Right,
+    Select,
+    Back,
+}
+
+/// Keyboard mapping of the menu actions
+const MENU_KEY_MAP: [(KeyCode, MenuAction); 6] = [
+    (KeyCode::Up, MenuAction::Up),
+    (KeyCode::Down, MenuAction::Down),
+    (KeyCode::Left, MenuAction::Left),
+    (KeyCode::Right, MenuAction::Right),
+    (KeyCode::Return, MenuAction::Select),
+    (KeyCode::Escape, MenuAction::Back),
+];
+
+/// Gamepad mapping of the menu actions
+const MENU_BUTTON_MAP: [(GamepadButtonType, MenuAction); 7] = [
+    (GamepadButtonType::DPadUp, MenuAction::Up),
+    (GamepadButtonType::DPadDown, MenuAction::Down),
+    (GamepadButtonType::DPadLeft, MenuAction::Left),
+    (GamepadButtonType::DPadRight, MenuAction::Right),
+    (GamepadButtonType::South, MenuAction::Select),
+    (GamepadButtonType::Start, MenuAction::Select),
+    (GamepadButtonType::East, MenuAction::Back),
+];
+
+/// Systems which press or release the player actions are labeled with this. Systems reading
+/// the actions should be run after them.
+#[derive(SystemLabel, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct PlayerInputLabel;
+
+/// Default keyboard mapping of the player actions in a single player game
+const KEY_MAP: [(KeyCode, PlayerAction); 10] = [
+    (KeyCode::Left, PlayerAction::MoveLeft),
+    (KeyCode::Right, PlayerAction::MoveRight),
+    (KeyCode::Down, PlayerAction::SoftDrop),
+    (KeyCode::LShift, PlayerAction::SwitchColor),
+    (KeyCode::RShift, PlayerAction::SwitchColor),
+    (KeyCode::Q, PlayerAction::SelectColor(BlockColor::BLUE)),
+    (KeyCode::W, PlayerAction::SelectColor(BlockColor::PINK)),
+    (KeyCode::E, PlayerAction::SelectColor(BlockColor::YELLOW)),
+    (KeyCode::R, PlayerAction::SelectColor(BlockColor::GREEN)),
+    (KeyCode::C, PlayerAction::Hold),
+];
+
+/// Player actions which can be bound to other keys in the options, in the order they are shown
+pub const BINDABLE_ACTIONS: [PlayerAction; 9] = [
+    PlayerAction::MoveLeft,
+    PlayerAction::MoveRight,
+    PlayerAction::SoftDrop,
+    PlayerAction::SwitchColor,
+    PlayerAction::SelectColor(BlockColor::BLUE),
+    PlayerAction::SelectColor(BlockColor::PINK),
+    PlayerAction::SelectColor(BlockColor::YELLOW),
+    PlayerAction::SelectColor(BlockColor::GREEN),
+    PlayerAction::Hold,
+];
+
+/// Keys which can be bound to the player actions. Keys already used for something else (digits,
+/// music, undo, hint, pause and the menu keys) are left out.
+pub const BINDABLE_KEYS: [KeyCode; 31] = [
+    KeyCode::A,
+    KeyCode::B,
+    KeyCode::C,
+    KeyCode::D,
+    KeyCode::E,
+    KeyCode::F,
+    KeyCode::G,
+    KeyCode::I,
+    KeyCode::J,
+    KeyCode::K,
+    KeyCode::L,
+    KeyCode::O,
+    KeyCode::Q,
+    KeyCode::R,
+    KeyCode::V,
+    KeyCode::W,
+    KeyCode::X,
+    KeyCode::Y,
+    KeyCode::Z,
+    KeyCode::Left,
+    KeyCode::Right,
+    KeyCode::Up,
+    KeyCode::Down,
+    KeyCode::Space,
+    KeyCode::Tab,
+    KeyCode::LShift,
+    KeyCode::RShift,
+    KeyCode::LControl,
+    KeyCode::RControl,
+    KeyCode::LAlt,
+    KeyCode::RAlt,
+];
+
+/// This resource holds the keyboard mapping of the player actions in a single player game. The
+/// keys can be changed in the options.
+pub struct KeyBindings(pub Vec<(KeyCode, PlayerAction)>);
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        KeyBindings(KEY_MAP.to_vec())
+    }
+}
+
+impl KeyBindings {
+    /// Returns the keys bound to the action
+    pub fn keys(&self, action: PlayerAction) -> Vec<KeyCode> {
+        self.0
+            .iter()
+            .filter(|(_, a)| *a == action)
+            .map(|(key, _)| *key)
+            .collect()
+    }
+
+    /// Replaces the keys bound to the action
+    pub fn set_keys(&mut self, action: PlayerAction, keys: &[KeyCode]) {
+        self.0.retain(|(_, a)| *a != action);
+        self.0.extend(keys.iter().map(|key| (*key, action)));
+    }
+
+    /// Binds the key to the action in place of its previous keys. Actions which had the key
+    /// before get all the previous keys of the action instead, so that every action stays
+    /// playable. The key is not bound if it would leave another action without keys. Returns
+    /// `true` if the key was bound.
+    pub fn bind(&mut self, action: PlayerAction, key: KeyCode) -> bool {
+        let previous = self.keys(action);
+        let mut displaced: Vec<PlayerAction> = vec![];
+        for (_, other) in self.0.iter().filter(|(k, a)| *k == key && *a != action) {
+            if !displaced.contains(other) {
+                displaced.push(*other);
+            }
+        }
+        if previous.is_empty() && displaced.iter().any(|other| self.keys(*other).len() == 1) {
+            return false;
+        }
+
+        self.0.retain(|(k, a)| *a != action && *k != key);
+        for other in displaced {
+            for previous_key in previous.iter() {
+                self.0.push((*previous_key, other));
+            }
+        }
+        self.0.push((key, action));
+        true
+    }
+}
+
+/// Keyboard mapping of the player on the left board in a versus game
+const LEFT_KEY_MAP: [(KeyCode, PlayerAction); 9] = [
+    (KeyCode::A, PlayerAction::MoveLeft),
+    (KeyCode::D, PlayerAction::MoveRight),
+    (KeyCode::Space, PlayerAction::SoftDrop),
+    (KeyCode::LShift, PlayerAction::SwitchColor),
+    (KeyCode::Q, PlayerAction::SelectColor(BlockColor::BLUE)),
+    (KeyCode::W, PlayerAction::SelectColor(BlockColor::PINK)),
+    (KeyCode::E, PlayerAction::SelectColor(BlockColor::YELLOW)),
+    (KeyCode::R, PlayerAction::SelectColor(BlockColor::GREEN)),
+    (KeyCode::Tab, PlayerAction::Hold),
+];
+
+/// Keyboard mapping of the player on the right board in a versus game
+const RIGHT_KEY_MAP: [(KeyCode, PlayerAction); 9] = [
+    (KeyCode::Left, PlayerAction::MoveLeft),
+    (KeyCode::Right, PlayerAction::MoveRight),
+    (KeyCode::Down, PlayerAction::SoftDrop),
+    (KeyCode::RShift, PlayerAction::SwitchColor),
+    (
+        KeyCode::Numpad1,
+        PlayerAction::SelectColor(BlockColor::BLUE),
+    ),
+    (
+        KeyCode::Numpad2,
+        PlayerAction::SelectColor(BlockColor::PINK),
+    ),
+    (
+        KeyCode::Numpad3,
+        PlayerAction::SelectColor(BlockColor::YELLOW),
+    ),
+    (
+        KeyCode::Numpad4,
+        PlayerAction::SelectColor(BlockColor::GREEN),
+    ),
+    (KeyCode::Numpad0, PlayerAction::Hold),
+];
+
+/// Returns the keyboard mapping of the player on the given board. Actions of a network game are
+/// replayed from the inputs exchanged with the opponent instead (see `net.rs`).
+fn get_key_map(bindings: &KeyBindings, versus: Versus, board: usize) -> &[(KeyCode, PlayerAction)] {
+    match (versus, board) {
+        (Versus::Off, _) => &bindings.0,
+        (Versus::Network, _) => &[],
+        (_, 0) => &LEFT_KEY_MAP,
+        _ => &RIGHT_KEY_MAP,
+    }
+}
+
+/// Bevy Plugin for handling the player actions
+pub struct ControlsPlugin;
+impl Plugin for ControlsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PerBoard<Input<PlayerAction>>>()
+            .add_system_set(SystemSet::on_enter(GameState::InGame).with_system(on_enter))
+            .init_resource::<Input<MenuAction>>()
+            .add_system_to_stage(CoreStage::PreUpdate, clear_player_actions)
+            .add_system_to_stage(CoreStage::PreUpdate, menu_actions.after(InputSystem))
+            .add_system(keyboard_player_actions.label(PlayerInputLabel));
+    }
+}
+
+/// Called once as the game is started
+fn on_enter(mut actions: ResMut<PerBoard<Input<PlayerAction>>>, versus: Res<Versus>) {
+    actions.reset(versus.boards());
+}
+
+/// Clears the just pressed / just released actions of the previous frame
+fn clear_player_actions(mut actions: ResMut<PerBoard<Input<PlayerAction>>>) {
+    for actions in actions.0.iter_mut() {
+        actions.clear();
+    }
+}
+
+/// Presses and releases the player actions according to the keyboard. Action bound to several
+/// keys is released once all of them are released. Board played by the AI is skipped.
+fn keyboard_player_actions(
+    input: Res<Input<KeyCode>>,
+    mut actions: ResMut<PerBoard<Input<PlayerAction>>>,
+    ai: Res<AiPlayer>,
+    versus: Res<Versus>,
+    bindings: Res<KeyBindings>,
+) {
+    for (board, actions) in actions.0.iter_mut().enumerate() {
+        if ai.enabled && ai.board == board {
+            continue;
+        }
+
+        let key_map = get_key_map(&bindings, *versus, board);
+        for (key, action) in key_map {
+            if input.just_pressed(*key) {
+                actions.press(*action);
+            } else if input.just_released(*key) {
+                // Action stays pressed as long as any of its keys is held
+                let held = key_map
+                    .iter()
+                    .any(|(other, a)| a == action && input.pressed(*other));
+                if !held {
+                    actions.release(*action);
+                }
+            }
+        }
+    }
+}
+
+/// Presses and releases the menu actions according to the keyboard and the gamepads
+fn menu_actions(
+    keys: Res<Input<KeyCode>>,
+    buttons: Res<Input<GamepadButton>>,
+    mut actions: ResMut<Input<MenuAction>>,
+) {
+    actions.clear();
+    for (key, action) in MENU_KEY_MAP {
+        if keys.just_pressed(key) {
+            actions.press(action);
+        } else if keys.just_released(key) {
+            actions.release(action);
+        }
+    }
+    for (button_type, action) in MENU_BUTTON_MAP {
+        if buttons
+            .get_just_pressed()
+            .any(|button| button.button_type == button_type)
+        {
+            actions.press(action);
+        } else if buttons
+            .get_just_released()
+            .any(|button| button.button_type == button_type)
+        {
+            actions.release(action);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bind_swaps_every_key_of_the_action() {
+        let mut bindings = KeyBindings(vec![
+            (KeyCode::LShift, PlayerAction::SwitchColor),
+            (KeyCode::RShift, PlayerAction::SwitchColor),
+            (KeyCode::C, PlayerAction::Hold),
+        ]);
+        assert!(bindings.bind(PlayerAction::SwitchColor, KeyCode::C));
+        assert_eq!(bindings.keys(PlayerAction::SwitchColor), vec![KeyCode::C]);
+        assert_eq!(
+            bindings.keys(PlayerAction::Hold),
+            vec![KeyCode::LShift, KeyCode::RShift]
+        );
+    }
+
+    #[test]
+    fn bind_keeps_other_actions_playable() {
+        let mut bindings = KeyBindings(vec![
+            (KeyCode::C, PlayerAction::Hold),
+            (KeyCode::Left, PlayerAction::MoveLeft),
+            (KeyCode::A, PlayerAction::MoveLeft),
+        ]);
+
+        // Action without keys can't take the only key of another action
+        assert!(!bindings.bind(PlayerAction::SoftDrop, KeyCode::C));
+        assert_eq!(bindings.keys(PlayerAction::Hold), vec![KeyCode::C]);
+        assert!(bindings.keys(PlayerAction::SoftDrop).is_empty());
+
+        // Other action keeps its remaining key
+        assert!(bindings.bind(PlayerAction::SoftDrop, KeyCode::A));
+        assert_eq!(bindings.keys(PlayerAction::SoftDrop), vec![KeyCode::A]);
+        assert_eq!(bindings.keys(PlayerAction::MoveLeft), vec![KeyCode::Left]);
+    }
+}
@@ -0,0 +1,90 @@
+//!
+//! Credits screen of the people and tools behind the game
+//!
+use crate::controls::MenuAction;
+use crate::localization::{Language, TextKey};
+use crate::prelude::*;
+use crate::view::BoardArea;
+use bevy::prelude::*;
+
+/// Credited roles and their authors in the order they are shown
+const CREDITS: [(TextKey, &str); 3] = [
+    (TextKey::CreditsDesign, "Jussi Kallio"),
+    (TextKey::CreditsMusic, "Eric Matyas\nwww.soundimage.org"),
+    (TextKey::CreditsEngine, "Bevy"),
+];
+
+/// Bevy Plugin for the credits screen
+pub struct CreditsPlugin;
+impl Plugin for CreditsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(GameState::Credits).with_system(on_enter))
+            .add_system_set(SystemSet::on_exit(GameState::Credits).with_system(on_exit))
+            .add_system_set(SystemSet::on_update(GameState::Credits).with_system(back_to_menu));
+    }
+}
+
+/// Called once when switching to `GameState::Credits`
+fn on_enter(mut commands: Commands, asset_server: Res<AssetServer>, language: Res<Language>) {
+    println!("Enter GameState::Credits");
+
+    let node = commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            color: UiColor(Color::NONE),
+            ..default()
+        })
+        .insert(MenuNode)
+        .insert(BoardArea)
+        .id();
+
+    let mut lines = vec![language.get(TextKey::Credits), String::new()];
+    for (role, author) in CREDITS {
+        lines.push(language.get(role));
+        lines.push(author.to_string());
+        lines.push(String::new());
+    }
+    lines.push(language.get(TextKey::Back));
+
+    let text = commands
+        .spawn_bundle(
+            TextBundle::from_section(
+                lines.join("\n"),
+                TextStyle {
+                    font: asset_server.load("fonts/04b_30.ttf"),
+                    font_size: 16.0,
+                    color: Color::BLACK,
+                },
+            )
+            .with_text_alignment(TextAlignment::CENTER),
+        )
+        .id();
+
+    commands.entity(node).push_children(&[text]);
+}
+
+/// Called once when switching from `GameState::Credits`
+fn on_exit(mut commands: Commands, query: Query<Entity, With<MenuNode>>) {
+    println!("Exit GameState::Credits");
+
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Switch back to `GameState::Menu` if Esc or Return key is pressed
+fn back_to_menu(mut actions: ResMut<Input<MenuAction>>, mut game_state: ResMut<State<GameState>>) {
+    if actions.just_pressed(MenuAction::Back) || actions.just_pressed(MenuAction::Select) {
+        game_state
+            .set(GameState::Menu)
+            .expect("Failed to change GameState::Menu");
+        actions.reset(MenuAction::Back);
+        actions.reset(MenuAction::Select);
+    }
+}
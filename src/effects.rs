@@ -0,0 +1,275 @@
+//!
+//! Visual effects of the cleared blocks: particle bursts, score popups, screen shake and a flash
+//! on multi-clears. Screen shake and flash can be turned off separately for players sensitive to
+//! motion.
+//!
+use crate::board::PerBoard;
+use crate::constants::prelude::*;
+use crate::in_game::{get_board_translation, BlockClearedEvent, Chain};
+use crate::palette::Palette;
+use crate::prelude::*;
+use crate::view::{BoardArea, CameraOffset};
+use bevy::prelude::*;
+use rand::Rng;
+
+/// Number of particles in the burst of a cleared block
+const PARTICLES_PER_BLOCK: usize = 12;
+
+/// Lifetime of a particle (in seconds)
+const PARTICLE_LIFETIME: f32 = 0.6;
+
+/// Downwards acceleration of the particles (in world units per second squared)
+const PARTICLE_GRAVITY: f32 = 900.0;
+
+/// Lifetime of a score popup (in seconds)
+const POPUP_LIFETIME: f32 = 1.0;
+
+/// Rising speed of a score popup (in world units per second)
+const POPUP_SPEED: f32 = 80.0;
+
+/// Duration of the screen shake (in seconds)
+const SHAKE_DURATION: f32 = 0.3;
+
+/// Strength of the screen shake for each step of the chain (in world units)
+const SHAKE_PER_CHAIN: f32 = 4.0;
+
+/// Maximum strength of the screen shake (in world units)
+const MAX_SHAKE: f32 = 20.0;
+
+/// Duration of the flash (in seconds)
+const FLASH_DURATION: f32 = 0.25;
+
+/// Defines which effects are shown as blocks are cleared. Selected in the options.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Effects {
+    /// Particles, score popups, screen shake and flash
+    All,
+    /// Particles and score popups without screen shake and flash
+    NoMotion,
+    /// No effects
+    Off,
+}
+
+impl Effects {
+    /// All the effect options in the order they are shown in the menu
+    pub const ALL: [Effects; 3] = [Effects::All, Effects::NoMotion, Effects::Off];
+
+    /// Returns the name of the effect option
+    pub fn name(&self) -> &'static str {
+        match self {
+            Effects::All => "All",
+            Effects::NoMotion => "No shake or flash",
+            Effects::Off => "Off",
+        }
+    }
+
+    /// Returns the next effect option (wraps around)
+    pub fn next(&self) -> Effects {
+        let index = Effects::ALL.iter().position(|e| e == self).unwrap_or(0);
+        Effects::ALL[(index + 1) % Effects::ALL.len()]
+    }
+
+    /// Returns the previous effect option (wraps around)
+    pub fn previous(&self) -> Effects {
+        let index = Effects::ALL.iter().position(|e| e == self).unwrap_or(0);
+        Effects::ALL[(index + Effects::ALL.len() - 1) % Effects::ALL.len()]
+    }
+
+    /// Returns `true` if the particles and score popups are shown
+    pub fn particles(&self) -> bool {
+        *self != Effects::Off
+    }
+
+    /// Returns `true` if the screen shakes and flashes
+    pub fn motion(&self) -> bool {
+        *self == Effects::All
+    }
+}
+
+/// Particle of a cleared block burst
+#[derive(Component)]
+struct Particle {
+    velocity: Vec2,
+    timer: Timer,
+}
+
+/// Floating score of a cleared block
+#[derive(Component)]
+struct ScorePopup(Timer);
+
+/// Flash covering the boards
+#[derive(Component)]
+struct Flash(Timer);
+
+/// This resource holds the state of the screen shake. Strength is zero while not shaking.
+struct ScreenShake {
+    strength: f32,
+    timer: Timer,
+}
+
+/// Bevy Plugin for the clear effects
+pub struct EffectsPlugin;
+impl Plugin for EffectsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_update(GameState::InGame).with_system(spawn_clear_effects),
+        )
+        .add_system(update_particles)
+        .add_system(update_popups)
+        .add_system(update_flash)
+        .add_system(shake_screen)
+        .insert_resource(ScreenShake {
+            strength: 0.0,
+            timer: Timer::from_seconds(SHAKE_DURATION, false),
+        });
+    }
+}
+
+/// System for spawning the effects of the cleared blocks
+fn spawn_clear_effects(
+    mut commands: Commands,
+    mut events: EventReader<BlockClearedEvent>,
+    chains: Res<PerBoard<Chain>>,
+    size: Res<BoardSize>,
+    mut shake: ResMut<ScreenShake>,
+    my_assets: Res<MyAssets>,
+    effects: Res<Effects>,
+    palette: Res<Palette>,
+) {
+    let mut rng = rand::thread_rng();
+    let mut cleared = 0;
+    let mut chain = 0;
+    for ev in events.iter() {
+        cleared += 1;
+        chain = chain.max(chains[ev.board].length);
+        if !effects.particles() {
+            continue;
+        }
+
+        // Burst of particles in the block's color
+        let translation = get_board_translation(&size, ev.board, &ev.position);
+        let color = palette.sprite_color(ev.color, ev.kind);
+        for _ in 0..PARTICLES_PER_BLOCK {
+            let angle = rng.gen_range(0.0..std::f32::consts::TAU);
+            let speed = rng.gen_range(100.0..350.0);
+            let size = rng.gen_range(6.0..14.0);
+            commands
+                .spawn_bundle(SpriteBundle {
+                    sprite: Sprite {
+                        color,
+                        custom_size: Some(Vec2::splat(size)),
+                        ..default()
+                    },
+                    transform: Transform::from_xyz(translation.x, translation.y, 15.0),
+                    ..default()
+                })
+                .insert(Particle {
+                    velocity: Vec2::new(angle.cos(), angle.sin()) * speed,
+                    timer: Timer::from_seconds(
+                        rng.gen_range(PARTICLE_LIFETIME / 2.0..PARTICLE_LIFETIME),
+                        false,
+                    ),
+                })
+                .insert(GameObject);
+        }
+
+        // Points of the block float up from it
+        commands
+            .spawn_bundle(Text2dBundle {
+                text: Text::from_section(format!("{:+}", ev.points), my_assets.text_style.clone())
+                    .with_alignment(TextAlignment::CENTER),
+                transform: Transform::from_xyz(translation.x, translation.y, 20.0),
+                ..default()
+            })
+            .insert(ScorePopup(Timer::from_seconds(POPUP_LIFETIME, false)))
+            .insert(GameObject);
+    }
+
+    if cleared == 0 || !effects.motion() {
+        return;
+    }
+
+    // Longer chains shake the screen harder
+    shake.strength = (SHAKE_PER_CHAIN * chain as f32).min(MAX_SHAKE);
+    shake.timer.reset();
+
+    // Several blocks cleared at once flash the boards
+    if cleared > 1 {
+        commands
+            .spawn_bundle(NodeBundle {
+                color: UiColor(Color::rgba(1.0, 1.0, 1.0, 0.0)),
+                ..default()
+            })
+            .insert(Flash(Timer::from_seconds(FLASH_DURATION, false)))
+            .insert(BoardArea)
+            .insert(GameObject);
+    }
+}
+
+/// System for moving and fading out the particles
+fn update_particles(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut Particle, &mut Transform, &mut Sprite)>,
+) {
+    let delta = time.delta_seconds();
+    for (entity, mut particle, mut transform, mut sprite) in query.iter_mut() {
+        if particle.timer.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        particle.velocity.y -= PARTICLE_GRAVITY * delta;
+        transform.translation += (particle.velocity * delta).extend(0.0);
+        sprite.color.set_a(particle.timer.percent_left());
+    }
+}
+
+/// System for moving up and fading out the score popups
+fn update_popups(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut ScorePopup, &mut Transform, &mut Text)>,
+) {
+    for (entity, mut popup, mut transform, mut text) in query.iter_mut() {
+        if popup.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        transform.translation.y += POPUP_SPEED * time.delta_seconds();
+        text.sections[0].style.color.set_a(popup.0.percent_left());
+    }
+}
+
+/// System for fading out the flash
+fn update_flash(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut Flash, &mut UiColor)>,
+) {
+    for (entity, mut flash, mut color) in query.iter_mut() {
+        if flash.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        color.0.set_a(0.5 * flash.0.percent_left());
+    }
+}
+
+/// System for shaking the screen. The shake fades out over its duration.
+fn shake_screen(time: Res<Time>, mut shake: ResMut<ScreenShake>, mut offset: ResMut<CameraOffset>) {
+    if shake.strength == 0.0 {
+        return;
+    }
+
+    if shake.timer.tick(time.delta()).finished() {
+        shake.strength = 0.0;
+        offset.0 = Vec2::ZERO;
+    } else {
+        let strength = shake.strength * shake.timer.percent_left();
+        let mut rng = rand::thread_rng();
+        offset.0 = Vec2::new(
+            rng.gen_range(-1.0..=1.0) * strength,
+            rng.gen_range(-1.0..=1.0) * strength,
+        );
+    }
+}
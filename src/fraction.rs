@@ -0,0 +1,329 @@
+//!
+//! Exact rational numbers used as block numbers
+//!
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+/// Rational number which is always kept in its lowest terms with a positive denominator.
+/// Integers simply have denominator of 1.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Fraction {
+    numerator: i64,
+    denominator: i64,
+}
+
+/// Greatest common divisor
+fn gcd(a: i128, b: i128) -> i128 {
+    let (mut a, mut b) = (a.abs(), b.abs());
+    while b != 0 {
+        let t = a % b;
+        a = b;
+        b = t;
+    }
+    a
+}
+
+/// Largest numerator or denominator which is stored exactly
+const LIMIT: i128 = i64::MAX as i128;
+
+impl Fraction {
+    pub const ZERO: Fraction = Fraction {
+        numerator: 0,
+        denominator: 1,
+    };
+
+    /// Reduces the fraction into its lowest terms with positive denominator. Fractions which
+    /// don't fit into `i64` even then are approximated.
+    fn reduce(numerator: i128, denominator: i128) -> Fraction {
+        assert!(denominator != 0, "Fraction denominator is zero");
+        let sign = denominator.signum();
+        let divisor = gcd(numerator, denominator).max(1);
+        let (numerator, denominator) = (sign * numerator / divisor, sign * denominator / divisor);
+        if numerator.abs() <= LIMIT && denominator <= LIMIT {
+            Fraction {
+                numerator: numerator as i64,
+                denominator: denominator as i64,
+            }
+        } else {
+            Fraction::approximate(numerator, denominator)
+        }
+    }
+
+    /// Returns the nearest fraction whose numerator and denominator fit into `i64`. Values beyond
+    /// the range of `i64` are clamped to `i64::MAX` or `-i64::MAX`. Values of the game never get
+    /// this large.
+    fn approximate(numerator: i128, denominator: i128) -> Fraction {
+        let sign = numerator.signum() as i64;
+        let (mut n, mut d) = (numerator.abs(), denominator);
+        if n / d >= LIMIT {
+            return Fraction::from(sign * i64::MAX);
+        }
+
+        // Convergents of the continued fraction, until the next one doesn't fit anymore
+        let (mut p0, mut q0, mut p1, mut q1) = (0, 1, 1, 0);
+        loop {
+            let a = n / d;
+            let next = a
+                .checked_mul(p1)
+                .zip(a.checked_mul(q1))
+                .map(|(p, q)| (p0 + p, q0 + q))
+                .filter(|(p, q)| *p <= LIMIT && *q <= LIMIT);
+            match next {
+                Some((p2, q2)) => {
+                    (p0, q0, p1, q1) = (p1, q1, p2, q2);
+                    (n, d) = (d, n - a * d);
+                }
+                None => break,
+            }
+        }
+
+        // The largest semiconvergent which fits is on the other side of the value. It's nearer
+        // than the latest convergent if the remaining term `n / d` is small enough.
+        let k = ((LIMIT - q0) / q1).min(if p1 > 0 { (LIMIT - p0) / p1 } else { LIMIT });
+        let (p, q) = if (n as f64 / d as f64) * (q1 as f64) < (q0 + 2 * k * q1) as f64 {
+            (p0 + k * p1, q0 + k * q1)
+        } else {
+            (p1, q1)
+        };
+        Fraction {
+            numerator: sign * p as i64,
+            denominator: q as i64,
+        }
+    }
+
+    /// Returns `true` if the fraction is a whole number
+    pub fn is_integer(&self) -> bool {
+        self.denominator == 1
+    }
+
+    /// Returns the value as integer if the fraction is a whole number
+    pub fn to_integer(self) -> Option<i64> {
+        if self.is_integer() {
+            Some(self.numerator)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the integer part (rounded towards zero)
+    pub fn trunc(&self) -> i64 {
+        self.numerator / self.denominator
+    }
+
+    /// Returns the nearest floating point value
+    pub fn to_f64(self) -> f64 {
+        self.numerator as f64 / self.denominator as f64
+    }
+
+    /// Returns the largest integer less than or equal to the fraction
+    pub fn floor(&self) -> i64 {
+        self.numerator.div_euclid(self.denominator)
+    }
+
+    /// Rounds the fraction into the integer away from zero
+    pub fn round_away_from_zero(&self) -> Fraction {
+        if self.is_integer() {
+            *self
+        } else if self.numerator > 0 {
+            Fraction::from(self.floor() + 1)
+        } else {
+            Fraction::from(self.floor())
+        }
+    }
+
+    /// Returns the non-negative remainder of the division. Panics if `rhs` is zero.
+    pub fn rem_euclid(&self, rhs: Fraction) -> Fraction {
+        let divisor = if rhs < Fraction::ZERO { -rhs } else { rhs };
+        *self - divisor * Fraction::from((*self / divisor).floor())
+    }
+}
+
+impl From<i64> for Fraction {
+    fn from(value: i64) -> Self {
+        Fraction {
+            numerator: value,
+            denominator: 1,
+        }
+    }
+}
+
+impl From<i32> for Fraction {
+    fn from(value: i32) -> Self {
+        Fraction::from(value as i64)
+    }
+}
+
+impl Add for Fraction {
+    type Output = Fraction;
+    fn add(self, rhs: Fraction) -> Fraction {
+        Fraction::reduce(
+            self.numerator as i128 * rhs.denominator as i128
+                + rhs.numerator as i128 * self.denominator as i128,
+            self.denominator as i128 * rhs.denominator as i128,
+        )
+    }
+}
+
+impl Sub for Fraction {
+    type Output = Fraction;
+    fn sub(self, rhs: Fraction) -> Fraction {
+        self + -rhs
+    }
+}
+
+impl Mul for Fraction {
+    type Output = Fraction;
+    fn mul(self, rhs: Fraction) -> Fraction {
+        Fraction::reduce(
+            self.numerator as i128 * rhs.numerator as i128,
+            self.denominator as i128 * rhs.denominator as i128,
+        )
+    }
+}
+
+impl Div for Fraction {
+    type Output = Fraction;
+    /// Panics if `rhs` is zero
+    fn div(self, rhs: Fraction) -> Fraction {
+        Fraction::reduce(
+            self.numerator as i128 * rhs.denominator as i128,
+            self.denominator as i128 * rhs.numerator as i128,
+        )
+    }
+}
+
+impl Neg for Fraction {
+    type Output = Fraction;
+    /// Saturates at `i64::MAX` since `i64::MIN` has no positive counterpart
+    fn neg(self) -> Fraction {
+        Fraction {
+            numerator: self.numerator.checked_neg().unwrap_or(i64::MAX),
+            denominator: self.denominator,
+        }
+    }
+}
+
+impl Ord for Fraction {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.numerator as i128 * other.denominator as i128)
+            .cmp(&(other.numerator as i128 * self.denominator as i128))
+    }
+}
+
+impl PartialOrd for Fraction {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Whole numbers are shown as integers (`7`) and the rest as fractions (`7/3`)
+impl fmt::Display for Fraction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_integer() {
+            write!(f, "{}", self.numerator)
+        } else {
+            write!(f, "{}/{}", self.numerator, self.denominator)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fraction(numerator: i64, denominator: i64) -> Fraction {
+        Fraction::from(numerator) / Fraction::from(denominator)
+    }
+
+    #[test]
+    fn reduce_to_lowest_terms() {
+        let half = fraction(6, 12);
+        assert_eq!((half.numerator, half.denominator), (1, 2));
+        assert_eq!(fraction(8, 4), Fraction::from(2));
+        assert!(fraction(8, 4).is_integer());
+        assert_eq!(fraction(0, 5), Fraction::ZERO);
+        assert_eq!(fraction(4, 6).to_string(), "2/3");
+    }
+
+    #[test]
+    fn sign_is_kept_in_numerator() {
+        let negative = fraction(3, -6);
+        assert_eq!((negative.numerator, negative.denominator), (-1, 2));
+        assert_eq!(fraction(-3, -6), fraction(1, 2));
+        assert_eq!(-fraction(1, 2), negative);
+        assert_eq!(negative.to_string(), "-1/2");
+        assert_eq!(negative.floor(), -1);
+        assert_eq!(negative.trunc(), 0);
+        assert_eq!(negative.round_away_from_zero(), Fraction::from(-1));
+    }
+
+    #[test]
+    fn rem_euclid_is_non_negative() {
+        assert_eq!(
+            Fraction::from(7).rem_euclid(Fraction::from(3)),
+            Fraction::from(1)
+        );
+        assert_eq!(
+            Fraction::from(-7).rem_euclid(Fraction::from(3)),
+            Fraction::from(2)
+        );
+        assert_eq!(
+            Fraction::from(-7).rem_euclid(Fraction::from(-3)),
+            Fraction::from(2)
+        );
+        assert_eq!(fraction(7, 2).rem_euclid(Fraction::from(2)), fraction(3, 2));
+        assert_eq!(fraction(-1, 3).rem_euclid(fraction(1, 2)), fraction(1, 6));
+    }
+
+    #[test]
+    fn ordering() {
+        let mut values = vec![
+            fraction(1, 2),
+            fraction(-1, 2),
+            fraction(1, 3),
+            Fraction::ZERO,
+        ];
+        values.sort();
+        assert_eq!(
+            values,
+            vec![
+                fraction(-1, 2),
+                Fraction::ZERO,
+                fraction(1, 3),
+                fraction(1, 2)
+            ]
+        );
+        assert!(fraction(-7, 3) < Fraction::from(-2));
+        assert_eq!(fraction(5, 3).max(fraction(3, 2)), fraction(5, 3));
+    }
+
+    #[test]
+    fn overflow_clamps_whole_value() {
+        let max = Fraction::from(i64::MAX);
+        assert_eq!(max * Fraction::from(2), max);
+        assert_eq!(max + fraction(1, 2), max);
+        assert_eq!(-max * Fraction::from(3), -max);
+        assert!((max * Fraction::from(2)).is_integer());
+    }
+
+    #[test]
+    fn overflow_approximates_with_bounded_denominator() {
+        // Exact sum would need a denominator of about i64::MAX squared
+        let a = fraction(1, i64::MAX);
+        let b = fraction(1, i64::MAX - 1);
+        let sum = a + b;
+        assert!(fraction(2, i64::MAX) <= sum && sum <= fraction(2, i64::MAX - 1));
+        assert!(-sum < Fraction::ZERO);
+
+        // Nearest fraction to 1/3 + 1/i64::MAX within the limits
+        let third = fraction(1, 3) + a;
+        assert!(third > fraction(1, 3));
+        assert!((third.to_f64() - 1.0 / 3.0).abs() < 1e-15);
+
+        // Small values don't collapse into zero with a bad denominator
+        let tiny = a * fraction(1, 3);
+        assert!(tiny >= Fraction::ZERO && tiny <= a);
+        assert!(tiny.denominator > 0);
+    }
+}
@@ -1,5 +1,13 @@
-use crate::in_game::HudLayer;
+use crate::ai::AiPlayer;
+use crate::audio::{PlaySfxEvent, Sfx};
+use crate::board::PerBoard;
+use crate::controls::MenuAction;
+use crate::hud::HudLayer;
+use crate::localization::{Language, TextKey};
 use crate::prelude::*;
+use crate::rules::Versus;
+use crate::stats::{is_tracked, GameStats};
+use crate::versus::{Outcome, VersusResult};
 use bevy::prelude::*;
 
 /// Identifier for the background/overlay layer
@@ -26,19 +34,41 @@ impl Plugin for GameOverPlugin {
 fn on_enter(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
-    score: Res<Score>,
+    scores: Res<PerBoard<Score>>,
     mut high_score: ResMut<HighScore>,
     mut query: Query<(Entity, &mut UiColor), With<HudLayer>>,
+    mut audio_events: EventWriter<PlaySfxEvent>,
+    ai: Res<AiPlayer>,
+    versus: Res<Versus>,
+    result: Res<VersusResult>,
+    stats: Res<GameStats>,
+    language: Res<Language>,
 ) {
     println!("Enter GameState::GameOver");
+    audio_events.send(PlaySfxEvent(Sfx::GameOver));
 
-    let txt: String = if score.0 > high_score.0 {
-        format!("GAME OVER\r\n\r\n* NEW HIGHSCORE *")
+    // High score is kept only for single player games
+    let score = &scores[0];
+    let game_over = language.get(TextKey::GameOver);
+    let txt: String = if *versus != Versus::Off {
+        get_versus_result_text(*versus, result.outcome, &scores, *language)
+    } else if ai.assisted {
+        format!("{}\r\n\r\n{}", game_over, language.get(TextKey::Autopilot))
+    } else if score.0 > high_score.0 {
+        format!(
+            "{}\r\n\r\n{}",
+            game_over,
+            language.get(TextKey::NewHighScore)
+        )
     } else {
-        format!("GAME OVER\r\n\r\nHi: {}", high_score.0)
+        format!(
+            "{}\r\n\r\n{}",
+            game_over,
+            language.format(TextKey::HighScore, &[&high_score.0])
+        )
     };
 
-    if score.0 > high_score.0 {
+    if score.0 > high_score.0 && !ai.assisted && *versus == Versus::Off {
         println!("New high_score: {}", high_score.0);
         high_score.0 = score.0;
     }
@@ -51,16 +81,33 @@ fn on_enter(
             alpha: 0.95,
         });
 
+        // Statistics of the game are shown below the result
+        let stats_txt = if is_tracked(*versus) {
+            format!("\r\n\r\n{}", stats.0.lines(*language).join("\r\n"))
+        } else {
+            String::new()
+        };
+
         let text = commands
             .spawn_bundle(
-                TextBundle::from_section(
-                    txt,
-                    TextStyle {
-                        font: asset_server.load("fonts/04b_30.ttf"),
-                        font_size: 28.0,
-                        color: Color::WHITE,
-                    },
-                )
+                TextBundle::from_sections([
+                    TextSection::new(
+                        txt,
+                        TextStyle {
+                            font: asset_server.load("fonts/04b_30.ttf"),
+                            font_size: 28.0,
+                            color: Color::WHITE,
+                        },
+                    ),
+                    TextSection::new(
+                        stats_txt,
+                        TextStyle {
+                            font: asset_server.load("fonts/04b_30.ttf"),
+                            font_size: 14.0,
+                            color: Color::WHITE,
+                        },
+                    ),
+                ])
                 .with_text_alignment(TextAlignment::CENTER)
                 .with_style(Style {
                     align_items: AlignItems::Center,
@@ -75,21 +122,45 @@ fn on_enter(
     }
 }
 
+/// Helper function to get the winner and the scores of a versus game. There is no winner if the
+/// game was quit before either board filled up, or if both boards filled up at the same time.
+fn get_versus_result_text(
+    versus: Versus,
+    outcome: Option<Outcome>,
+    scores: &PerBoard<Score>,
+    language: Language,
+) -> String {
+    let winner = match (versus, outcome) {
+        (_, None) => language.get(TextKey::GameOver),
+        (_, Some(Outcome::Draw)) => language.get(TextKey::Draw),
+        (Versus::Computer, Some(Outcome::Lost(1))) | (Versus::Network, Some(Outcome::Lost(1))) => {
+            language.get(TextKey::YouWin)
+        }
+        (_, Some(Outcome::Lost(loser))) => language.format(
+            TextKey::Wins,
+            &[&versus.player_name(1 - loser, language).to_uppercase()],
+        ),
+    };
+    let scores: Vec<String> = scores.0.iter().map(|score| score.0.to_string()).collect();
+    format!("{}\r\n\r\n{}", winner, scores.join(" - "))
+}
+
 fn on_exit(mut commands: Commands, query: Query<Entity, With<GameObject>>) {
     for entity in query.iter() {
         commands.entity(entity).despawn_recursive();
     }
 }
 
-/// Switch to `GameState::Menu` if Return key is pressed
+/// Switch to `GameState::Menu` if Return or Esc key is pressed
 fn back_to_menu_on_enter(
-    mut input: ResMut<Input<KeyCode>>,
+    mut actions: ResMut<Input<MenuAction>>,
     mut game_state: ResMut<State<GameState>>,
 ) {
-    if input.just_pressed(KeyCode::Return) || input.just_pressed(KeyCode::Escape) {
+    if actions.just_pressed(MenuAction::Select) || actions.just_pressed(MenuAction::Back) {
         game_state
             .set(GameState::Menu)
             .expect("Failed to change GameState:Menu");
-        input.reset(KeyCode::Return);
+        actions.reset(MenuAction::Select);
+        actions.reset(MenuAction::Back);
     }
 }
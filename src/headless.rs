@@ -0,0 +1,97 @@
+//!
+//! Game without a window, rendering or audio for the tests and benchmarks. Only the simulation of
+//! the boards is run, and the time advances by a fixed step on each update.
+//!
+use crate::ai::AiPlugin;
+use crate::audio::{MusicIntensity, PlaySfxEvent};
+use crate::board::BoardPreset;
+use crate::constants::prelude::*;
+use crate::controls::{ControlsPlugin, KeyBindings};
+use crate::in_game::{Animations, GameSeed, InGamePlugin, StartLevel};
+use crate::localization::Language;
+use crate::palette::{BlockSymbols, Palette};
+use crate::prelude::*;
+use crate::rules::{Adjacency, GameMode, Resolution, Versus};
+use crate::toast::ShowToastEvent;
+use crate::undo::UndoPlugin;
+use crate::versus::VersusPlugin;
+use crate::view::ViewLayout;
+use bevy::input::InputPlugin;
+use bevy::prelude::*;
+use bevy::time::{create_time_channels, TimeSender};
+use bevy::utils::Instant;
+use std::time::Duration;
+
+/// Time the game advances on each update (in seconds)
+const FRAME_TIME: f32 = 1.0 / 60.0;
+
+/// Game running the simulation of the boards without a window
+pub struct HeadlessGame {
+    pub app: App,
+    clock: TimeSender,
+    now: Instant,
+}
+
+impl HeadlessGame {
+    /// Creates a game with the given seed and rules. The game starts on the first update.
+    pub fn new(seed: u64, mode: GameMode, versus: Versus) -> Self {
+        let (clock, receiver) = create_time_channels();
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(InputPlugin)
+            .insert_resource(receiver)
+            .init_resource::<Windows>()
+            .insert_resource(ViewLayout {
+                boards: 1,
+                panels: false,
+                window: Vec2::new(WINDOW_WIDTH, WINDOW_HEIGHT),
+            })
+            .insert_resource(mode)
+            .insert_resource(Adjacency::Flood)
+            .insert_resource(Resolution::First)
+            .insert_resource(versus)
+            .insert_resource(Language::English)
+            .insert_resource(KeyBindings::default())
+            .insert_resource(StartLevel::default())
+            .insert_resource(Animations(false))
+            .insert_resource(BoardPreset::Classic)
+            .insert_resource(MusicIntensity::default())
+            .insert_resource(Palette::Default)
+            .insert_resource(BlockSymbols(false))
+            .insert_resource(MyAssets {
+                text_style: TextStyle::default(),
+                block_texture: Handle::default(),
+                edge_texture: Handle::default(),
+            })
+            .add_event::<PlaySfxEvent>()
+            .add_event::<ShowToastEvent>()
+            .add_state(GameState::InGame)
+            .add_plugin(ControlsPlugin)
+            .add_plugin(AiPlugin)
+            .add_plugin(InGamePlugin)
+            .add_plugin(UndoPlugin)
+            .add_plugin(VersusPlugin)
+            .insert_resource(GameSeed(Some(seed)));
+
+        HeadlessGame {
+            app,
+            clock,
+            now: Instant::now(),
+        }
+    }
+
+    /// Advances the game by one frame
+    pub fn update(&mut self) {
+        self.now += Duration::from_secs_f32(FRAME_TIME);
+        self.clock
+            .0
+            .send(self.now)
+            .expect("Failed to advance the time");
+        self.app.update();
+    }
+
+    /// Returns `true` once the game is over
+    pub fn is_over(&self) -> bool {
+        *self.app.world.resource::<State<GameState>>().current() != GameState::InGame
+    }
+}
@@ -0,0 +1,167 @@
+//!
+//! Best scores of the single player games in each game mode. The high score of the selected mode
+//! is shown during the game, and the table of all the modes can be viewed from the main menu.
+//! High scores are saved to disk whenever a new one is set.
+//!
+use crate::controls::MenuAction;
+use crate::localization::{Language, TextKey};
+use crate::prelude::*;
+use crate::rules::GameMode;
+use crate::storage::{load_values, save_values};
+use crate::view::BoardArea;
+use bevy::prelude::*;
+
+/// Name of the file holding the high scores
+const HIGH_SCORES_FILE: &str = "high_scores.txt";
+
+/// This resource holds the high score of each game mode (in the order of `GameMode::ALL`)
+pub struct HighScores([i32; GameMode::ALL.len()]);
+
+impl HighScores {
+    /// Returns the high score of the game mode
+    pub fn get(&self, mode: GameMode) -> i32 {
+        GameMode::ALL
+            .iter()
+            .position(|m| *m == mode)
+            .map_or(0, |i| self.0[i])
+    }
+
+    /// Loads the high scores from disk
+    fn load() -> HighScores {
+        let values = load_values(HIGH_SCORES_FILE);
+        let mut scores = [0; GameMode::ALL.len()];
+        for (score, mode) in scores.iter_mut().zip(GameMode::ALL) {
+            *score = values
+                .get(&get_mode_key(mode))
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(0);
+        }
+        HighScores(scores)
+    }
+
+    /// Saves the high scores to disk
+    fn save(&self) {
+        let values: Vec<(String, String)> = GameMode::ALL
+            .iter()
+            .zip(self.0)
+            .map(|(mode, score)| (get_mode_key(*mode), score.to_string()))
+            .collect();
+        save_values(HIGH_SCORES_FILE, &values);
+    }
+}
+
+/// Helper function to get the key of the game mode in the saved file
+fn get_mode_key(mode: GameMode) -> String {
+    format!("{:?}", mode).to_lowercase()
+}
+
+/// Bevy Plugin for the high scores
+pub struct HighScoresPlugin;
+impl Plugin for HighScoresPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(GameState::InGame).with_system(on_game_start))
+            .add_system(record_high_score)
+            .add_system_set(SystemSet::on_enter(GameState::HighScores).with_system(on_enter))
+            .add_system_set(SystemSet::on_exit(GameState::HighScores).with_system(on_exit))
+            .add_system_set(SystemSet::on_update(GameState::HighScores).with_system(back_to_menu))
+            .insert_resource(HighScores::load());
+    }
+}
+
+/// Called once as the game is started. The high score of the selected mode is the one to beat.
+fn on_game_start(
+    mut high_score: ResMut<HighScore>,
+    high_scores: Res<HighScores>,
+    mode: Res<GameMode>,
+) {
+    high_score.0 = high_scores.get(*mode);
+}
+
+/// System for saving the new high score of the selected mode
+fn record_high_score(
+    high_score: Res<HighScore>,
+    mut high_scores: ResMut<HighScores>,
+    mode: Res<GameMode>,
+) {
+    if !high_score.is_changed() || high_score.0 <= high_scores.get(*mode) {
+        return;
+    }
+
+    if let Some(i) = GameMode::ALL.iter().position(|m| *m == *mode) {
+        high_scores.0[i] = high_score.0;
+        high_scores.save();
+    }
+}
+
+/// Called once when switching to `GameState::HighScores`
+fn on_enter(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    high_scores: Res<HighScores>,
+    language: Res<Language>,
+) {
+    println!("Enter GameState::HighScores");
+
+    let node = commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            color: UiColor(Color::NONE),
+            ..default()
+        })
+        .insert(MenuNode)
+        .insert(BoardArea)
+        .id();
+
+    let mut lines = vec![language.get(TextKey::HighScores), String::new()];
+    for mode in GameMode::ALL {
+        lines.push(format!(
+            "{}: {}",
+            language.get(TextKey::GameMode(mode)),
+            high_scores.get(mode)
+        ));
+    }
+    lines.push(String::new());
+    lines.push(language.get(TextKey::Back));
+
+    let text = commands
+        .spawn_bundle(
+            TextBundle::from_section(
+                lines.join("\n"),
+                TextStyle {
+                    font: asset_server.load("fonts/04b_30.ttf"),
+                    font_size: 16.0,
+                    color: Color::BLACK,
+                },
+            )
+            .with_text_alignment(TextAlignment::CENTER),
+        )
+        .id();
+
+    commands.entity(node).push_children(&[text]);
+}
+
+/// Called once when switching from `GameState::HighScores`
+fn on_exit(mut commands: Commands, query: Query<Entity, With<MenuNode>>) {
+    println!("Exit GameState::HighScores");
+
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Switch back to `GameState::Menu` if Esc or Return key is pressed
+fn back_to_menu(mut actions: ResMut<Input<MenuAction>>, mut game_state: ResMut<State<GameState>>) {
+    if actions.just_pressed(MenuAction::Back) || actions.just_pressed(MenuAction::Select) {
+        game_state
+            .set(GameState::Menu)
+            .expect("Failed to change GameState::Menu");
+        actions.reset(MenuAction::Back);
+        actions.reset(MenuAction::Select);
+    }
+}
@@ -0,0 +1,262 @@
+//!
+//! Hints suggesting the best placement for the dropping block
+//!
+use crate::ai::{landing_position, AiPlayer, Situation};
+use crate::board::{find_drop_neighbors, BlockMap, PerBoard};
+use crate::constants::prelude::*;
+use crate::fraction::Fraction;
+use crate::in_game::{
+    get_operator, get_translation, BlockColor, BlockKind, BlockParams, BlockPosition,
+    DroppingBlock, Level, NextBlock, Number, Operation, SolidBlock,
+};
+use crate::localization::{Language, TextKey};
+use crate::palette::Palette;
+use crate::prelude::*;
+use crate::rules::{GameRules, Versus};
+use crate::toast::ShowToastEvent;
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+/// Number of free hints in each game
+const HINTS_PER_GAME: u32 = 3;
+
+/// Score penalty of each hint once the free hints are used
+const HINT_PENALTY: i32 = 10;
+
+/// Maximum number of calculations explained in a hint
+const MAX_EXPLAINED: usize = 3;
+
+/// This resource holds the number of hints used in the current game
+#[derive(Default)]
+pub struct Hints {
+    pub used: u32,
+}
+
+/// Highlight of the suggested column. Holds the dropping block the hint was given for.
+#[derive(Component)]
+struct HintMarker(Entity);
+
+/// Bevy Plugin for the hint system
+pub struct HintPlugin;
+impl Plugin for HintPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(GameState::InGame).with_system(on_enter))
+            .add_system_set(
+                SystemSet::on_update(GameState::InGame)
+                    .with_system(show_hint)
+                    .with_system(remove_old_hints),
+            )
+            .insert_resource(Hints::default());
+    }
+}
+
+/// Called once as the game is started
+fn on_enter(mut hints: ResMut<Hints>) {
+    hints.used = 0;
+}
+
+/// Helper function to describe a calculation, e.g. `7 + 3 = 10`
+fn describe_calculation(
+    neighbor: Fraction,
+    operation: Operation,
+    number: Fraction,
+    result: Fraction,
+) -> String {
+    match operation {
+        Operation::SQUARE | Operation::NEGATE | Operation::SWAP => {
+            format!("{}{} = {}", get_operator(operation), neighbor, result)
+        }
+        Operation::SET => format!("{} -> {}", neighbor, result),
+        _ => format!(
+            "{} {} {} = {}",
+            neighbor,
+            get_operator(operation),
+            number,
+            result
+        ),
+    }
+}
+
+/// Explains what happens when the block is dropped with given color at `pos`
+fn explain_placement(
+    situation: &Situation,
+    block: &BlockParams,
+    color: BlockColor,
+    pos: &Coords,
+    language: Language,
+) -> Vec<String> {
+    let rules = situation.rules;
+    let neighbors =
+        find_drop_neighbors(situation.block_map, pos, color, block.kind, rules.adjacency);
+    match block.kind {
+        BlockKind::STONE => return vec![language.get(TextKey::KeepStoneLow)],
+        BlockKind::BOMB => return vec![language.format(TextKey::BombClears, &[&neighbors.len()])],
+        BlockKind::NORMAL | BlockKind::WILDCARD => {}
+    }
+    if neighbors.is_empty() {
+        return vec![language.get(TextKey::NothingToCombine)];
+    }
+
+    let numbers: Vec<Fraction> = neighbors
+        .iter()
+        .filter_map(|(_, entity)| situation.numbers.get(entity).copied())
+        .collect();
+    let (results, dropped) = rules.combine(block.number, block.operation, &numbers);
+    let target = rules.target_rule(situation.level);
+
+    // Calculations reaching the target are explained first
+    let mut lines: Vec<(bool, String)> = numbers
+        .iter()
+        .zip(results.iter())
+        .map(|(neighbor, result)| {
+            let clears = target.is_target(*result, situation.level);
+            let mut line = describe_calculation(*neighbor, block.operation, block.number, *result);
+            if clears {
+                line = language.format(TextKey::Clears, &[&line]);
+            }
+            (clears, line)
+        })
+        .collect();
+    if let Some(result) = dropped {
+        if target.is_target(result, situation.level) {
+            lines.push((
+                true,
+                language.format(TextKey::DroppedBlockClears, &[&result]),
+            ));
+        }
+    }
+    lines.sort_by_key(|(clears, _)| !*clears);
+    lines
+        .into_iter()
+        .take(MAX_EXPLAINED)
+        .map(|(_, line)| line)
+        .collect()
+}
+
+/// System for showing a hint when H key is pressed
+fn show_hint(
+    mut commands: Commands,
+    input: Res<Input<KeyCode>>,
+    mut hints: ResMut<Hints>,
+    mut scores: ResMut<PerBoard<Score>>,
+    ai: Res<AiPlayer>,
+    versus: Res<Versus>,
+    block_maps: Res<PerBoard<BlockMap>>,
+    rules: Res<GameRules>,
+    levels: Res<PerBoard<Level>>,
+    next_blocks: Res<PerBoard<NextBlock>>,
+    dropping_query: Query<
+        (
+            Entity,
+            &BlockPosition,
+            &Number,
+            &BlockColor,
+            &BlockKind,
+            &Operation,
+        ),
+        With<DroppingBlock>,
+    >,
+    solid_query: Query<(Entity, &Number), With<SolidBlock>>,
+    marker_query: Query<&HintMarker>,
+    mut toast_events: EventWriter<ShowToastEvent>,
+    (language, palette): (Res<Language>, Res<Palette>),
+) {
+    // No hints while the AI is playing, in a versus game or if the hint is already shown
+    if !input.just_pressed(KeyCode::H) || ai.enabled || *versus != Versus::Off {
+        return;
+    }
+    let block_map = &block_maps[0];
+    if let Ok((entity, pos, number, color, kind, operation)) = dropping_query.get_single() {
+        if marker_query.iter().any(|marker| marker.0 == entity) {
+            return;
+        }
+
+        let block = BlockParams {
+            number: number.0,
+            color: *color,
+            operation: *operation,
+            kind: *kind,
+        };
+        let numbers: HashMap<Entity, Fraction> = solid_query
+            .iter()
+            .map(|(entity, number)| (entity, number.0))
+            .collect();
+        let situation = Situation {
+            block_map,
+            numbers: &numbers,
+            rules: &rules,
+            level: levels[0].number,
+            next: next_blocks[0].0.as_ref(),
+        };
+
+        let (column, color) = match situation.best_placement(&block, &pos.0) {
+            Some(placement) => placement,
+            None => return,
+        };
+        let landing = match landing_position(block_map, &pos.0, column) {
+            Some(landing) => landing,
+            None => return,
+        };
+
+        // Free hints first, then each hint costs points
+        hints.used += 1;
+        let cost = if hints.used > HINTS_PER_GAME {
+            scores[0].0 = (scores[0].0 - HINT_PENALTY).max(0);
+            language.format(TextKey::HintCost, &[&HINT_PENALTY])
+        } else {
+            language.format(TextKey::HintsLeft, &[&(HINTS_PER_GAME - hints.used)])
+        };
+
+        let mut lines = match kind {
+            BlockKind::NORMAL | BlockKind::BOMB => {
+                vec![language.format(TextKey::Hint, &[&language.get(TextKey::Color(color))])]
+            }
+            BlockKind::WILDCARD | BlockKind::STONE => vec![language.get(TextKey::HintDropHere)],
+        };
+        lines.extend(explain_placement(
+            &situation, &block, color, &landing, *language,
+        ));
+        lines.push(cost);
+        toast_events.send(ShowToastEvent(lines.join("\n")));
+
+        // Highlight the column from the landing position up to the dropping block
+        let mut highlight = match kind {
+            BlockKind::NORMAL | BlockKind::BOMB => palette.color(color),
+            BlockKind::WILDCARD | BlockKind::STONE => Color::WHITE,
+        };
+        highlight.set_a(0.3);
+        for y in landing.y..=pos.0.y {
+            let cell = Coords::new(column, y);
+            let mut transform =
+                Transform::from_translation(get_translation(&block_map.size(), &cell));
+            transform.translation.z = 0.5;
+            commands
+                .spawn_bundle(SpriteBundle {
+                    sprite: Sprite {
+                        color: highlight,
+                        custom_size: Some(Vec2::splat(BLOCK_SIZE)),
+                        ..default()
+                    },
+                    transform,
+                    ..default()
+                })
+                .insert(GameObject)
+                .insert(BlockPosition(cell))
+                .insert(HintMarker(entity));
+        }
+    }
+}
+
+/// System for removing the highlight once the block has dropped
+fn remove_old_hints(
+    mut commands: Commands,
+    marker_query: Query<(Entity, &HintMarker)>,
+    dropping_query: Query<Entity, With<DroppingBlock>>,
+) {
+    let current = dropping_query.get_single().ok();
+    for (entity, marker) in marker_query.iter() {
+        if Some(marker.0) != current {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
@@ -1,10 +1,23 @@
+use crate::controls::{KeyBindings, MenuAction, PlayerAction};
+use crate::in_game::BlockColor;
+use crate::localization::{Language, TextKey};
 use crate::prelude::*;
+use crate::view::BoardArea;
 use bevy::prelude::*;
 
+/// Pages of the how-to-play in the order they are shown
+const PAGES: [TextKey; 2] = [TextKey::HowToPlayControls, TextKey::HowToPlayRules];
+
+/// Identifier for the instructions text
 #[derive(Component)]
-struct ImgInstructions;
+struct InstructionsText;
+
+/// This resource holds the index of the page currently shown
+struct HowToPlayPage(usize);
 
-struct ReadyToPlay(bool);
+/// This resource holds the state which follows the last page: the game, or the main menu if
+/// how-to-play was opened from the menu
+pub struct AfterHowToPlay(pub GameState);
 
 /// Bevy plugin for showing how-to-play
 pub struct HowToPlayPlugin;
@@ -12,16 +25,65 @@ impl Plugin for HowToPlayPlugin {
     fn build(&self, app: &mut App) {
         app.add_system_set(SystemSet::on_enter(GameState::HowToPlay).with_system(on_enter))
             .add_system_set(SystemSet::on_exit(GameState::HowToPlay).with_system(on_exit))
-            .add_system_set(
-                SystemSet::on_update(GameState::HowToPlay).with_system(start_game_on_enter),
-            )
-            .insert_resource(ReadyToPlay(false));
+            .add_system_set(SystemSet::on_update(GameState::HowToPlay).with_system(turn_page))
+            .insert_resource(HowToPlayPage(0))
+            .insert_resource(AfterHowToPlay(GameState::InGame));
     }
 }
 
-/// Called once when switching to `GameState::Menu`
-fn on_enter(mut commands: Commands, my_assets: Res<MyAssets>) {
+/// Helper function to get the names of the keys bound to the actions
+fn get_key_names(bindings: &KeyBindings, actions: &[PlayerAction]) -> String {
+    let names: Vec<String> = actions
+        .iter()
+        .flat_map(|action| bindings.keys(*action))
+        .map(|key| format!("{:?}", key))
+        .collect();
+    names.join(" ")
+}
+
+/// Helper function to get the text of the how-to-play page. Controls are shown with the keys
+/// currently bound to the actions.
+fn get_page_text(language: Language, bindings: &KeyBindings, page: usize) -> String {
+    let text = match PAGES[page] {
+        TextKey::HowToPlayControls => language.format(
+            TextKey::HowToPlayControls,
+            &[
+                &get_key_names(bindings, &[PlayerAction::MoveLeft]),
+                &get_key_names(bindings, &[PlayerAction::MoveRight]),
+                &get_key_names(bindings, &[PlayerAction::SoftDrop]),
+                &get_key_names(bindings, &[PlayerAction::SwitchColor]),
+                &get_key_names(
+                    bindings,
+                    &[
+                        PlayerAction::SelectColor(BlockColor::BLUE),
+                        PlayerAction::SelectColor(BlockColor::PINK),
+                        PlayerAction::SelectColor(BlockColor::YELLOW),
+                        PlayerAction::SelectColor(BlockColor::GREEN),
+                    ],
+                ),
+                &get_key_names(bindings, &[PlayerAction::Hold]),
+            ],
+        ),
+        key => language.get(key),
+    };
+    format!(
+        "{}\n\n{}\n\n{}",
+        language.get(TextKey::HowToPlay),
+        text,
+        language.get(TextKey::Continue)
+    )
+}
+
+/// Called once when switching to `GameState::HowToPlay`
+fn on_enter(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut page: ResMut<HowToPlayPage>,
+    language: Res<Language>,
+    bindings: Res<KeyBindings>,
+) {
     println!("Enter GameState::HowToPlay");
+    page.0 = 0;
 
     let node = commands
         .spawn_bundle(NodeBundle {
@@ -29,16 +91,18 @@ fn on_enter(mut commands: Commands, my_assets: Res<MyAssets>) {
                 size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
                 position_type: PositionType::Absolute,
                 justify_content: JustifyContent::Center,
-                align_items: AlignItems::FlexEnd,
+                align_items: AlignItems::Center,
                 ..default()
             },
             color: UiColor(Color::NONE),
             ..default()
         })
         .insert(MenuNode)
+        .insert(BoardArea)
         .id();
 
-    let img = commands
+    // Logo picture
+    let logo = commands
         .spawn_bundle(ImageBundle {
             style: Style {
                 size: Size::new(Val::Px(300.0), Val::Auto),
@@ -46,48 +110,80 @@ fn on_enter(mut commands: Commands, my_assets: Res<MyAssets>) {
                 position: UiRect {
                     left: Val::Auto,
                     right: Val::Auto,
-                    top: Val::Px(150.0),
+                    top: Val::Px(60.0),
                     bottom: Val::Auto,
                 },
                 align_self: AlignSelf::Center,
                 ..default()
             },
-            image: UiImage(my_assets.how_to_play_1_texture.clone()),
+            image: asset_server.load("logo.png").into(),
             ..default()
         })
-        .insert(ImgInstructions)
         .id();
 
-    commands.entity(node).push_children(&[img]);
+    let text = commands
+        .spawn_bundle(
+            TextBundle::from_section(
+                get_page_text(*language, &bindings, 0),
+                TextStyle {
+                    font: asset_server.load("fonts/04b_30.ttf"),
+                    font_size: 16.0,
+                    color: Color::BLACK,
+                },
+            )
+            .with_text_alignment(TextAlignment::CENTER),
+        )
+        .insert(InstructionsText)
+        .id();
+
+    commands.entity(node).push_children(&[logo, text]);
 }
 
-/// Called once when switching from `GameState::Menu`
+/// Called once when switching from `GameState::HowToPlay`
 fn on_exit(mut commands: Commands, query: Query<Entity, With<MenuNode>>) {
-    println!("Exit GameState::Menu");
+    println!("Exit GameState::HowToPlay");
 
     for entity in query.iter() {
         commands.entity(entity).despawn_recursive();
     }
 }
 
-/// Switch to `GameState::InGame` if Return key is pressed
-fn start_game_on_enter(
-    mut input: ResMut<Input<KeyCode>>,
+/// Turn the pages with Left / Right keys. Return key shows the next page, and switches to the
+/// following state after the last page. Esc returns to the main menu.
+fn turn_page(
+    mut actions: ResMut<Input<MenuAction>>,
     mut game_state: ResMut<State<GameState>>,
-    mut query: Query<&mut UiImage, With<ImgInstructions>>,
-    mut ready_to_play: ResMut<ReadyToPlay>,
-    my_assets: Res<MyAssets>,
+    mut query: Query<&mut Text, With<InstructionsText>>,
+    mut page: ResMut<HowToPlayPage>,
+    after: Res<AfterHowToPlay>,
+    language: Res<Language>,
+    bindings: Res<KeyBindings>,
 ) {
-    if input.just_pressed(KeyCode::Return) {
-        if ready_to_play.0 == true {
-            game_state
-                .set(GameState::InGame)
-                .expect("Failed to change GameState:InGame");
-            input.reset(KeyCode::Return);
-            ready_to_play.0 = false;
-        } else if let Ok(mut img) = query.get_single_mut() {
-            img.0 = my_assets.how_to_play_2_texture.clone();
-            ready_to_play.0 = true;
+    let next_state = if actions.just_pressed(MenuAction::Back) {
+        Some(GameState::Menu)
+    } else if actions.just_pressed(MenuAction::Select) && page.0 + 1 >= PAGES.len() {
+        Some(after.0)
+    } else if (actions.just_pressed(MenuAction::Select) || actions.just_pressed(MenuAction::Right))
+        && page.0 + 1 < PAGES.len()
+    {
+        page.0 += 1;
+        None
+    } else if actions.just_pressed(MenuAction::Left) && page.0 > 0 {
+        page.0 -= 1;
+        None
+    } else {
+        return;
+    };
+
+    if let Some(next_state) = next_state {
+        game_state
+            .set(next_state)
+            .expect("Failed to change GameState");
+        actions.reset(MenuAction::Select);
+        actions.reset(MenuAction::Back);
+    } else {
+        for mut text in query.iter_mut() {
+            text.sections[0].value = get_page_text(*language, &bindings, page.0);
         }
     }
 }
@@ -0,0 +1,289 @@
+//!
+//! Side panel next to each board showing the score, level, elapsed time and the current target,
+//! together with previews of the next block and the block on hold
+//!
+use crate::board::{BoardId, PerBoard};
+use crate::constants::prelude::*;
+use crate::in_game::{get_dropping_block_text, BlockParams, GameTime, HeldBlock, Level, NextBlock};
+use crate::localization::{Language, TextKey};
+use crate::palette::Palette;
+use crate::prelude::*;
+use crate::rules::{GameRules, Versus};
+use crate::view::BoardArea;
+use bevy::prelude::*;
+
+/// Width of the side panel in the default window size (in pixels)
+const PANEL_PX: f32 = PANEL_WIDTH * WINDOW_HEIGHT / WORLD_HEIGHT;
+
+/// Size of the next and hold block previews (in pixels)
+const PREVIEW_SIZE: f32 = 48.0;
+
+/// Color of the labels in the side panel
+const LABEL_COLOR: Color = Color::rgb(0.3, 0.3, 0.3);
+
+/// Identifier for the HUD layer
+#[derive(Component)]
+pub struct HudLayer;
+
+/// Label of a value in the side panel. Labels follow the selected language.
+#[derive(Component)]
+struct HudLabel(TextKey);
+
+/// Value shown in the side panel
+#[derive(Component, Copy, Clone, PartialEq, Eq)]
+enum HudValue {
+    Score,
+    HighScore,
+    Level,
+    Time,
+    Cleared,
+    Target,
+}
+
+/// Block preview shown in the side panel
+#[derive(Component, Copy, Clone, PartialEq, Eq)]
+enum BlockPreview {
+    Next,
+    Hold,
+}
+
+/// Bevy Plugin for the side panels
+pub struct HudPlugin;
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(GameState::InGame).with_system(spawn_hud))
+            .add_system_set(
+                SystemSet::on_update(GameState::InGame)
+                    .with_system(update_hud_values)
+                    .with_system(update_hud_labels)
+                    .with_system(update_block_previews),
+            );
+    }
+}
+
+/// Helper function to spawn a text node of the side panel
+fn spawn_text(commands: &mut Commands, value: String, style: TextStyle) -> Entity {
+    commands
+        .spawn_bundle(TextBundle::from_section(value, style).with_style(Style {
+            margin: UiRect::new(Val::Px(0.0), Val::Px(0.0), Val::Px(2.0), Val::Px(2.0)),
+            max_size: Size::new(Val::Px(PANEL_PX - 16.0), Val::Undefined),
+            ..default()
+        }))
+        .id()
+}
+
+/// Helper function to spawn a preview of the next or held block
+fn spawn_preview(commands: &mut Commands, my_assets: &MyAssets, preview: BlockPreview) -> Entity {
+    let text = commands
+        .spawn_bundle(TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 12.0,
+                ..my_assets.text_style.clone()
+            },
+        ))
+        .id();
+    commands
+        .spawn_bundle(ImageBundle {
+            style: Style {
+                size: Size::new(Val::Px(PREVIEW_SIZE), Val::Px(PREVIEW_SIZE)),
+                margin: UiRect::new(Val::Px(0.0), Val::Px(0.0), Val::Px(4.0), Val::Px(4.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            image: UiImage(my_assets.block_texture.clone()),
+            color: UiColor(Color::NONE),
+            ..default()
+        })
+        .insert(preview)
+        .push_children(&[text])
+        .id()
+}
+
+/// Spawns the side panel of each board
+fn spawn_hud(
+    mut commands: Commands,
+    my_assets: Res<MyAssets>,
+    versus: Res<Versus>,
+    language: Res<Language>,
+) {
+    let hud = commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                ..default()
+            },
+            color: UiColor(Color::NONE),
+            ..default()
+        })
+        .insert(HudLayer)
+        .insert(BoardArea)
+        .insert(GameObject)
+        .id();
+
+    let label_style = TextStyle {
+        font_size: 12.0,
+        color: LABEL_COLOR,
+        ..my_assets.text_style.clone()
+    };
+    let value_style = |font_size| TextStyle {
+        font_size,
+        ..my_assets.text_style.clone()
+    };
+
+    for board in 0..versus.boards() {
+        // Board takes the left side of its area and the panel the right side
+        let area = commands
+            .spawn_bundle(NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Px(WINDOW_WIDTH), Val::Percent(100.0)),
+                    justify_content: JustifyContent::FlexEnd,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                color: UiColor(Color::NONE),
+                ..default()
+            })
+            .id();
+
+        let panel = commands
+            .spawn_bundle(NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Px(PANEL_PX), Val::Auto),
+                    flex_direction: FlexDirection::ColumnReverse,
+                    align_items: AlignItems::Center,
+                    padding: UiRect::all(Val::Px(8.0)),
+                    ..default()
+                },
+                color: UiColor(Color::rgba(1.0, 1.0, 1.0, 0.25)),
+                ..default()
+            })
+            .id();
+
+        // Label and value pairs stacked from top to bottom. High score is kept only for single
+        // player games.
+        let mut rows = vec![(TextKey::HudScore, HudValue::Score, 32.0)];
+        if *versus == Versus::Off {
+            rows.push((TextKey::HudHighScore, HudValue::HighScore, 20.0));
+        }
+        rows.extend([
+            (TextKey::HudLevel, HudValue::Level, 20.0),
+            (TextKey::HudTime, HudValue::Time, 20.0),
+            (TextKey::HudCleared, HudValue::Cleared, 20.0),
+        ]);
+
+        let mut children = vec![];
+        for (key, value, font_size) in rows {
+            let label = spawn_text(&mut commands, language.get(key), label_style.clone());
+            commands.entity(label).insert(HudLabel(key));
+            children.push(label);
+            let text = spawn_text(&mut commands, String::new(), value_style(font_size));
+            commands.entity(text).insert(value).insert(BoardId(board));
+            children.push(text);
+        }
+
+        // Target rule describes itself
+        let target = spawn_text(&mut commands, String::new(), value_style(12.0));
+        commands
+            .entity(target)
+            .insert(HudValue::Target)
+            .insert(BoardId(board));
+        children.push(target);
+
+        for (key, preview) in [
+            (TextKey::HudNext, BlockPreview::Next),
+            (TextKey::HudHold, BlockPreview::Hold),
+        ] {
+            let label = spawn_text(&mut commands, language.get(key), label_style.clone());
+            commands.entity(label).insert(HudLabel(key));
+            children.push(label);
+            let image = spawn_preview(&mut commands, &my_assets, preview);
+            commands.entity(image).insert(BoardId(board));
+            children.push(image);
+        }
+
+        commands.entity(panel).push_children(&children);
+        commands.entity(area).push_children(&[panel]);
+        commands.entity(hud).push_children(&[area]);
+    }
+}
+
+/// Update the values of the side panels
+fn update_hud_values(
+    mut query: Query<(&mut Text, &HudValue, &BoardId)>,
+    scores: Res<PerBoard<Score>>,
+    high_score: Res<HighScore>,
+    levels: Res<PerBoard<Level>>,
+    game_time: Res<GameTime>,
+    rules: Res<GameRules>,
+    language: Res<Language>,
+) {
+    for (mut text, value, board) in query.iter_mut() {
+        let level = &levels[board.0];
+        let new_value = match value {
+            HudValue::Score => scores[board.0].0.to_string(),
+            HudValue::HighScore => high_score.0.max(scores[board.0].0).to_string(),
+            HudValue::Level => level.number.to_string(),
+            HudValue::Time => {
+                let seconds = game_time.0.elapsed().as_secs();
+                format!("{}:{:02}", seconds / 60, seconds % 60)
+            }
+            HudValue::Cleared => level.cleared_blocks.to_string(),
+            HudValue::Target => rules
+                .target_rule(level.number)
+                .description(level.number, *language),
+        };
+
+        // Text layout is recomputed only when the value changes
+        if text.sections[0].value != new_value {
+            text.sections[0].value = new_value;
+        }
+    }
+}
+
+/// Update the labels of the side panels in case the language is changed during the game
+fn update_hud_labels(mut query: Query<(&mut Text, &HudLabel)>, language: Res<Language>) {
+    if !language.is_changed() {
+        return;
+    }
+
+    for (mut text, label) in query.iter_mut() {
+        text.sections[0].value = language.get(label.0);
+    }
+}
+
+/// Update the previews of the next and held blocks
+fn update_block_previews(
+    mut query: Query<(&mut UiColor, &BlockPreview, &BoardId, &Children)>,
+    mut text_query: Query<&mut Text>,
+    next_blocks: Res<PerBoard<NextBlock>>,
+    held_blocks: Res<PerBoard<HeldBlock>>,
+    palette: Res<Palette>,
+) {
+    for (mut color, preview, board, children) in query.iter_mut() {
+        let block: Option<BlockParams> = match preview {
+            BlockPreview::Next => next_blocks[board.0].0,
+            BlockPreview::Hold => held_blocks[board.0].block,
+        };
+        let (new_color, new_text) = match block {
+            Some(block) => (
+                palette.sprite_color(block.color, block.kind),
+                get_dropping_block_text(block.kind, block.operation, block.number),
+            ),
+            None => (Color::NONE, String::new()),
+        };
+
+        if color.0 != new_color {
+            color.0 = new_color;
+        }
+        for child in children.iter() {
+            if let Ok(mut text) = text_query.get_mut(*child) {
+                if text.sections[0].value != new_text {
+                    text.sections[0].value = new_text.clone();
+                }
+            }
+        }
+    }
+}
@@ -1,79 +1,270 @@
-use crate::audio::{PlaySfxEvent, Sfx};
-use crate::board::{BlockMap, BoardPlugin, MoveBlockEvent};
+use crate::audio::{MusicIntensity, PlaySfxEvent, Sfx};
+use crate::board::{
+    get_neighbor_positions, BlockMap, BoardId, BoardPlugin, MoveBlockEvent, PerBoard,
+};
 use crate::constants::prelude::*;
+use crate::controls::{PlayerAction, PlayerInputLabel};
+use crate::fraction::Fraction;
+use crate::localization::{Language, TextKey};
+use crate::palette::Palette;
 use crate::prelude::*;
+use crate::rules::{Adjacency, GameMode, GameRules, Resolution, Versus};
+use crate::toast::ShowToastEvent;
+use crate::undo::UndoSnapshotLabel;
+use crate::versus::{AttackEvent, GarbageRng, VersusResult};
+use bevy::ecs::schedule::ShouldRun;
 use bevy::prelude::*;
 use bevy::time::Stopwatch;
-use rand::Rng;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use std::collections::HashMap;
 
 /// This `Component` defines the math operation which the block performs
-#[derive(Component, Copy, Clone, Eq, PartialEq)]
+#[derive(Component, Copy, Clone, Eq, PartialEq, Debug)]
 pub enum Operation {
     ADD,
     SUBTRACT,
     MULTIPLY,
     DIVIDE,
+    /// Remainder of the division (always non-negative)
+    MODULO,
+    /// Square of the number (dropping block number is not used)
+    SQUARE,
+    /// Changes the sign of the number (dropping block number is not used)
+    NEGATE,
+    /// Swaps the tens and ones digits of the number. Single digit numbers are left as they are.
+    /// (dropping block number is not used)
+    SWAP,
+    /// Sets the number to the dropping block number
+    SET,
+}
+
+impl Operation {
+    pub const ALL: [Operation; 9] = [
+        Operation::ADD,
+        Operation::SUBTRACT,
+        Operation::MULTIPLY,
+        Operation::DIVIDE,
+        Operation::MODULO,
+        Operation::SQUARE,
+        Operation::NEGATE,
+        Operation::SWAP,
+        Operation::SET,
+    ];
 }
 
 /// This `Event` is sent when new solid block needs to be spawned
 pub struct SpawnDroppingBlockEvent {
-    number: i32,
-    position: Coords,
-    color: BlockColor,
-    operation: Operation,
+    pub board: usize,
+    pub number: Fraction,
+    pub position: Coords,
+    pub color: BlockColor,
+    pub operation: Operation,
+    pub kind: BlockKind,
 }
 
 /// This `Event` is sent when new dropping block needs to be spawned
 pub struct SpawnSolidBlockEvent {
-    number: i32,
-    position: Coords,
-    color: BlockColor,
+    pub board: usize,
+    pub number: Fraction,
+    pub position: Coords,
+    pub color: BlockColor,
+    pub kind: BlockKind,
+    /// Block is restored from a snapshot or added as garbage instead of dropped, so it's not
+    /// combined with its neighbors
+    pub inserted: bool,
 }
 
 /// This list contains "despawning" blocks not despawned immediately because of animation.
 #[derive(Default)]
 pub struct DespawningBlocks(HashMap<Entity, (Entity, Timer, Timer)>);
 
-/// This `Event` is sent when parameters for dropping block needs to be randomized
-pub struct RandomizeDroppingBlockEvent;
+impl DespawningBlocks {
+    /// Returns `true` if no blocks are being despawned
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns `true` if the block is being despawned
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.0.contains_key(&entity)
+    }
+}
+
+/// Systems which run when the dropping block lands are labeled with this
+#[derive(SystemLabel, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct BlockLandingLabel;
+
+/// System updating the `SimulationStep` is labeled with this. Systems holding the simulation
+/// should be run after it.
+#[derive(SystemLabel, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct SimulationStepLabel;
+
+/// Run criteria of the `GameState::InGame` update is labeled with this. The simulation systems
+/// pipe it through `simulation_ready`.
+#[derive(RunCriteriaLabel, Clone, PartialEq, Eq, Hash, Debug)]
+struct InGameUpdateLabel;
+
+/// This resource tells how the game advances on the current frame. The game follows the frame
+/// time, except in a network game where it advances by a fixed time step, and only once the
+/// inputs of both players have arrived (see `net.rs`).
+pub struct SimulationStep {
+    /// Simulation systems are skipped on this frame if this is `false`
+    pub ready: bool,
+    /// Time the game advances on this frame
+    pub delta: std::time::Duration,
+}
+
+impl Default for SimulationStep {
+    fn default() -> Self {
+        SimulationStep {
+            ready: true,
+            delta: std::time::Duration::ZERO,
+        }
+    }
+}
+
+/// This `Event` is sent when parameters for dropping block needs to be randomized. Holds the
+/// board of the block.
+pub struct RandomizeDroppingBlockEvent(pub usize);
 
-/// This `Event` is sent when calculations needs to be performed for solid block
+/// This `Event` is sent when the dropped block is combined with its neighbors. Neighbors are
+/// listed in the order they are processed.
 pub struct PerformCalculationEvent {
     pub entity: Entity,
-    pub number: i32,
+    pub neighbors: Vec<Entity>,
+    pub number: Fraction,
     pub operation: Operation,
 }
 
 /// This `Event` is sent when block number needs to be updated
 pub struct UpdateBlockNumberEvent {
     entity: Entity,
-    number: i32,
+    number: Fraction,
 }
 
+/// This `Event` is sent when solid block is cleared from the board
+pub struct ClearBlockEvent(pub Entity);
+
+/// This `Event` is sent for each numbered block once it has been cleared (crumbled stones are
+/// not included)
+pub struct BlockClearedEvent {
+    pub board: usize,
+    pub number: Fraction,
+    /// Points given for the block
+    pub points: i32,
+    pub position: Coords,
+    pub color: BlockColor,
+    pub kind: BlockKind,
+}
+
+/// This `Event` is sent when the player changes the color of the dropping block. Holds the
+/// board of the block.
+pub struct ColorSwitchedEvent(pub usize);
+
 /// `Timer` for calculating when dropping block drops one square
-struct DropTimer(Timer);
+pub struct DropTimer(pub Timer);
+
+impl Default for DropTimer {
+    fn default() -> Self {
+        DropTimer(Timer::from_seconds(INITIAL_DROP_SPEED, true))
+    }
+}
 
 /// `Timer` for restricting horizontal movement
+#[derive(Default)]
 struct MoveTimer(Stopwatch);
 
 /// This resource holds the current block drop speed (in seconds)
-struct DropSpeed(pub f32);
+pub struct DropSpeed(pub f32);
+
+impl Default for DropSpeed {
+    fn default() -> Self {
+        DropSpeed(INITIAL_DROP_SPEED)
+    }
+}
+
+/// Random number generator for the dropping blocks. Seeded at the start of each game. Boards of
+/// a versus game get the same seed so that both players get the same blocks.
+pub struct GameRng(pub StdRng);
+
+impl Default for GameRng {
+    fn default() -> Self {
+        GameRng(StdRng::from_entropy())
+    }
+}
+
+/// This resource holds the seed for the blocks of the next game. A random seed is used if it's
+/// not set.
+#[derive(Default)]
+pub struct GameSeed(pub Option<u64>);
 
-/// This resource holds the entity of the last dropped block
+/// Parameters of a dropping block
+#[derive(Copy, Clone, Debug)]
+pub struct BlockParams {
+    pub number: Fraction,
+    pub color: BlockColor,
+    pub operation: Operation,
+    pub kind: BlockKind,
+}
+
+/// This resource holds the parameters of the block which is dropped next
+#[derive(Default)]
+pub struct NextBlock(pub Option<BlockParams>);
+
+/// This resource holds the block put on hold. Hold can be used once per dropped block.
+#[derive(Default)]
+pub struct HeldBlock {
+    pub block: Option<BlockParams>,
+    used: bool,
+}
+
+/// This resource holds the time elapsed in the current game
+#[derive(Default)]
+pub struct GameTime(pub Stopwatch);
+
+/// This resource holds the operation of the last dropped block
 #[derive(Default)]
 pub struct LastDroppedBlock {
-    pub entity: Option<Entity>,
     pub operation: Option<Operation>,
 }
 
-/// Identifier for the HUD layer
-#[derive(Component)]
-pub struct HudLayer;
+/// This resource holds the current level and the number of blocks cleared during the game
+#[derive(Clone)]
+pub struct Level {
+    pub number: u32,
+    pub cleared_blocks: u32,
+}
+
+impl Default for Level {
+    fn default() -> Self {
+        Level {
+            number: 1,
+            cleared_blocks: 0,
+        }
+    }
+}
+
+/// This resource holds the level the games are started from. Selected in the options.
+pub struct StartLevel(pub u32);
 
-/// Identifier for the ScoreText
+impl Default for StartLevel {
+    fn default() -> Self {
+        StartLevel(1)
+    }
+}
+
+/// This resource tracks consecutive drops which have cleared blocks
+#[derive(Default, Clone)]
+pub struct Chain {
+    pub length: u32,
+    /// Set once the latest dropped block has cleared something
+    cleared: bool,
+}
+
+/// Solid block restored from a snapshot or added as garbage instead of dropped
 #[derive(Component)]
-pub struct ScoreText;
+pub struct Inserted;
 
 /// Dropping block is tagged with this `Component`
 #[derive(Component)]
@@ -89,10 +280,10 @@ pub struct EdgeBlock;
 
 /// This `Component` determines the block number
 #[derive(Component)]
-pub struct Number(pub i32);
+pub struct Number(pub Fraction);
 
 /// This `Component` holds the block color
-#[derive(Component, PartialEq, Clone, Copy)]
+#[derive(Component, PartialEq, Eq, Hash, Clone, Copy, Debug)]
 pub enum BlockColor {
     NONE,
     BLUE,
@@ -101,21 +292,23 @@ pub enum BlockColor {
     GREEN,
 }
 
+/// This `Component` defines the special behavior of the block
+#[derive(Component, PartialEq, Eq, Clone, Copy, Debug)]
+pub enum BlockKind {
+    /// Numbered block which combines with same colored blocks
+    NORMAL,
+    /// Clears all same colored neighbors regardless of their value
+    BOMB,
+    /// Combines with blocks of any color
+    WILDCARD,
+    /// Never combines. Crumbles once a block next to it is cleared.
+    STONE,
+}
+
 /// This `Component` determines the coordinates in `BlockMap`
 #[derive(Component)]
 pub struct BlockPosition(pub Coords);
 
-/// Helper function to translate the `BlockColor` enum into actual RGB Color value
-pub fn get_color(block_color: BlockColor) -> Color {
-    match block_color {
-        BlockColor::NONE => Color::NONE,
-        BlockColor::BLUE => Color::CYAN,
-        BlockColor::YELLOW => Color::GOLD,
-        BlockColor::PINK => Color::ORANGE_RED,
-        BlockColor::GREEN => Color::LIME_GREEN,
-    }
-}
-
 /// Helper function to translate `Operation` into string
 pub fn get_operator(op: Operation) -> String {
     match op {
@@ -123,198 +316,386 @@ pub fn get_operator(op: Operation) -> String {
         Operation::SUBTRACT => "-",
         Operation::MULTIPLY => "x",
         Operation::DIVIDE => "/",
+        Operation::MODULO => "%",
+        Operation::SQUARE => "^2",
+        Operation::NEGATE => "~",
+        Operation::SWAP => "<>",
+        Operation::SET => "=",
     }
     .to_string()
 }
 
-/// Helper function to get real position
-pub fn get_translation(win_size: &Vec2, pos: &Coords) -> Vec3 {
+/// Helper function to get the text shown in the dropping block. Operations which don't use the
+/// dropping block number show only the operator.
+pub fn get_dropping_block_text(kind: BlockKind, op: Operation, number: Fraction) -> String {
+    match kind {
+        BlockKind::BOMB | BlockKind::STONE => get_solid_block_text(kind, number),
+        _ => match op {
+            Operation::SQUARE | Operation::NEGATE | Operation::SWAP => get_operator(op),
+            _ => format!("{}{}", get_operator(op), number),
+        },
+    }
+}
+
+/// Helper function to get the font size for the block text so that long numbers fit in the block
+pub fn get_block_font_size(text: &str) -> f32 {
+    (72.0 / text.chars().count() as f32).min(24.0)
+}
+
+/// Helper function to get the text shown in the solid block
+pub fn get_solid_block_text(kind: BlockKind, number: Fraction) -> String {
+    match kind {
+        BlockKind::BOMB => "*".to_string(),
+        BlockKind::STONE => "#".to_string(),
+        _ => number.to_string(),
+    }
+}
+
+/// Helper function to get real position on a board of given size. The area of the first board
+/// and its side panel is centered on the world origin.
+pub fn get_translation(size: &BoardSize, pos: &Coords) -> Vec3 {
     Vec3::new(
-        -win_size.x / 2.0 + pos.x as f32 * BLOCK_SIZE + BLOCK_SIZE + 15.0,
-        -win_size.y / 2.0 + pos.y as f32 * BLOCK_SIZE - HALF_BLOCK + 7.0,
+        (pos.x as f32 - (size.width - 1) as f32 / 2.0) * BLOCK_SIZE - PANEL_WIDTH / 2.0,
+        -WORLD_HEIGHT / 2.0 + (pos.y + 1) as f32 * BLOCK_SIZE + HALF_BLOCK,
         1.0,
     )
 }
 
+/// Helper function to get real position on the given board. Boards are side by side.
+pub fn get_board_translation(size: &BoardSize, board: usize, pos: &Coords) -> Vec3 {
+    let mut translation = get_translation(size, pos);
+    translation.x += board as f32 * BOARD_AREA_WIDTH;
+    translation
+}
+
+/// Returns a `SystemSet` for the systems which advance the game. The systems run like the ones in
+/// `SystemSet::on_update(GameState::InGame)`, but they are skipped while the simulation is held.
+pub fn on_simulation_update() -> SystemSet {
+    SystemSet::new().with_run_criteria(RunCriteria::pipe(InGameUpdateLabel, simulation_ready))
+}
+
+/// Run criteria which skips the simulation systems on the frames the simulation is held
+fn simulation_ready(In(input): In<ShouldRun>, step: Res<SimulationStep>) -> ShouldRun {
+    match (input, step.ready) {
+        (ShouldRun::Yes, false) => ShouldRun::No,
+        (ShouldRun::YesAndCheckAgain, false) => ShouldRun::NoAndCheckAgain,
+        (input, _) => input,
+    }
+}
+
 /// Bevy `Plugin` for handling the actual gameplay of this game
 pub struct InGamePlugin;
 impl Plugin for InGamePlugin {
     fn build(&self, app: &mut App) {
         app.add_plugin(BoardPlugin)
-            .add_system_set(SystemSet::on_enter(GameState::InGame).with_system(on_enter))
+            .add_system_set(
+                SystemSet::on_enter(GameState::InGame)
+                    .with_system(on_enter)
+                    .with_system(reset_boards.after(on_enter)),
+            )
             .add_system_set(SystemSet::on_exit(GameState::InGame).with_system(on_exit))
             .add_system_set(
-                SystemSet::on_update(GameState::InGame)
-                    .with_system(back_to_menu_on_esc)
-                    .with_system(handle_dropping_block_movement)
-                    .with_system(update_block_translation)
+                SystemSet::new()
+                    .with_run_criteria(State::on_update(GameState::InGame).label(InGameUpdateLabel))
+                    .with_system(update_block_number_text)
+                    .with_system(update_operator_text)
+                    .with_system(update_block_color),
+            )
+            .add_system_set(
+                on_simulation_update()
+                    .with_system(
+                        handle_dropping_block_movement
+                            .after(PlayerInputLabel)
+                            .label(BlockLandingLabel),
+                    )
                     .with_system(spawn_solid_block)
                     .with_system(spawn_dropping_block)
-                    .with_system(randomize_new_block)
+                    .with_system(randomize_new_block.after(UndoSnapshotLabel))
                     .with_system(perform_calculation)
                     .with_system(update_block_number)
-                    .with_system(update_block_number_text)
-                    .with_system(update_score_text)
-                    .with_system(update_operator_text)
-                    .with_system(update_block_color)
+                    .with_system(clear_blocks)
                     .with_system(despawn_blocks)
-                    .with_system(switch_dropping_block_color)
-                    .with_system(drop_floating_blocks),
+                    .with_system(switch_dropping_block_color.after(PlayerInputLabel))
+                    .with_system(hold_block.after(BlockLandingLabel))
+                    .with_system(tick_game_time)
+                    .with_system(drop_floating_blocks)
+                    .with_system(update_danger_level),
+            )
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                update_simulation_step.label(SimulationStepLabel),
             )
+            // Edge blocks are shown also outside the game
+            .add_system(update_block_translation)
             .add_event::<SpawnSolidBlockEvent>()
             .add_event::<SpawnDroppingBlockEvent>()
             .add_event::<RandomizeDroppingBlockEvent>()
             .add_event::<PerformCalculationEvent>()
             .add_event::<UpdateBlockNumberEvent>()
-            .insert_resource(MoveTimer(Stopwatch::new()))
-            .insert_resource(DropSpeed(INITIAL_DROP_SPEED))
-            .insert_resource(LastDroppedBlock::default())
+            .add_event::<ClearBlockEvent>()
+            .add_event::<BlockClearedEvent>()
+            .add_event::<ColorSwitchedEvent>()
+            .insert_resource(PerBoard::<Score>::default())
+            .insert_resource(PerBoard::<MoveTimer>::default())
+            .insert_resource(PerBoard::<DropTimer>::default())
+            .insert_resource(PerBoard::<DropSpeed>::default())
+            .insert_resource(PerBoard::<LastDroppedBlock>::default())
+            .insert_resource(PerBoard::<NextBlock>::default())
+            .insert_resource(PerBoard::<HeldBlock>::default())
+            .insert_resource(GameTime::default())
+            .insert_resource(SimulationStep::default())
+            .insert_resource(PerBoard::<GameRng>::default())
+            .insert_resource(GameSeed::default())
+            .insert_resource(PerBoard::<Level>::default())
+            .insert_resource(PerBoard::<Chain>::default())
+            .insert_resource(GameRules::new(
+                GameMode::Classic,
+                Adjacency::Flood,
+                Resolution::First,
+            ))
             .insert_resource(DespawningBlocks::default());
     }
 }
 
 /// Called once as the game is started
 fn on_enter(
-    mut commands: Commands,
     mut gen_event: EventWriter<RandomizeDroppingBlockEvent>,
-    asset_server: Res<AssetServer>,
-    mut score: ResMut<Score>,
-    mut drop_speed: ResMut<DropSpeed>,
+    mut rules: ResMut<GameRules>,
+    mode: Res<GameMode>,
+    adjacency: Res<Adjacency>,
+    resolution: Res<Resolution>,
+    versus: Res<Versus>,
 ) {
     println!("Enter GameState::InGame");
 
-    // Spawn score text
-    let hud = commands
-        .spawn_bundle(NodeBundle {
-            style: Style {
-                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
-                position_type: PositionType::Absolute,
-                justify_content: JustifyContent::Center,
-                align_items: AlignItems::FlexEnd,
-                ..default()
-            },
-            color: UiColor(Color::NONE),
-            ..default()
-        })
-        .insert(HudLayer)
-        .insert(GameObject)
-        .id();
+    // Rules are defined by the selected game mode, adjacency and resolution policy
+    *rules = GameRules::new(*mode, *adjacency, *resolution);
 
-    let text = commands
-        .spawn_bundle(TextBundle::from_section(
-            "0",
-            TextStyle {
-                font: asset_server.load("fonts/04b_30.ttf"),
-                font_size: 32.0,
-                color: Color::BLACK,
-            },
-        ))
-        .insert(ScoreText)
-        .id();
+    // Drops can't be undone while the opponent keeps playing
+    if *versus != Versus::Off {
+        rules.undo = false;
+    }
+
+    // Generate the first dropping block of each board
+    for board in 0..versus.boards() {
+        gen_event.send(RandomizeDroppingBlockEvent(board));
+    }
+}
 
-    commands.entity(hud).push_children(&[text]);
+/// Called once as the game is started. Resets the score, level, speed and the upcoming blocks of
+/// each board.
+fn reset_boards(
+    versus: Res<Versus>,
+    rules: Res<GameRules>,
+    start_level: Res<StartLevel>,
+    mut seed: ResMut<GameSeed>,
+    mut scores: ResMut<PerBoard<Score>>,
+    mut levels: ResMut<PerBoard<Level>>,
+    mut chains: ResMut<PerBoard<Chain>>,
+    mut next_blocks: ResMut<PerBoard<NextBlock>>,
+    mut held_blocks: ResMut<PerBoard<HeldBlock>>,
+    mut game_time: ResMut<GameTime>,
+    mut last_dropped_blocks: ResMut<PerBoard<LastDroppedBlock>>,
+    mut rngs: ResMut<PerBoard<GameRng>>,
+    mut garbage_rngs: ResMut<PerBoard<GarbageRng>>,
+    mut drop_speeds: ResMut<PerBoard<DropSpeed>>,
+    mut drop_timers: ResMut<PerBoard<DropTimer>>,
+    mut move_timers: ResMut<PerBoard<MoveTimer>>,
+) {
+    let boards = versus.boards();
+    scores.reset(boards);
+    levels.reset(boards);
+    chains.reset(boards);
+    next_blocks.reset(boards);
+    held_blocks.reset(boards);
+    game_time.0.reset();
+    last_dropped_blocks.reset(boards);
+    drop_speeds.reset(boards);
+    drop_timers.reset(boards);
+    move_timers.reset(boards);
 
-    // Generate the first dropping block
-    gen_event.send(RandomizeDroppingBlockEvent);
+    // Game started from a higher level has the speed it would have had after the drops needed
+    // for reaching the level. Network games always start from the first level.
+    let start = if *versus == Versus::Network {
+        1
+    } else {
+        start_level.0
+    };
+    let drops = (start - 1) * BLOCKS_PER_LEVEL;
+    let speed = INITIAL_DROP_SPEED * rules.drop_speed_factor.powi(drops as i32);
+    for board in 0..boards {
+        levels[board].number = start;
+        drop_speeds[board].0 = speed;
+        drop_timers[board]
+            .0
+            .set_duration(std::time::Duration::from_secs_f32(speed));
+    }
 
-    // Reset the score resource
-    score.0 = 0;
+    // Every board gets the same blocks. Garbage is drawn from a separate sequence so that it
+    // doesn't change the blocks of the board.
+    let seed = seed.0.take().unwrap_or_else(rand::random::<u64>);
+    rngs.0 = (0..boards)
+        .map(|_| GameRng(StdRng::seed_from_u64(seed)))
+        .collect();
+    garbage_rngs.0 = (0..boards)
+        .map(|_| GarbageRng(StdRng::seed_from_u64(!seed)))
+        .collect();
+}
 
-    // Reset the initial drop speed
-    drop_speed.0 = INITIAL_DROP_SPEED;
-    commands.insert_resource(DropTimer(Timer::from_seconds(INITIAL_DROP_SPEED, true)))
+/// System for setting the time the game advances on this frame. Network game advances by a fixed
+/// time step so that both instances simulate the boards identically.
+fn update_simulation_step(time: Res<Time>, versus: Res<Versus>, mut step: ResMut<SimulationStep>) {
+    step.ready = true;
+    step.delta = if *versus == Versus::Network {
+        std::time::Duration::from_secs_f32(NETWORK_TIME_STEP)
+    } else {
+        time.delta()
+    };
 }
 
 /// Called once after game has ended
-fn on_exit() {
+fn on_exit(mut music_intensity: ResMut<MusicIntensity>) {
     println!("Exit GameState::InGame");
+
+    // Calm down the music
+    music_intensity.0 = 0.0;
 }
 
-/// System for returning to Menu in case Esc key is pressed
-pub fn back_to_menu_on_esc(
-    mut input: ResMut<Input<KeyCode>>,
-    mut game_state: ResMut<State<GameState>>,
-) {
-    if input.just_pressed(KeyCode::Escape) && *game_state.current() != GameState::Menu {
-        game_state
-            .set(GameState::GameOver)
-            .expect("Failed to change GameState::GameOver");
-        input.reset(KeyCode::Escape);
+/// Helper function to randomize the parameters for a new dropping block
+fn random_block_params(rules: &GameRules, level: u32, rng: &mut StdRng) -> BlockParams {
+    let operations = rules.available_operations(level);
+    let num = rules.spawn_distribution(level).sample(rng);
+    let col = rng.gen_range(1..=4);
+    let op = rng.gen_range(0..operations.len());
+
+    let color = match col {
+        1 => BlockColor::BLUE,
+        2 => BlockColor::YELLOW,
+        3 => BlockColor::PINK,
+        _ => BlockColor::GREEN,
+    };
+
+    let mut operation = operations[op];
+
+    // Prevent division by zero
+    if (operation == Operation::DIVIDE || operation == Operation::MODULO) && num == 0 {
+        operation = Operation::MULTIPLY;
+    }
+
+    // Roll for a special block
+    let mut kind = BlockKind::NORMAL;
+    for (special_kind, chance) in rules.special_blocks.iter() {
+        if rng.gen::<f32>() < *chance {
+            kind = *special_kind;
+            break;
+        }
+    }
+
+    let (number, color) = match kind {
+        BlockKind::NORMAL => (num, color),
+        BlockKind::BOMB => (0, color),
+        BlockKind::WILDCARD => (num, BlockColor::NONE),
+        BlockKind::STONE => (0, BlockColor::NONE),
+    };
+
+    BlockParams {
+        number: Fraction::from(number),
+        color,
+        operation,
+        kind,
     }
 }
 
-/// System for randomizing new parameters for the dropping block
+/// System for randomizing new parameters for the dropping block. The block is taken from
+/// `NextBlock` and a new next block is randomized in its place.
 fn randomize_new_block(
     mut gen_event: EventReader<RandomizeDroppingBlockEvent>,
     mut spawn_event: EventWriter<SpawnDroppingBlockEvent>,
+    mut next_blocks: ResMut<PerBoard<NextBlock>>,
+    mut rngs: ResMut<PerBoard<GameRng>>,
+    rules: Res<GameRules>,
+    levels: Res<PerBoard<Level>>,
+    size: Res<BoardSize>,
 ) {
-    for _ in gen_event.iter() {
-        let num = rand::thread_rng().gen_range(0..=9);
-        let col = rand::thread_rng().gen_range(1..=4);
-        let op = rand::thread_rng().gen_range(1..=4);
-
-        let color = match col {
-            1 => BlockColor::BLUE,
-            2 => BlockColor::YELLOW,
-            3 => BlockColor::PINK,
-            _ => BlockColor::GREEN,
-        };
-
-        let mut operation = match op {
-            1 => Operation::ADD,
-            2 => Operation::SUBTRACT,
-            3 => Operation::MULTIPLY,
-            _ => Operation::DIVIDE,
+    for ev in gen_event.iter() {
+        let board = ev.0;
+        let level = levels[board].number;
+        let rng = &mut rngs[board].0;
+        let block = match next_blocks[board].0.take() {
+            Some(block) => block,
+            None => random_block_params(&rules, level, rng),
         };
-
-        // Prevent division by zero
-        if operation == Operation::DIVIDE && num == 0 {
-            operation = Operation::MULTIPLY;
-        }
+        next_blocks[board].0 = Some(random_block_params(&rules, level, rng));
 
         spawn_event.send(SpawnDroppingBlockEvent {
-            number: num,
-            position: INITIAL_POSITION,
-            color,
-            operation,
+            board,
+            number: block.number,
+            position: size.initial_position(),
+            color: block.color,
+            operation: block.operation,
+            kind: block.kind,
         });
     }
 }
 
+/// Helper function to spawn a solid block with its text
+pub fn spawn_solid_block_entity(
+    commands: &mut Commands,
+    my_assets: &MyAssets,
+    palette: Palette,
+    ev: &SpawnSolidBlockEvent,
+) -> Entity {
+    let block = commands
+        .spawn_bundle(SpriteBundle {
+            sprite: Sprite {
+                color: palette.sprite_color(ev.color, ev.kind),
+                ..default()
+            },
+            texture: my_assets.block_texture.clone(),
+            transform: INITIAL_TRANSFORM,
+            ..default()
+        })
+        .insert(GameObject)
+        .insert(SolidBlock)
+        .insert(Number(ev.number))
+        .insert(ev.color)
+        .insert(ev.kind)
+        .insert(BlockPosition(ev.position))
+        .insert(BoardId(ev.board))
+        .id();
+    if ev.inserted {
+        commands.entity(block).insert(Inserted);
+    }
+
+    let value = get_solid_block_text(ev.kind, ev.number);
+    let text = commands
+        .spawn_bundle(Text2dBundle {
+            text: Text::from_section(
+                value.clone(),
+                TextStyle {
+                    font_size: get_block_font_size(&value),
+                    ..my_assets.text_style.clone()
+                },
+            )
+            .with_alignment(TextAlignment::CENTER),
+            transform: Transform::from_xyz(0.0, 0.0, 10.0),
+            ..default()
+        })
+        .id();
+
+    commands.entity(block).push_children(&[text]);
+    block
+}
+
 /// System for spawning solid blocks
 fn spawn_solid_block(
     mut commands: Commands,
     mut event_reader: EventReader<SpawnSolidBlockEvent>,
-    block_style: Res<MyAssets>,
+    my_assets: Res<MyAssets>,
+    palette: Res<Palette>,
 ) {
     for ev in event_reader.iter() {
-        let block = commands
-            .spawn_bundle(SpriteBundle {
-                sprite: Sprite {
-                    color: get_color(ev.color),
-                    ..default()
-                },
-                texture: block_style.block_texture.clone(),
-                transform: INITIAL_TRANSFORM,
-                ..default()
-            })
-            .insert(GameObject)
-            .insert(SolidBlock)
-            .insert(Number(ev.number))
-            .insert(ev.color)
-            .insert(BlockPosition(ev.position))
-            .id();
-
-        let text = commands
-            .spawn_bundle(Text2dBundle {
-                text: Text::from_section(ev.number.to_string(), block_style.text_style.clone())
-                    .with_alignment(TextAlignment::CENTER),
-                transform: Transform::from_xyz(0.0, 0.0, 10.0),
-                ..default()
-            })
-            .id();
-
-        commands.entity(block).push_children(&[text]);
+        spawn_solid_block_entity(&mut commands, &my_assets, *palette, ev);
     }
 }
 
@@ -323,12 +704,13 @@ fn spawn_dropping_block(
     mut commands: Commands,
     mut event_reader: EventReader<SpawnDroppingBlockEvent>,
     my_assets: Res<MyAssets>,
+    palette: Res<Palette>,
 ) {
     for ev in event_reader.iter() {
         let block = commands
             .spawn_bundle(SpriteBundle {
                 sprite: Sprite {
-                    color: get_color(ev.color),
+                    color: palette.sprite_color(ev.color, ev.kind),
                     ..default()
                 },
                 texture: my_assets.block_texture.clone(),
@@ -339,15 +721,21 @@ fn spawn_dropping_block(
             .insert(DroppingBlock)
             .insert(Number(ev.number))
             .insert(BlockPosition(ev.position))
+            .insert(BoardId(ev.board))
             .insert(ev.color)
+            .insert(ev.kind)
             .insert(ev.operation)
             .id();
 
+        let value = get_dropping_block_text(ev.kind, ev.operation, ev.number);
         let text = commands
             .spawn_bundle(Text2dBundle {
                 text: Text::from_section(
-                    format!("{}{}", get_operator(ev.operation), ev.number),
-                    my_assets.text_style.clone(),
+                    value.clone(),
+                    TextStyle {
+                        font_size: get_block_font_size(&value),
+                        ..my_assets.text_style.clone()
+                    },
                 )
                 .with_alignment(TextAlignment::CENTER),
                 transform: Transform::from_xyz(0.0, 0.0, 10.0),
@@ -362,77 +750,108 @@ fn spawn_dropping_block(
 /// System for handling both player input and dropping block downward movement
 fn handle_dropping_block_movement(
     mut commands: Commands,
-    time: Res<Time>,
-    input: Res<Input<KeyCode>>,
-    mut drop_timer: ResMut<DropTimer>,
-    mut time_since_last_moved: ResMut<MoveTimer>,
+    step: Res<SimulationStep>,
+    actions: Res<PerBoard<Input<PlayerAction>>>,
+    mut drop_timers: ResMut<PerBoard<DropTimer>>,
+    mut move_timers: ResMut<PerBoard<MoveTimer>>,
     mut query: Query<
-        (Entity, &Number, &BlockColor, &mut BlockPosition, &Operation),
+        (
+            Entity,
+            &Number,
+            &BlockColor,
+            &BlockKind,
+            &mut BlockPosition,
+            &Operation,
+            &BoardId,
+        ),
         With<DroppingBlock>,
     >,
     mut spawn_event: EventWriter<SpawnSolidBlockEvent>,
     mut gen_event: EventWriter<RandomizeDroppingBlockEvent>,
-    mut drop_speed: ResMut<DropSpeed>,
-    block_map: Res<BlockMap>,
+    mut drop_speeds: ResMut<PerBoard<DropSpeed>>,
+    block_maps: Res<PerBoard<BlockMap>>,
     mut audio_events: EventWriter<PlaySfxEvent>,
-    mut last_dropped_block: ResMut<LastDroppedBlock>,
-    mut game_state: ResMut<State<GameState>>,
+    mut last_dropped_blocks: ResMut<PerBoard<LastDroppedBlock>>,
+    mut chains: ResMut<PerBoard<Chain>>,
+    rules: Res<GameRules>,
+    mut result: ResMut<VersusResult>,
 ) {
-    for (entity, number, color, mut pos, op) in query.iter_mut() {
+    for (entity, number, color, kind, mut pos, op, board) in query.iter_mut() {
+        let board = board.0;
+        let actions = &actions[board];
+        let block_map = &block_maps[board];
+        let time_since_last_moved = &mut move_timers[board];
+        let drop_timer = &mut drop_timers[board];
+
         // Handle Left / Right Movement
         // Block should move immediately after releasing the key or in case key is pressed move once
         // per 0.3 seconds.
-        time_since_last_moved.0.tick(time.delta());
-        if input.just_pressed(KeyCode::Left)
-            || input.pressed(KeyCode::Left) && time_since_last_moved.0.elapsed_secs() > 0.3
+        time_since_last_moved.0.tick(step.delta);
+        if actions.just_pressed(PlayerAction::MoveLeft)
+            || actions.pressed(PlayerAction::MoveLeft)
+                && time_since_last_moved.0.elapsed_secs() > 0.3
         {
             if block_map.is_none(&Coords::new(pos.0.x - 1, pos.0.y)) {
                 pos.0.x -= 1;
+                audio_events.send(PlaySfxEvent(Sfx::BlockMoved));
+            } else {
+                audio_events.send(PlaySfxEvent(Sfx::MoveBlocked));
             }
             time_since_last_moved.0.reset();
-        } else if input.just_pressed(KeyCode::Right)
-            || input.pressed(KeyCode::Right) && time_since_last_moved.0.elapsed_secs() > 0.3
+        } else if actions.just_pressed(PlayerAction::MoveRight)
+            || actions.pressed(PlayerAction::MoveRight)
+                && time_since_last_moved.0.elapsed_secs() > 0.3
         {
             if block_map.is_none(&Coords::new(pos.0.x + 1, pos.0.y)) {
                 pos.0.x += 1;
+                audio_events.send(PlaySfxEvent(Sfx::BlockMoved));
+            } else {
+                audio_events.send(PlaySfxEvent(Sfx::MoveBlocked));
             }
             time_since_last_moved.0.reset();
         }
 
         // Handle block dropping
-        drop_timer.0.tick(time.delta());
+        drop_timer.0.tick(step.delta);
         if drop_timer.0.just_finished()
-            || (drop_timer.0.elapsed_secs() >= 0.02 && (input.pressed(KeyCode::Down)))
+            || (drop_timer.0.elapsed_secs() >= 0.02 && (actions.pressed(PlayerAction::SoftDrop)))
         {
             if block_map.is_none(&Coords::new(pos.0.x, pos.0.y - 1)) {
                 pos.0.y -= 1;
                 drop_timer.0.reset();
-            } else if pos.0.y >= INITIAL_POSITION.y {
-                println!("GAME OVER!");
-
-                game_state
-                    .set(GameState::GameOver)
-                    .expect("Failed to change GameState::GameOver");
+            } else if pos.0.y >= block_map.size().initial_position().y {
+                result.top_out(board);
             } else {
                 // Spawn solid block where the dropping block ended
                 spawn_event.send(SpawnSolidBlockEvent {
+                    board,
                     number: number.0,
                     position: Coords::new(pos.0.x, pos.0.y),
                     color: color.clone(),
+                    kind: *kind,
+                    inserted: false,
                 });
 
                 // Store the last dropped block operation before it's despawned
-                last_dropped_block.operation = Some(*op);
+                last_dropped_blocks[board].operation = Some(*op);
+
+                // Chain breaks if the previous drop didn't clear anything
+                let chain = &mut chains[board];
+                if !chain.cleared {
+                    chain.length = 0;
+                }
+                chain.cleared = false;
 
                 // Despawn dropping block
                 commands.entity(entity).despawn_recursive();
                 audio_events.send(PlaySfxEvent(Sfx::BlockDropped));
 
                 // Generate new dropping block
-                gen_event.send(RandomizeDroppingBlockEvent);
+                gen_event.send(RandomizeDroppingBlockEvent(board));
 
                 // Speed up a little each time block is droped
-                drop_speed.0 *= 0.99;
+                let drop_speed = &mut drop_speeds[board];
+                drop_speed.0 *= rules.drop_speed_factor;
                 drop_timer
                     .0
                     .set_duration(std::time::Duration::from_secs_f32(drop_speed.0));
@@ -443,11 +862,19 @@ fn handle_dropping_block_movement(
 
 /// System for switching the color of the dropping block
 fn switch_dropping_block_color(
-    input: Res<Input<KeyCode>>,
-    mut query: Query<&mut BlockColor, With<DroppingBlock>>,
+    actions: Res<PerBoard<Input<PlayerAction>>>,
+    mut query: Query<(&mut BlockColor, &BlockKind, &BoardId), With<DroppingBlock>>,
+    mut audio_events: EventWriter<PlaySfxEvent>,
+    mut switched_events: EventWriter<ColorSwitchedEvent>,
 ) {
-    if let Ok(mut color) = query.get_single_mut() {
-        if input.just_pressed(KeyCode::RShift) || input.just_pressed(KeyCode::LShift) {
+    for (mut color, kind, board) in query.iter_mut() {
+        // Wildcard and stone blocks have no color to switch
+        if *kind == BlockKind::WILDCARD || *kind == BlockKind::STONE {
+            continue;
+        }
+        let actions = &actions[board.0];
+        let old_color = *color;
+        if actions.just_pressed(PlayerAction::SwitchColor) {
             *color = match *color {
                 BlockColor::NONE => BlockColor::NONE,
                 BlockColor::BLUE => BlockColor::PINK,
@@ -456,85 +883,187 @@ fn switch_dropping_block_color(
                 BlockColor::YELLOW => BlockColor::BLUE,
             }
         }
-        if input.just_pressed(KeyCode::Q) {
-            *color = BlockColor::BLUE;
-        } else if input.just_pressed(KeyCode::W) {
-            *color = BlockColor::PINK;
-        } else if input.just_pressed(KeyCode::E) {
-            *color = BlockColor::YELLOW;
-        } else if input.just_pressed(KeyCode::R) {
-            *color = BlockColor::GREEN;
+        for selected in [
+            BlockColor::BLUE,
+            BlockColor::PINK,
+            BlockColor::YELLOW,
+            BlockColor::GREEN,
+        ] {
+            if actions.just_pressed(PlayerAction::SelectColor(selected)) {
+                *color = selected;
+            }
+        }
+
+        if *color != old_color {
+            audio_events.send(PlaySfxEvent(Sfx::ColorSwitched));
+            switched_events.send(ColorSwitchedEvent(board.0));
         }
     }
 }
 
 /// System which is called to perform math calculation for each same color solid block
-/// than the dropped block (next to dropping position)
+/// than the dropped block (next to dropping position). The dropped block number is resolved
+/// from the results according to the `Resolution` of the game.
 pub fn perform_calculation(
     mut calculate_event: EventReader<PerformCalculationEvent>,
-    mut query: Query<&Number, With<SolidBlock>>,
-    mut last_dropped_block: ResMut<LastDroppedBlock>,
+    query: Query<&Number, With<SolidBlock>>,
     mut update_num_event: EventWriter<UpdateBlockNumberEvent>,
+    rules: Res<GameRules>,
 ) {
     for ev in calculate_event.iter() {
-        if let Ok(number) = query.get_mut(ev.entity) {
-            let number = match ev.operation {
-                Operation::ADD => number.0 + ev.number,
-                Operation::SUBTRACT => number.0 - ev.number,
-                Operation::MULTIPLY => number.0 * ev.number,
-                Operation::DIVIDE => {
-                    if number.0 > 0 {
-                        (number.0 as f32 / ev.number as f32).ceil() as i32
-                    } else if number.0 < 0 {
-                        (number.0 as f32 / ev.number as f32).floor() as i32
-                    } else {
-                        0
-                    }
-                }
-            }
-            .clamp(-99, 99);
+        let neighbors: Vec<(Entity, Fraction)> = ev
+            .neighbors
+            .iter()
+            .filter_map(|entity| query.get(*entity).ok().map(|number| (*entity, number.0)))
+            .collect();
+        let numbers: Vec<Fraction> = neighbors.iter().map(|(_, number)| *number).collect();
+
+        let (results, dropped) = rules.combine(ev.number, ev.operation, &numbers);
+        for ((entity, _), number) in neighbors.iter().zip(results) {
             update_num_event.send(UpdateBlockNumberEvent {
-                entity: ev.entity,
+                entity: *entity,
                 number,
             });
+        }
 
-            // Update also the last dropped block number
-            if let Some(entity) = last_dropped_block.entity {
-                update_num_event.send(UpdateBlockNumberEvent { entity, number });
-                last_dropped_block.entity = None;
-                last_dropped_block.operation = None;
-            }
+        // Update also the dropped block number
+        if let Some(number) = dropped {
+            update_num_event.send(UpdateBlockNumberEvent {
+                entity: ev.entity,
+                number,
+            });
         }
     }
 }
 
-/// System for updating the block's actual translation (based on `BlockMap` position)
+/// This `Component` animates the block translation from its previous position to the current
+/// `BlockPosition`
+#[derive(Component)]
+struct Slide {
+    from: Vec2,
+    to: Vec2,
+    timer: Timer,
+}
+
+/// This resource tells whether the blocks slide to their new positions or jump there directly.
+/// Selected in the options.
+pub struct Animations(pub bool);
+
+/// Helper function for easing the slide (fast start, slow end)
+fn ease_out(t: f32) -> f32 {
+    1.0 - (1.0 - t).powi(3)
+}
+
+/// System for updating the block's actual translation (based on `BlockMap` position). Blocks
+/// slide to their new position, and the slide takes longer the further the block moves. New
+/// blocks, and all the blocks while animations are off, are placed directly to their position.
 fn update_block_translation(
-    win_size: Res<WindowSize>,
-    mut query: Query<(&BlockPosition, &mut Transform)>, //, Changed<BlockPosition>>,
+    mut commands: Commands,
+    time: Res<Time>,
+    animations: Res<Animations>,
+    size: Res<BoardSize>,
+    mut query: Query<(
+        Entity,
+        &BlockPosition,
+        &mut Transform,
+        Option<&BoardId>,
+        Option<&mut Slide>,
+        ChangeTrackers<BlockPosition>,
+    )>,
 ) {
     // TODO: For some reason block translation would not always update if Changed-filter was being used.
-    for (pos, mut transform) in query.iter_mut() {
-        let trans = get_translation(&win_size.0, &pos.0);
-        transform.translation.x = trans.x;
-        transform.translation.y = trans.y;
+    for (entity, pos, mut transform, board, slide, tracker) in query.iter_mut() {
+        let board = board.map(|board| board.0).unwrap_or(0);
+        let target = get_board_translation(&size, board, &pos.0).truncate();
+        let current = transform.translation.truncate();
+
+        if tracker.is_added() || !animations.0 {
+            transform.translation.x = target.x;
+            transform.translation.y = target.y;
+            if slide.is_some() {
+                commands.entity(entity).remove::<Slide>();
+            }
+            continue;
+        }
+
+        match slide {
+            Some(mut slide) if slide.to == target => {
+                slide.timer.tick(time.delta());
+                let translation = slide.from.lerp(slide.to, ease_out(slide.timer.percent()));
+                transform.translation.x = translation.x;
+                transform.translation.y = translation.y;
+            }
+            _ if current != target => {
+                let blocks = current.distance(target) / BLOCK_SIZE;
+                commands.entity(entity).insert(Slide {
+                    from: current,
+                    to: target,
+                    timer: Timer::from_seconds(SLIDE_DURATION * blocks.sqrt(), false),
+                });
+            }
+            _ => (),
+        }
     }
 }
 
 /// System for handling the number change of a block
 fn update_block_number(
-    mut query: Query<(Entity, &mut Number, &BlockPosition)>,
+    mut query: Query<(&mut Number, &BoardId)>,
     mut event: EventReader<UpdateBlockNumberEvent>,
-    mut block_map: ResMut<BlockMap>,
     mut audio_events: EventWriter<PlaySfxEvent>,
-    mut despawning_blocks: ResMut<DespawningBlocks>,
-    mut score: ResMut<Score>,
+    mut clear_events: EventWriter<ClearBlockEvent>,
+    rules: Res<GameRules>,
+    levels: Res<PerBoard<Level>>,
 ) {
     for ev in event.iter() {
-        if let Ok((entity, mut number, pos)) = query.get_mut(ev.entity) {
-            // Target reached --> Despawn block
-            if ev.number % 10 == 0 {
-                score.0 += number.0;
+        if let Ok((mut number, board)) = query.get_mut(ev.entity) {
+            let level = levels[board.0].number;
+            let target = rules.target_rule(level);
+
+            // Target reached --> Clear block
+            if target.is_target(ev.number, level) {
+                clear_events.send(ClearBlockEvent(ev.entity));
+            } else {
+                audio_events.send(PlaySfxEvent(Sfx::Combination(
+                    target.closeness(ev.number, level),
+                )));
+                number.0 = ev.number
+            }
+        }
+    }
+}
+
+/// System for clearing blocks from the board. Stone blocks next to the cleared blocks crumble.
+fn clear_blocks(
+    query: Query<(&Number, &BlockPosition, &BlockColor, &BlockKind, &BoardId)>,
+    mut events: EventReader<ClearBlockEvent>,
+    mut block_maps: ResMut<PerBoard<BlockMap>>,
+    mut audio_events: EventWriter<PlaySfxEvent>,
+    mut despawning_blocks: ResMut<DespawningBlocks>,
+    mut scores: ResMut<PerBoard<Score>>,
+    mut levels: ResMut<PerBoard<Level>>,
+    mut chains: ResMut<PerBoard<Chain>>,
+    mut toast_events: EventWriter<ShowToastEvent>,
+    mut attack_events: EventWriter<AttackEvent>,
+    mut cleared_events: EventWriter<BlockClearedEvent>,
+    rules: Res<GameRules>,
+    versus: Res<Versus>,
+    language: Res<Language>,
+) {
+    // Number of blocks cleared on each board
+    let mut cleared = vec![0; versus.boards()];
+
+    for ev in events.iter() {
+        let mut clearing = vec![ev.0];
+        while let Some(entity) = clearing.pop() {
+            // Block may already be cleared e.g. by a bomb
+            if despawning_blocks.0.contains_key(&entity) {
+                continue;
+            }
+
+            if let Ok((number, pos, color, kind, board)) = query.get(entity) {
+                let board = board.0;
+                let block_map = &mut block_maps[board];
                 despawning_blocks.0.insert(
                     entity,
                     (
@@ -543,29 +1072,167 @@ fn update_block_number(
                         Timer::from_seconds(0.05, false),
                     ),
                 );
-
                 block_map.set_block(&pos.0, None);
+
+                // Crumbled stones give no points
+                if *kind == BlockKind::STONE {
+                    continue;
+                }
+
+                let points = number.0.trunc().clamp(i32::MIN as i64, i32::MAX as i64) as i32;
+                scores[board].0 = scores[board].0.saturating_add(points);
                 audio_events.send(PlaySfxEvent(Sfx::BlocksCleared));
-            } else {
-                number.0 = ev.number
+                cleared[board] += 1;
+                cleared_events.send(BlockClearedEvent {
+                    board,
+                    number: number.0,
+                    points,
+                    position: pos.0,
+                    color: *color,
+                    kind: *kind,
+                });
+
+                // First clear of the drop continues the chain
+                let chain = &mut chains[board];
+                if !chain.cleared {
+                    chain.cleared = true;
+                    chain.length += 1;
+                    if chain.length > 1 {
+                        audio_events.send(PlaySfxEvent(Sfx::ChainStep(chain.length)));
+                    }
+                }
+
+                let level = &mut levels[board];
+                level.cleared_blocks += 1;
+                if level.cleared_blocks.is_multiple_of(BLOCKS_PER_LEVEL) {
+                    level.number += 1;
+                    audio_events.send(PlaySfxEvent(Sfx::LevelUp));
+                    let player = if *versus == Versus::Off {
+                        String::new()
+                    } else {
+                        format!("{}: ", versus.player_name(board, *language))
+                    };
+                    toast_events.send(ShowToastEvent(format!(
+                        "{}{}",
+                        player,
+                        language.format(TextKey::Level, &[&level.number])
+                    )));
+
+                    // Tell the player if the target changes
+                    let target = rules.target_rule(level.number);
+                    if target != rules.target_rule(level.number - 1) {
+                        toast_events.send(ShowToastEvent(language.format(
+                            TextKey::NewTarget,
+                            &[&target.description(level.number, *language)],
+                        )));
+                    }
+                }
+
+                // Free the stones next to the cleared block
+                for neighbor_pos in get_neighbor_positions(&pos.0) {
+                    if let Some((neighbor, _, BlockKind::STONE)) =
+                        block_map.get_block(&neighbor_pos)
+                    {
+                        clearing.push(neighbor);
+                    }
+                }
+            }
+        }
+    }
+
+    // Cleared blocks attack the opponent in a versus game
+    if *versus != Versus::Off {
+        for (board, cleared) in cleared.into_iter().enumerate() {
+            if cleared > 0 {
+                attack_events.send(AttackEvent {
+                    board,
+                    cleared,
+                    chain: chains[board].length,
+                });
             }
         }
     }
 }
 
+/// System for putting the dropping block on hold. The held block is dropped in its place, or a
+/// new block if nothing was held. Block which has just landed can't be held.
+fn hold_block(
+    mut commands: Commands,
+    actions: Res<PerBoard<Input<PlayerAction>>>,
+    mut held_blocks: ResMut<PerBoard<HeldBlock>>,
+    query: Query<
+        (
+            Entity,
+            &Number,
+            &BlockColor,
+            &Operation,
+            &BlockKind,
+            &BoardId,
+        ),
+        With<DroppingBlock>,
+    >,
+    mut landed_events: EventReader<SpawnSolidBlockEvent>,
+    mut gen_event: EventWriter<RandomizeDroppingBlockEvent>,
+    mut spawn_event: EventWriter<SpawnDroppingBlockEvent>,
+    mut audio_events: EventWriter<PlaySfxEvent>,
+    size: Res<BoardSize>,
+) {
+    let mut landed = vec![];
+    for ev in landed_events.iter().filter(|ev| !ev.inserted) {
+        held_blocks[ev.board].used = false;
+        landed.push(ev.board);
+    }
+
+    for (entity, number, color, operation, kind, board) in query.iter() {
+        let board = board.0;
+        let held = &mut held_blocks[board];
+        if !actions[board].just_pressed(PlayerAction::Hold) || held.used || landed.contains(&board)
+        {
+            continue;
+        }
+
+        held.used = true;
+        let block = held.block.replace(BlockParams {
+            number: number.0,
+            color: *color,
+            operation: *operation,
+            kind: *kind,
+        });
+        commands.entity(entity).despawn_recursive();
+        audio_events.send(PlaySfxEvent(Sfx::ColorSwitched));
+
+        match block {
+            Some(block) => spawn_event.send(SpawnDroppingBlockEvent {
+                board,
+                number: block.number,
+                position: size.initial_position(),
+                color: block.color,
+                operation: block.operation,
+                kind: block.kind,
+            }),
+            None => gen_event.send(RandomizeDroppingBlockEvent(board)),
+        }
+    }
+}
+
+/// System for measuring the time elapsed in the game
+fn tick_game_time(step: Res<SimulationStep>, mut game_time: ResMut<GameTime>) {
+    game_time.0.tick(step.delta);
+}
+
 /// Despawn blocks
 fn despawn_blocks(
     mut commands: Commands,
-    time: Res<Time>,
+    step: Res<SimulationStep>,
     mut despawning_blocks: ResMut<DespawningBlocks>,
     mut query: Query<(&mut Sprite, &mut Visibility)>,
 ) {
     let mut removals = vec![];
     for block in despawning_blocks.0.values_mut() {
-        if block.1.tick(time.delta()).just_finished() {
+        if block.1.tick(step.delta).just_finished() {
             commands.entity(block.0).despawn_recursive();
             removals.push(block.0);
-        } else if block.2.tick(time.delta()).just_finished() {
+        } else if block.2.tick(step.delta).just_finished() {
             if let Ok((mut sprite, mut visibility)) = query.get_mut(block.0) {
                 visibility.is_visible = if visibility.is_visible { false } else { true };
                 sprite.color = Color::WHITE;
@@ -581,60 +1248,76 @@ fn despawn_blocks(
 
 /// System for updating the `Text` child element of the block each time `Number` changes
 pub fn update_block_number_text(
-    mut block_query: Query<(&Number, &Children, &SolidBlock), Changed<Number>>,
+    mut block_query: Query<(&Number, &BlockKind, &Children, &SolidBlock), Changed<Number>>,
     mut child_query: Query<&mut Text>,
 ) {
-    for (number, children, _) in block_query.iter_mut() {
+    for (number, kind, children, _) in block_query.iter_mut() {
         for child in children {
             if let Ok(mut text) = child_query.get_mut(*child) {
-                text.sections[0].value = number.0.to_string();
+                text.sections[0].value = get_solid_block_text(*kind, number.0);
+                text.sections[0].style.font_size = get_block_font_size(&text.sections[0].value);
             }
         }
     }
 }
 /// System for updating the math operation character of the dropping block
 pub fn update_operator_text(
-    mut block_query: Query<(&Number, &Children, &Operation, &DroppingBlock), Changed<Operation>>,
+    mut block_query: Query<
+        (&Number, &BlockKind, &Children, &Operation, &DroppingBlock),
+        Changed<Operation>,
+    >,
     mut child_query: Query<&mut Text>,
 ) {
-    for (number, children, operation, _) in block_query.iter_mut() {
+    for (number, kind, children, operation, _) in block_query.iter_mut() {
         for child in children {
             if let Ok(mut text) = child_query.get_mut(*child) {
-                text.sections[0].value = format!("{}{}", get_operator(*operation), number.0);
+                text.sections[0].value = get_dropping_block_text(*kind, *operation, number.0);
+                text.sections[0].style.font_size = get_block_font_size(&text.sections[0].value);
             }
         }
     }
 }
 
-/// Update the block number color each time BlockColor changes
-pub fn update_block_color(mut query: Query<(&BlockColor, &mut Sprite), Changed<BlockColor>>) {
-    for (block_color, mut sprite) in query.iter_mut() {
-        sprite.color = get_color(*block_color);
-    }
-}
-
-/// Update the score text
-pub fn update_score_text(mut query: Query<&mut Text, With<ScoreText>>, score: Res<Score>) {
-    if let Ok(mut text) = query.get_single_mut() {
-        text.sections[0].value = format!("{}", score.0);
+/// Update the block color each time BlockColor or the palette changes
+pub fn update_block_color(
+    mut query: Query<(
+        &BlockColor,
+        &BlockKind,
+        &mut Sprite,
+        ChangeTrackers<BlockColor>,
+    )>,
+    palette: Res<Palette>,
+) {
+    for (block_color, kind, mut sprite, tracker) in query.iter_mut() {
+        if tracker.is_changed() || palette.is_changed() {
+            sprite.color = palette.sprite_color(*block_color, *kind);
+        }
     }
 }
 
-/// Drop solid block if below square is empty
+/// Drop solid block if below square is empty. Blocks wait until the cleared blocks of their
+/// board are gone.
 pub fn drop_floating_blocks(
-    mut query: Query<&mut BlockPosition, With<SolidBlock>>,
-    block_map: Res<BlockMap>,
+    mut query: Query<(Entity, &mut BlockPosition, &BoardId), With<SolidBlock>>,
+    block_maps: Res<PerBoard<BlockMap>>,
     despawning_blocks: Res<DespawningBlocks>,
     mut event_writer: EventWriter<MoveBlockEvent>,
 ) {
-    if despawning_blocks.0.is_empty() {
-        for mut pos in query.iter_mut() {
-            if block_map
+    let busy_boards: Vec<usize> = query
+        .iter()
+        .filter(|(entity, ..)| despawning_blocks.contains(*entity))
+        .map(|(_, _, board)| board.0)
+        .collect();
+
+    for (_, mut pos, board) in query.iter_mut() {
+        if !busy_boards.contains(&board.0) {
+            if block_maps[board.0]
                 .get_block(&Coords::new(pos.0.x, pos.0.y - 1))
                 .is_none()
             {
                 let new_pos = Coords::new(pos.0.x, pos.0.y - 1);
                 event_writer.send(MoveBlockEvent {
+                    board: board.0,
                     old_pos: pos.0,
                     new_pos,
                 });
@@ -643,3 +1326,40 @@ pub fn drop_floating_blocks(
         }
     }
 }
+
+/// Sets the music intensity based on how close the stack of solid blocks is to the top and how
+/// fast the blocks are dropping. Plays also warning sound when the stack is in the danger zone.
+fn update_danger_level(
+    query: Query<&BlockPosition, With<SolidBlock>>,
+    drop_speeds: Res<PerBoard<DropSpeed>>,
+    mut music_intensity: ResMut<MusicIntensity>,
+    mut audio_events: EventWriter<PlaySfxEvent>,
+    size: Res<BoardSize>,
+) {
+    let stack_height = query.iter().map(|pos| pos.0.y).max().unwrap_or(0);
+
+    let top = size.initial_position().y;
+    let stack_intensity = if stack_height >= top - DANGER_ZONE_HEIGHT {
+        audio_events.send(PlaySfxEvent(Sfx::Danger));
+        1.0
+    } else if stack_height >= top - 2 * DANGER_ZONE_HEIGHT {
+        0.5
+    } else {
+        0.0
+    };
+
+    let drop_speed = drop_speeds
+        .0
+        .iter()
+        .map(|speed| speed.0)
+        .fold(INITIAL_DROP_SPEED, f32::min);
+    let speed_intensity = if drop_speed < INTENSE_DROP_SPEED {
+        1.0
+    } else if drop_speed < FAST_DROP_SPEED {
+        0.5
+    } else {
+        0.0
+    };
+
+    music_intensity.0 = f32::max(stack_intensity, speed_intensity);
+}
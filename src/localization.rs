@@ -0,0 +1,818 @@
+//!
+//! Translations of the texts shown to the player. Each language has its own string table, and
+//! the texts are looked up with `TextKey`. Texts with `{}` placeholders are filled in with
+//! `Language::format`.
+//!
+//! The font of the game has no glyphs for some letters (e.g. ä and ö), so the texts are written
+//! correctly in the tables and the missing letters are replaced only when the text is looked up.
+//!
+use crate::achievements::Achievement;
+use crate::ai::AiSkill;
+use crate::board::BoardPreset;
+use crate::controls::PlayerAction;
+use crate::effects::Effects;
+use crate::in_game::BlockColor;
+use crate::palette::Palette;
+use crate::rules::{Adjacency, GameMode, Resolution, Versus};
+use crate::view::WindowScale;
+use std::fmt::Display;
+
+/// Language of the texts. Selected in the options.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Language {
+    English,
+    Finnish,
+    German,
+}
+
+/// Keys of the translated texts
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum TextKey {
+    // Main menu
+    GameMode(GameMode),
+    AdjacencyOption,
+    Adjacency(Adjacency),
+    ResolutionOption,
+    Resolution(Resolution),
+    VersusOption,
+    Versus(Versus),
+    LanguageOption,
+    PaletteOption,
+    Palette(Palette),
+    EffectsOption,
+    Effects(Effects),
+    Options,
+    HighScores,
+    AchievementsEntry,
+    Credits,
+    Quit,
+    // Options
+    SymbolsOption,
+    On,
+    Off,
+    MusicVolumeOption,
+    SfxVolumeOption,
+    KeyOption,
+    Action(PlayerAction),
+    PressKey,
+    StartLevelOption,
+    BoardSizeOption,
+    BoardPreset(BoardPreset),
+    WindowScaleOption,
+    WindowScale(WindowScale),
+    AnimationsOption,
+    // Pause
+    Paused,
+    Resume,
+    EndGame,
+    // Credits
+    CreditsDesign,
+    CreditsMusic,
+    CreditsEngine,
+    // How to play
+    HowToPlay,
+    HowToPlayControls,
+    HowToPlayRules,
+    Continue,
+    // Game
+    Level,
+    NewTarget,
+    TargetMultipleOf,
+    TargetValue,
+    TargetPrime,
+    TargetSquare,
+    You,
+    Ai,
+    Opponent,
+    Player,
+    SendsBlocks,
+    ConnectionLost,
+    OutOfSync,
+    // HUD
+    HudScore,
+    HudHighScore,
+    HudLevel,
+    HudTime,
+    HudCleared,
+    HudNext,
+    HudHold,
+    // Game over
+    GameOver,
+    NewHighScore,
+    HighScore,
+    Autopilot,
+    YouWin,
+    Wins,
+    Draw,
+    // Undo
+    UndoNotAvailable,
+    UndoLeft,
+    NothingToUndo,
+    // Hints
+    Color(BlockColor),
+    Hint,
+    HintDropHere,
+    KeepStoneLow,
+    BombClears,
+    NothingToCombine,
+    Clears,
+    DroppedBlockClears,
+    HintCost,
+    HintsLeft,
+    // Autopilot
+    AutopilotOn,
+    AutopilotOff,
+    AutopilotSkill,
+    AiSkill(AiSkill),
+    // Music
+    ShuffleOn,
+    ShuffleOff,
+    RepeatAll,
+    RepeatOne,
+    RepeatOff,
+    NowPlaying,
+    // Statistics
+    Statistics,
+    Games,
+    BlocksDropped,
+    BlocksCleared,
+    ClearsPerOperation,
+    AverageValue,
+    MaxChain,
+    ColorSwitches,
+    TimePlayed,
+    DropsPerMinute,
+    // Achievements
+    Achievements,
+    AchievementUnlocked,
+    AchievementName(Achievement),
+    AchievementDescription(Achievement),
+    // Network lobby
+    NetworkGame,
+    HostGame,
+    JoinGame,
+    HostAddress,
+    Connect,
+    WaitingForOpponent,
+    ConnectingTo,
+    Connected,
+    VersionMismatch,
+    NoConnection,
+    Back,
+}
+
+impl Language {
+    /// All the languages in the order they are shown in the menu
+    pub const ALL: [Language; 3] = [Language::English, Language::Finnish, Language::German];
+
+    /// Returns the name of the language in the language itself
+    pub fn name(&self) -> &'static str {
+        match self {
+            Language::English => "English",
+            Language::Finnish => "Suomi",
+            Language::German => "Deutsch",
+        }
+    }
+
+    /// Returns the next language (wraps around)
+    pub fn next(&self) -> Language {
+        let index = Language::ALL.iter().position(|l| l == self).unwrap_or(0);
+        Language::ALL[(index + 1) % Language::ALL.len()]
+    }
+
+    /// Returns the previous language (wraps around)
+    pub fn previous(&self) -> Language {
+        let index = Language::ALL.iter().position(|l| l == self).unwrap_or(0);
+        Language::ALL[(index + Language::ALL.len() - 1) % Language::ALL.len()]
+    }
+
+    /// Returns the translated text
+    pub fn get(&self, key: TextKey) -> String {
+        let text = match self {
+            Language::English => english(key),
+            Language::Finnish => finnish(key),
+            Language::German => german(key),
+        };
+        self.fit_to_font(text)
+    }
+
+    /// Returns the translated text with the `{}` placeholders replaced by the arguments in order
+    pub fn format(&self, key: TextKey, args: &[&dyn Display]) -> String {
+        let text = self.get(key);
+        let mut parts = text.split("{}");
+        let mut result = parts.next().unwrap_or_default().to_string();
+        for (i, part) in parts.enumerate() {
+            if let Some(arg) = args.get(i) {
+                result.push_str(&arg.to_string());
+            }
+            result.push_str(part);
+        }
+        result
+    }
+
+    /// Replaces the letters missing from the font with the ones the language uses in their
+    /// place
+    fn fit_to_font(&self, text: &str) -> String {
+        let mut result = String::with_capacity(text.len());
+        for c in text.chars() {
+            match (self, c) {
+                (Language::German, 'ä') => result.push_str("ae"),
+                (Language::German, 'ö') => result.push_str("oe"),
+                (Language::German, 'Ä') => result.push_str("AE"),
+                (Language::German, 'Ö') => result.push_str("OE"),
+                (Language::German, 'ü') => result.push_str("ue"),
+                (Language::German, 'Ü') => result.push_str("UE"),
+                (Language::German, 'ß') => result.push_str("ss"),
+                (_, 'ä') => result.push('a'),
+                (_, 'ö') => result.push('o'),
+                (_, 'Ä') => result.push('A'),
+                (_, 'Ö') => result.push('O'),
+                (_, 'ü') => result.push('u'),
+                (_, 'Ü') => result.push('U'),
+                _ => result.push(c),
+            }
+        }
+        result
+    }
+}
+
+/// English texts
+fn english(key: TextKey) -> &'static str {
+    match key {
+        TextKey::GameMode(mode) => mode.name(),
+        TextKey::AdjacencyOption => "Adjacency: {}",
+        TextKey::Adjacency(adjacency) => adjacency.name(),
+        TextKey::ResolutionOption => "Dropped block: {}",
+        TextKey::Resolution(resolution) => resolution.name(),
+        TextKey::VersusOption => "Versus: {}",
+        TextKey::Versus(versus) => versus.name(),
+        TextKey::LanguageOption => "Language: {}",
+        TextKey::PaletteOption => "Colors: {}",
+        TextKey::Palette(palette) => palette.name(),
+        TextKey::EffectsOption => "Effects: {}",
+        TextKey::Effects(effects) => effects.name(),
+        TextKey::Options => "Options",
+        TextKey::HighScores => "High scores",
+        TextKey::AchievementsEntry => "Achievements",
+        TextKey::Credits => "Credits",
+        TextKey::Quit => "Quit",
+        TextKey::SymbolsOption => "Block symbols: {}",
+        TextKey::On => "On",
+        TextKey::Off => "Off",
+        TextKey::MusicVolumeOption => "Music volume: {}%",
+        TextKey::SfxVolumeOption => "Sound volume: {}%",
+        TextKey::KeyOption => "{}: {}",
+        TextKey::Action(action) => match action {
+            PlayerAction::MoveLeft => "Move left",
+            PlayerAction::MoveRight => "Move right",
+            PlayerAction::SoftDrop => "Speed up",
+            PlayerAction::SwitchColor => "Switch color",
+            PlayerAction::SelectColor(BlockColor::NONE) => "No color",
+            PlayerAction::SelectColor(BlockColor::BLUE) => "Blue",
+            PlayerAction::SelectColor(BlockColor::PINK) => "Red",
+            PlayerAction::SelectColor(BlockColor::YELLOW) => "Yellow",
+            PlayerAction::SelectColor(BlockColor::GREEN) => "Green",
+            PlayerAction::Hold => "Hold block",
+        },
+        TextKey::PressKey => "Press a key",
+        TextKey::StartLevelOption => "Start level: {}",
+        TextKey::BoardSizeOption => "Board size: {} ({} x {})",
+        TextKey::BoardPreset(preset) => preset.name(),
+        TextKey::WindowScaleOption => "Window scale: {}",
+        TextKey::WindowScale(scale) => scale.name(),
+        TextKey::AnimationsOption => "Animations: {}",
+        TextKey::Paused => "Paused",
+        TextKey::Resume => "Resume",
+        TextKey::EndGame => "End game",
+        TextKey::CreditsDesign => "Game design and coding",
+        TextKey::CreditsMusic => "Music",
+        TextKey::CreditsEngine => "Game engine",
+        TextKey::HowToPlay => "How to play?",
+        TextKey::HowToPlayControls => {
+            "{} / {}: Move\n\
+             {}: Speed up\n\
+             {}: Switch color\n\
+             {}: Quick color\n\
+             {}: Hold block\n\
+             P: Pause\n\
+             M: Mute music\n\
+             1-9: Music tracks"
+        }
+        TextKey::HowToPlayRules => {
+            "Drop the block next to\n\
+             blocks of the same color\n\
+             to calculate with them\n\n\
+             19 +1 = 20\n\
+             64 -4 = 60\n\
+             57 x0 = 0\n\n\
+             Blocks reaching the\n\
+             target are cleared"
+        }
+        TextKey::Continue => "Enter: Continue",
+        TextKey::Level => "Level {}",
+        TextKey::NewTarget => "New {}",
+        TextKey::TargetMultipleOf => "Target: multiple of {}",
+        TextKey::TargetValue => "Target: {}",
+        TextKey::TargetPrime => "Target: prime",
+        TextKey::TargetSquare => "Target: square",
+        TextKey::You => "You",
+        TextKey::Ai => "AI",
+        TextKey::Opponent => "Opponent",
+        TextKey::Player => "Player {}",
+        TextKey::SendsBlocks => "{} sends {} blocks",
+        TextKey::ConnectionLost => "Connection lost",
+        TextKey::OutOfSync => "Game out of sync",
+        TextKey::HudScore => "Score",
+        TextKey::HudHighScore => "High score",
+        TextKey::HudLevel => "Level",
+        TextKey::HudTime => "Time",
+        TextKey::HudCleared => "Cleared",
+        TextKey::HudNext => "Next",
+        TextKey::HudHold => "Hold",
+        TextKey::GameOver => "GAME OVER",
+        TextKey::NewHighScore => "* NEW HIGHSCORE *",
+        TextKey::HighScore => "Hi: {}",
+        TextKey::Autopilot => "Autopilot",
+        TextKey::YouWin => "YOU WIN",
+        TextKey::Wins => "{} WINS",
+        TextKey::Draw => "DRAW",
+        TextKey::UndoNotAvailable => "Undo is available in single player Zen mode",
+        TextKey::UndoLeft => "Undo ({} left)",
+        TextKey::NothingToUndo => "Nothing to undo",
+        TextKey::Color(color) => match color {
+            BlockColor::NONE => "None",
+            BlockColor::BLUE => "Blue (Q)",
+            BlockColor::PINK => "Red (W)",
+            BlockColor::YELLOW => "Yellow (E)",
+            BlockColor::GREEN => "Green (R)",
+        },
+        TextKey::Hint => "Hint: {}",
+        TextKey::HintDropHere => "Hint: Drop here",
+        TextKey::KeepStoneLow => "Keep the stone low",
+        TextKey::BombClears => "Bomb clears {} blocks",
+        TextKey::NothingToCombine => "Nothing to combine, keep the stack low",
+        TextKey::Clears => "{} clears!",
+        TextKey::DroppedBlockClears => "Dropped block {} clears!",
+        TextKey::HintCost => "Hint cost {} points",
+        TextKey::HintsLeft => "Hints left: {}",
+        TextKey::AutopilotOn => "Autopilot: on",
+        TextKey::AutopilotOff => "Autopilot: off",
+        TextKey::AutopilotSkill => "Autopilot skill: {}",
+        TextKey::AiSkill(skill) => skill.name(),
+        TextKey::ShuffleOn => "Shuffle on",
+        TextKey::ShuffleOff => "Shuffle off",
+        TextKey::RepeatAll => "Repeat all",
+        TextKey::RepeatOne => "Repeat one",
+        TextKey::RepeatOff => "Repeat off",
+        TextKey::NowPlaying => "Now playing",
+        TextKey::Statistics => "STATISTICS",
+        TextKey::Games => "Games: {}",
+        TextKey::BlocksDropped => "Blocks dropped: {}",
+        TextKey::BlocksCleared => "Blocks cleared: {}",
+        TextKey::ClearsPerOperation => "Clears: {}",
+        TextKey::AverageValue => "Average value: {}",
+        TextKey::MaxChain => "Max chain: {}",
+        TextKey::ColorSwitches => "Color switches: {}",
+        TextKey::TimePlayed => "Time played: {}",
+        TextKey::DropsPerMinute => "Drops per minute: {}",
+        TextKey::Achievements => "ACHIEVEMENTS {}/{}",
+        TextKey::AchievementUnlocked => "Achievement: {}",
+        TextKey::AchievementName(achievement) => achievement.name(),
+        TextKey::AchievementDescription(achievement) => achievement.description(),
+        TextKey::NetworkGame => "NETWORK GAME",
+        TextKey::HostGame => "H: Host game",
+        TextKey::JoinGame => "J: Join game",
+        TextKey::HostAddress => "Host address:",
+        TextKey::Connect => "Return: Connect",
+        TextKey::WaitingForOpponent => "Waiting for opponent on port {}",
+        TextKey::ConnectingTo => "Connecting to {}",
+        TextKey::Connected => "Connected",
+        TextKey::VersionMismatch => "Opponent has another version of the game",
+        TextKey::NoConnection => "No connection: {}",
+        TextKey::Back => "Esc: Back",
+    }
+}
+
+/// Finnish texts
+fn finnish(key: TextKey) -> &'static str {
+    match key {
+        TextKey::GameMode(mode) => match mode {
+            GameMode::Classic => "Klassinen",
+            GameMode::Advanced => "Edistynyt",
+            GameMode::Fractions => "Murtoluvut",
+            GameMode::Unbounded => "Rajaton",
+            GameMode::Zen => "Zen",
+        },
+        TextKey::AdjacencyOption => "Naapurit: {}",
+        TextKey::Adjacency(adjacency) => match adjacency {
+            Adjacency::Flood => "Alue",
+            Adjacency::Direct => "Vierekkäiset",
+            Adjacency::EightWay => "8 suuntaa",
+            Adjacency::LineOfSight => "Näkölinja",
+        },
+        TextKey::ResolutionOption => "Pudotettu pala: {}",
+        TextKey::Resolution(resolution) => match resolution {
+            Resolution::First => "Ensimmäinen tulos",
+            Resolution::Last => "Viimeinen tulos",
+            Resolution::Sum => "Tulosten summa",
+            Resolution::Keep => "Oma luku",
+        },
+        TextKey::VersusOption => "Kaksinpeli: {}",
+        TextKey::Versus(versus) => match versus {
+            Versus::Off => "Ei",
+            Versus::TwoPlayers => "2 pelaajaa",
+            Versus::Computer => "Tekoäly",
+            Versus::Network => "Verkko",
+        },
+        TextKey::LanguageOption => "Kieli: {}",
+        TextKey::PaletteOption => "Värit: {}",
+        TextKey::Palette(palette) => match palette {
+            Palette::Default => "Oletus",
+            Palette::Deuteranopia => "Deuteranopia",
+            Palette::Protanopia => "Protanopia",
+            Palette::Tritanopia => "Tritanopia",
+            Palette::HighContrast => "Suuri kontrasti",
+        },
+        TextKey::EffectsOption => "Efektit: {}",
+        TextKey::Effects(effects) => match effects {
+            Effects::All => "Kaikki",
+            Effects::NoMotion => "Ei tärinää eikä välähdyksiä",
+            Effects::Off => "Pois",
+        },
+        TextKey::Options => "Asetukset",
+        TextKey::HighScores => "Ennätykset",
+        TextKey::AchievementsEntry => "Saavutukset",
+        TextKey::Credits => "Tekijät",
+        TextKey::Quit => "Lopeta",
+        TextKey::SymbolsOption => "Palikoiden symbolit: {}",
+        TextKey::On => "Päällä",
+        TextKey::Off => "Pois",
+        TextKey::MusicVolumeOption => "Musiikin voimakkuus: {}%",
+        TextKey::SfxVolumeOption => "Äänten voimakkuus: {}%",
+        TextKey::KeyOption => "{}: {}",
+        TextKey::Action(action) => match action {
+            PlayerAction::MoveLeft => "Vasemmalle",
+            PlayerAction::MoveRight => "Oikealle",
+            PlayerAction::SoftDrop => "Nopeuta",
+            PlayerAction::SwitchColor => "Vaihda väri",
+            PlayerAction::SelectColor(BlockColor::NONE) => "Ei väriä",
+            PlayerAction::SelectColor(BlockColor::BLUE) => "Sininen",
+            PlayerAction::SelectColor(BlockColor::PINK) => "Punainen",
+            PlayerAction::SelectColor(BlockColor::YELLOW) => "Keltainen",
+            PlayerAction::SelectColor(BlockColor::GREEN) => "Vihreä",
+            PlayerAction::Hold => "Pidä pala",
+        },
+        TextKey::PressKey => "Paina näppäintä",
+        TextKey::StartLevelOption => "Aloitustaso: {}",
+        TextKey::BoardSizeOption => "Pelialue: {} ({} x {})",
+        TextKey::BoardPreset(preset) => match preset {
+            BoardPreset::Small => "Pieni",
+            BoardPreset::Medium => "Keskikokoinen",
+            BoardPreset::Classic => "Klassinen",
+        },
+        TextKey::WindowScaleOption => "Ikkunan koko: {}",
+        TextKey::WindowScale(scale) => match scale {
+            WindowScale::Small => "Pieni",
+            WindowScale::Medium => "Keskikokoinen",
+            WindowScale::Large => "Suuri",
+        },
+        TextKey::AnimationsOption => "Animaatiot: {}",
+        TextKey::Paused => "Tauko",
+        TextKey::Resume => "Jatka",
+        TextKey::EndGame => "Lopeta peli",
+        TextKey::CreditsDesign => "Pelisuunnittelu ja ohjelmointi",
+        TextKey::CreditsMusic => "Musiikki",
+        TextKey::CreditsEngine => "Pelimoottori",
+        TextKey::HowToPlay => "Näin pelaat",
+        TextKey::HowToPlayControls => {
+            "{} / {}: Siirrä\n\
+             {}: Nopeuta\n\
+             {}: Vaihda väriä\n\
+             {}: Pikaväri\n\
+             {}: Pidä palikka\n\
+             P: Tauko\n\
+             M: Mykistä musiikki\n\
+             1-9: Kappaleet"
+        }
+        TextKey::HowToPlayRules => {
+            "Pudota pala samanvärisen\n\
+             palan viereen laskeaksesi\n\
+             niillä\n\n\
+             19 +1 = 20\n\
+             64 -4 = 60\n\
+             57 x0 = 0\n\n\
+             Kohteen saavuttavat\n\
+             palat poistuvat"
+        }
+        TextKey::Continue => "Enter: Jatka",
+        TextKey::Level => "Taso {}",
+        TextKey::NewTarget => "Uusi {}",
+        TextKey::TargetMultipleOf => "Kohde: {}:n monikerta",
+        TextKey::TargetValue => "Kohde: {}",
+        TextKey::TargetPrime => "Kohde: alkuluku",
+        TextKey::TargetSquare => "Kohde: neliöluku",
+        TextKey::You => "Sinä",
+        TextKey::Ai => "Tekoäly",
+        TextKey::Opponent => "Vastustaja",
+        TextKey::Player => "Pelaaja {}",
+        TextKey::SendsBlocks => "{} lähettää {} palaa",
+        TextKey::ConnectionLost => "Yhteys katkesi",
+        TextKey::OutOfSync => "Peli ei ole enää tahdissa",
+        TextKey::HudScore => "Pisteet",
+        TextKey::HudHighScore => "Ennätys",
+        TextKey::HudLevel => "Taso",
+        TextKey::HudTime => "Aika",
+        TextKey::HudCleared => "Poistettu",
+        TextKey::HudNext => "Seuraava",
+        TextKey::HudHold => "Pidossa",
+        TextKey::GameOver => "PELI PÄÄTTYI",
+        TextKey::NewHighScore => "* UUSI ENNÄTYS *",
+        TextKey::HighScore => "Ennätys: {}",
+        TextKey::Autopilot => "Autopilotti",
+        TextKey::YouWin => "VOITIT",
+        TextKey::Wins => "{} VOITTI",
+        TextKey::Draw => "TASAPELI",
+        TextKey::UndoNotAvailable => "Kumoaminen on käytössä yksinpelin Zen-tilassa",
+        TextKey::UndoLeft => "Kumottu ({} jäljellä)",
+        TextKey::NothingToUndo => "Ei kumottavaa",
+        TextKey::Color(color) => match color {
+            BlockColor::NONE => "Ei väriä",
+            BlockColor::BLUE => "Sininen (Q)",
+            BlockColor::PINK => "Punainen (W)",
+            BlockColor::YELLOW => "Keltainen (E)",
+            BlockColor::GREEN => "Vihreä (R)",
+        },
+        TextKey::Hint => "Vihje: {}",
+        TextKey::HintDropHere => "Vihje: Pudota tähän",
+        TextKey::KeepStoneLow => "Pidä kivi alhaalla",
+        TextKey::BombClears => "Pommi poistaa {} palaa",
+        TextKey::NothingToCombine => "Ei yhdistettävää, pidä pino matalana",
+        TextKey::Clears => "{} poistuu!",
+        TextKey::DroppedBlockClears => "Pudotettu pala {} poistuu!",
+        TextKey::HintCost => "Vihje maksoi {} pistettä",
+        TextKey::HintsLeft => "Vihjeitä jäljellä: {}",
+        TextKey::AutopilotOn => "Autopilotti: päällä",
+        TextKey::AutopilotOff => "Autopilotti: pois",
+        TextKey::AutopilotSkill => "Autopilotin taito: {}",
+        TextKey::AiSkill(skill) => match skill {
+            AiSkill::Easy => "Helppo",
+            AiSkill::Normal => "Normaali",
+            AiSkill::Hard => "Vaikea",
+        },
+        TextKey::ShuffleOn => "Sekoitus päällä",
+        TextKey::ShuffleOff => "Sekoitus pois",
+        TextKey::RepeatAll => "Toista kaikki",
+        TextKey::RepeatOne => "Toista yksi",
+        TextKey::RepeatOff => "Ei toistoa",
+        TextKey::NowPlaying => "Nyt soi",
+        TextKey::Statistics => "TILASTOT",
+        TextKey::Games => "Pelejä: {}",
+        TextKey::BlocksDropped => "Pudotetut palat: {}",
+        TextKey::BlocksCleared => "Poistetut palat: {}",
+        TextKey::ClearsPerOperation => "Poistot: {}",
+        TextKey::AverageValue => "Keskiarvo: {}",
+        TextKey::MaxChain => "Pisin ketju: {}",
+        TextKey::ColorSwitches => "Värinvaihdot: {}",
+        TextKey::TimePlayed => "Peliaika: {}",
+        TextKey::DropsPerMinute => "Pudotuksia minuutissa: {}",
+        TextKey::Achievements => "SAAVUTUKSET {}/{}",
+        TextKey::AchievementUnlocked => "Saavutus: {}",
+        TextKey::AchievementName(achievement) => match achievement {
+            Achievement::Clear99 => "Ysiysi",
+            Achievement::TripleClear => "Hattutemppu",
+            Achievement::OnlyAddition => "Plussaaja",
+            Achievement::Level10 => "Kaksinumeroinen",
+            Achievement::Chain5 => "Ketjureaktio",
+        },
+        TextKey::AchievementDescription(achievement) => match achievement {
+            Achievement::Clear99 => "Poista 99",
+            Achievement::TripleClear => "Poista kolme palaa yhdellä pudotuksella",
+            Achievement::OnlyAddition => "Käytä vain yhteenlaskua koko pelin ajan",
+            Achievement::Level10 => "Saavuta taso 10",
+            Achievement::Chain5 => "Poista paloja 5 pudotuksella peräkkäin",
+        },
+        TextKey::NetworkGame => "VERKKOPELI",
+        TextKey::HostGame => "H: Isännöi peliä",
+        TextKey::JoinGame => "J: Liity peliin",
+        TextKey::HostAddress => "Isännän osoite:",
+        TextKey::Connect => "Enter: Yhdistä",
+        TextKey::WaitingForOpponent => "Odotetaan vastustajaa portissa {}",
+        TextKey::ConnectingTo => "Yhdistetään {}",
+        TextKey::Connected => "Yhdistetty",
+        TextKey::VersionMismatch => "Vastustajalla on eri versio pelistä",
+        TextKey::NoConnection => "Ei yhteyttä: {}",
+        TextKey::Back => "Esc: Takaisin",
+    }
+}
+
+/// German texts
+fn german(key: TextKey) -> &'static str {
+    match key {
+        TextKey::GameMode(mode) => match mode {
+            GameMode::Classic => "Klassisch",
+            GameMode::Advanced => "Fortgeschritten",
+            GameMode::Fractions => "Brüche",
+            GameMode::Unbounded => "Grenzenlos",
+            GameMode::Zen => "Zen",
+        },
+        TextKey::AdjacencyOption => "Nachbarn: {}",
+        TextKey::Adjacency(adjacency) => match adjacency {
+            Adjacency::Flood => "Fläche",
+            Adjacency::Direct => "Direkt",
+            Adjacency::EightWay => "8 Richtungen",
+            Adjacency::LineOfSight => "Sichtlinie",
+        },
+        TextKey::ResolutionOption => "Fallender Block: {}",
+        TextKey::Resolution(resolution) => match resolution {
+            Resolution::First => "Erstes Ergebnis",
+            Resolution::Last => "Letztes Ergebnis",
+            Resolution::Sum => "Summe",
+            Resolution::Keep => "Eigene Zahl",
+        },
+        TextKey::VersusOption => "Duell: {}",
+        TextKey::Versus(versus) => match versus {
+            Versus::Off => "Aus",
+            Versus::TwoPlayers => "2 Spieler",
+            Versus::Computer => "Gegen KI",
+            Versus::Network => "Netzwerk",
+        },
+        TextKey::LanguageOption => "Sprache: {}",
+        TextKey::PaletteOption => "Farben: {}",
+        TextKey::Palette(palette) => match palette {
+            Palette::Default => "Standard",
+            Palette::Deuteranopia => "Deuteranopie",
+            Palette::Protanopia => "Protanopie",
+            Palette::Tritanopia => "Tritanopie",
+            Palette::HighContrast => "Hoher Kontrast",
+        },
+        TextKey::EffectsOption => "Effekte: {}",
+        TextKey::Effects(effects) => match effects {
+            Effects::All => "Alle",
+            Effects::NoMotion => "Ohne Wackeln und Blitze",
+            Effects::Off => "Aus",
+        },
+        TextKey::Options => "Optionen",
+        TextKey::HighScores => "Bestenliste",
+        TextKey::AchievementsEntry => "Erfolge",
+        TextKey::Credits => "Mitwirkende",
+        TextKey::Quit => "Beenden",
+        TextKey::SymbolsOption => "Blocksymbole: {}",
+        TextKey::On => "An",
+        TextKey::Off => "Aus",
+        TextKey::MusicVolumeOption => "Musiklautstärke: {}%",
+        TextKey::SfxVolumeOption => "Soundlautstärke: {}%",
+        TextKey::KeyOption => "{}: {}",
+        TextKey::Action(action) => match action {
+            PlayerAction::MoveLeft => "Nach links",
+            PlayerAction::MoveRight => "Nach rechts",
+            PlayerAction::SoftDrop => "Beschleunigen",
+            PlayerAction::SwitchColor => "Farbe wechseln",
+            PlayerAction::SelectColor(BlockColor::NONE) => "Keine Farbe",
+            PlayerAction::SelectColor(BlockColor::BLUE) => "Blau",
+            PlayerAction::SelectColor(BlockColor::PINK) => "Rot",
+            PlayerAction::SelectColor(BlockColor::YELLOW) => "Gelb",
+            PlayerAction::SelectColor(BlockColor::GREEN) => "Grün",
+            PlayerAction::Hold => "Block halten",
+        },
+        TextKey::PressKey => "Taste drücken",
+        TextKey::StartLevelOption => "Startlevel: {}",
+        TextKey::BoardSizeOption => "Spielfeld: {} ({} x {})",
+        TextKey::BoardPreset(preset) => match preset {
+            BoardPreset::Small => "Klein",
+            BoardPreset::Medium => "Mittel",
+            BoardPreset::Classic => "Klassisch",
+        },
+        TextKey::WindowScaleOption => "Fenstergröße: {}",
+        TextKey::WindowScale(scale) => match scale {
+            WindowScale::Small => "Klein",
+            WindowScale::Medium => "Mittel",
+            WindowScale::Large => "Groß",
+        },
+        TextKey::AnimationsOption => "Animationen: {}",
+        TextKey::Paused => "Pause",
+        TextKey::Resume => "Weiter",
+        TextKey::EndGame => "Spiel beenden",
+        TextKey::CreditsDesign => "Spieldesign und Programmierung",
+        TextKey::CreditsMusic => "Musik",
+        TextKey::CreditsEngine => "Spiel-Engine",
+        TextKey::HowToPlay => "Spielanleitung",
+        TextKey::HowToPlayControls => {
+            "{} / {}: Bewegen\n\
+             {}: Beschleunigen\n\
+             {}: Farbe wechseln\n\
+             {}: Schnellfarbe\n\
+             {}: Block halten\n\
+             P: Pause\n\
+             M: Musik stumm\n\
+             1-9: Musikstücke"
+        }
+        TextKey::HowToPlayRules => {
+            "Lass den Block neben\n\
+             gleichfarbige Blöcke\n\
+             fallen, um zu rechnen\n\n\
+             19 +1 = 20\n\
+             64 -4 = 60\n\
+             57 x0 = 0\n\n\
+             Blöcke, die das Ziel\n\
+             erreichen, verschwinden"
+        }
+        TextKey::Continue => "Enter: Weiter",
+        TextKey::Level => "Level {}",
+        TextKey::NewTarget => "Neues {}",
+        TextKey::TargetMultipleOf => "Ziel: Vielfaches von {}",
+        TextKey::TargetValue => "Ziel: {}",
+        TextKey::TargetPrime => "Ziel: Primzahl",
+        TextKey::TargetSquare => "Ziel: Quadratzahl",
+        TextKey::You => "Du",
+        TextKey::Ai => "KI",
+        TextKey::Opponent => "Gegner",
+        TextKey::Player => "Spieler {}",
+        TextKey::SendsBlocks => "{} schickt {} Blöcke",
+        TextKey::ConnectionLost => "Verbindung verloren",
+        TextKey::OutOfSync => "Spiel nicht mehr synchron",
+        TextKey::HudScore => "Punkte",
+        TextKey::HudHighScore => "Rekord",
+        TextKey::HudLevel => "Level",
+        TextKey::HudTime => "Zeit",
+        TextKey::HudCleared => "Entfernt",
+        TextKey::HudNext => "Nächster",
+        TextKey::HudHold => "Halten",
+        TextKey::GameOver => "SPIEL VORBEI",
+        TextKey::NewHighScore => "* NEUER REKORD *",
+        TextKey::HighScore => "Rekord: {}",
+        TextKey::Autopilot => "Autopilot",
+        TextKey::YouWin => "DU GEWINNST",
+        TextKey::Wins => "{} GEWINNT",
+        TextKey::Draw => "UNENTSCHIEDEN",
+        TextKey::UndoNotAvailable => "Rückgängig nur im Zen-Modus für einen Spieler",
+        TextKey::UndoLeft => "Rückgängig ({} übrig)",
+        TextKey::NothingToUndo => "Nichts rückgängig zu machen",
+        TextKey::Color(color) => match color {
+            BlockColor::NONE => "Keine",
+            BlockColor::BLUE => "Blau (Q)",
+            BlockColor::PINK => "Rot (W)",
+            BlockColor::YELLOW => "Gelb (E)",
+            BlockColor::GREEN => "Grün (R)",
+        },
+        TextKey::Hint => "Tipp: {}",
+        TextKey::HintDropHere => "Tipp: Hier fallen lassen",
+        TextKey::KeepStoneLow => "Halte den Stein unten",
+        TextKey::BombClears => "Bombe entfernt {} Blöcke",
+        TextKey::NothingToCombine => "Nichts zu kombinieren, halte den Stapel niedrig",
+        TextKey::Clears => "{} verschwindet!",
+        TextKey::DroppedBlockClears => "Fallender Block {} verschwindet!",
+        TextKey::HintCost => "Tipp kostet {} Punkte",
+        TextKey::HintsLeft => "Tipps übrig: {}",
+        TextKey::AutopilotOn => "Autopilot: an",
+        TextKey::AutopilotOff => "Autopilot: aus",
+        TextKey::AutopilotSkill => "Autopilot-Stärke: {}",
+        TextKey::AiSkill(skill) => match skill {
+            AiSkill::Easy => "Leicht",
+            AiSkill::Normal => "Normal",
+            AiSkill::Hard => "Schwer",
+        },
+        TextKey::ShuffleOn => "Zufall an",
+        TextKey::ShuffleOff => "Zufall aus",
+        TextKey::RepeatAll => "Alle wiederholen",
+        TextKey::RepeatOne => "Eins wiederholen",
+        TextKey::RepeatOff => "Wiederholung aus",
+        TextKey::NowPlaying => "Es läuft",
+        TextKey::Statistics => "STATISTIK",
+        TextKey::Games => "Spiele: {}",
+        TextKey::BlocksDropped => "Blöcke gesetzt: {}",
+        TextKey::BlocksCleared => "Blöcke entfernt: {}",
+        TextKey::ClearsPerOperation => "Entfernt mit: {}",
+        TextKey::AverageValue => "Durchschnittswert: {}",
+        TextKey::MaxChain => "Längste Kette: {}",
+        TextKey::ColorSwitches => "Farbwechsel: {}",
+        TextKey::TimePlayed => "Spielzeit: {}",
+        TextKey::DropsPerMinute => "Blöcke pro Minute: {}",
+        TextKey::Achievements => "ERFOLGE {}/{}",
+        TextKey::AchievementUnlocked => "Erfolg: {}",
+        TextKey::AchievementName(achievement) => match achievement {
+            Achievement::Clear99 => "Neunundneunzig",
+            Achievement::TripleClear => "Hattrick",
+            Achievement::OnlyAddition => "Immer plus",
+            Achievement::Level10 => "Zweistellig",
+            Achievement::Chain5 => "Kettenreaktion",
+        },
+        TextKey::AchievementDescription(achievement) => match achievement {
+            Achievement::Clear99 => "Entferne eine 99",
+            Achievement::TripleClear => "Entferne drei Blöcke mit einem Block",
+            Achievement::OnlyAddition => "Nur Addition für ein ganzes Spiel",
+            Achievement::Level10 => "Erreiche Level 10",
+            Achievement::Chain5 => "Entferne Blöcke 5-mal in Folge",
+        },
+        TextKey::NetworkGame => "NETZWERKSPIEL",
+        TextKey::HostGame => "H: Spiel hosten",
+        TextKey::JoinGame => "J: Spiel beitreten",
+        TextKey::HostAddress => "Host-Adresse:",
+        TextKey::Connect => "Enter: Verbinden",
+        TextKey::WaitingForOpponent => "Warte auf Gegner an Port {}",
+        TextKey::ConnectingTo => "Verbinde mit {}",
+        TextKey::Connected => "Verbunden",
+        TextKey::VersionMismatch => "Gegner hat eine andere Spielversion",
+        TextKey::NoConnection => "Keine Verbindung: {}",
+        TextKey::Back => "Esc: Zurück",
+    }
+}
@@ -7,29 +7,69 @@
 //! Music by Eric Matyas
 //! www.soundimage.org
 //!
+use achievements::AchievementsPlugin;
+use ai::AiPlugin;
 use audio::AudioPlugin;
 use bevy::prelude::*;
 use bevy::render::camera::ScalingMode;
+use bevy::window::WindowResizeConstraints;
 use constants::prelude::*;
+use controls::ControlsPlugin;
+use credits::CreditsPlugin;
+use effects::EffectsPlugin;
 use game_over::GameOverPlugin;
+use high_scores::HighScoresPlugin;
+use hint::HintPlugin;
 use how_to_play::HowToPlayPlugin;
-use in_game::{get_translation, BlockPosition, InGamePlugin};
+use hud::HudPlugin;
+use in_game::InGamePlugin;
 use menu::MenuPlugin;
+#[cfg(not(target_arch = "wasm32"))]
+use net::NetPlugin;
+use options::OptionsPlugin;
+use palette::PalettePlugin;
+use pause::PausePlugin;
+use rules::{Adjacency, GameMode, Resolution, Versus};
+use stats::StatsPlugin;
+use toast::ToastPlugin;
+use undo::UndoPlugin;
+use versus::VersusPlugin;
+use view::ViewPlugin;
+mod achievements;
+mod ai;
 mod audio;
 mod board;
 mod constants;
+mod controls;
+mod credits;
+mod effects;
+mod fraction;
 mod game_over;
+#[cfg(test)]
+mod headless;
+mod high_scores;
+mod hint;
 mod how_to_play;
+mod hud;
 mod in_game;
+mod localization;
 mod menu;
-
-/// Resource for holding the window size
-pub struct WindowSize(Vec2);
+#[cfg(not(target_arch = "wasm32"))]
+mod net;
+mod options;
+mod palette;
+mod pause;
+mod rules;
+mod stats;
+mod storage;
+mod synth;
+mod toast;
+mod undo;
+mod versus;
+mod view;
 
 pub mod prelude {
-    pub use super::{
-        EdgeBlock, GameObject, GameState, HighScore, MenuNode, MyAssets, Score, WindowSize,
-    };
+    pub use super::{EdgeBlock, GameObject, GameState, HighScore, MenuNode, MyAssets, Score};
 }
 
 /// Tag for MenuItems
@@ -55,6 +95,13 @@ pub enum GameState {
     InGame,
     GameOver,
     HowToPlay,
+    Lobby,
+    Stats,
+    Achievements,
+    Options,
+    HighScores,
+    Credits,
+    Paused,
 }
 
 /// Block image texture and text style is preloaded in this resource
@@ -62,53 +109,69 @@ pub struct MyAssets {
     text_style: TextStyle,
     block_texture: Handle<Image>,
     edge_texture: Handle<Image>,
-    how_to_play_1_texture: Handle<Image>,
-    how_to_play_2_texture: Handle<Image>,
-}
-
-/// This `Event` is sent when edge blocks need to be spawned
-pub struct SpawnEdgeBlockEvent {
-    position: Coords,
 }
 
 /// Resource for storing the score
+#[derive(Default)]
 pub struct Score(i32);
 pub struct HighScore(i32);
 
 fn main() {
-    let win_size = Vec2::new(WORLD_HEIGHT * ASPECT_RATIO, WORLD_HEIGHT);
-
-    App::new()
-        .insert_resource(WindowDescriptor {
-            title: "Combine".into(),
-            width: win_size.x,
-            height: win_size.y,
-            resizable: false,
-            ..Default::default()
-        })
-        .insert_resource(ClearColor(BACKGROUND_COLOR))
-        .insert_resource(WindowSize(win_size))
-        .insert_resource(Score(0))
-        .insert_resource(HighScore(0))
-        .add_event::<LaunchMenuEvent>()
-        .add_state(GameState::Init)
-        .add_plugins(DefaultPlugins)
-        .add_plugin(AudioPlugin)
-        .add_plugin(MenuPlugin)
-        .add_plugin(HowToPlayPlugin)
-        .add_plugin(InGamePlugin)
-        .add_plugin(GameOverPlugin)
-        .add_startup_system(game_setup)
-        .add_system_set(SystemSet::on_update(GameState::Init).with_system(launch_menu))
-        .add_system_set(SystemSet::on_update(GameState::Init).with_system(spawn_edge_block))
-        .add_event::<SpawnEdgeBlockEvent>()
-        .run();
+    let mut app = App::new();
+    app.insert_resource(WindowDescriptor {
+        title: "Combine".into(),
+        width: WINDOW_WIDTH,
+        height: WINDOW_HEIGHT,
+        resize_constraints: WindowResizeConstraints {
+            min_width: WINDOW_WIDTH / 2.0,
+            min_height: WINDOW_HEIGHT / 2.0,
+            ..default()
+        },
+        ..Default::default()
+    })
+    .insert_resource(ClearColor(BACKGROUND_COLOR))
+    .insert_resource(HighScore(0))
+    .insert_resource(GameMode::Classic)
+    .insert_resource(Adjacency::Flood)
+    .insert_resource(Resolution::First)
+    .insert_resource(Versus::Off)
+    .add_event::<LaunchMenuEvent>()
+    .add_state(GameState::Init)
+    .add_plugins(DefaultPlugins)
+    .add_plugin(ViewPlugin)
+    .add_plugin(ToastPlugin)
+    .add_plugin(AudioPlugin)
+    .add_plugin(ControlsPlugin)
+    .add_plugin(AiPlugin)
+    .add_plugin(MenuPlugin)
+    .add_plugin(OptionsPlugin)
+    .add_plugin(HowToPlayPlugin)
+    .add_plugin(InGamePlugin)
+    .add_plugin(PausePlugin)
+    .add_plugin(HudPlugin)
+    .add_plugin(PalettePlugin)
+    .add_plugin(EffectsPlugin)
+    .add_plugin(HintPlugin)
+    .add_plugin(UndoPlugin)
+    .add_plugin(VersusPlugin)
+    .add_plugin(StatsPlugin)
+    .add_plugin(AchievementsPlugin)
+    .add_plugin(HighScoresPlugin)
+    .add_plugin(CreditsPlugin)
+    .add_plugin(GameOverPlugin)
+    .add_startup_system(game_setup)
+    .add_system_set(SystemSet::on_update(GameState::Init).with_system(launch_menu));
+
+    // Network game uses TCP sockets and threads, which aren't available in the browser
+    #[cfg(not(target_arch = "wasm32"))]
+    app.add_plugin(NetPlugin);
+
+    app.run();
 }
 
 pub fn game_setup(
     mut commands: Commands,
     mut launch_event: EventWriter<LaunchMenuEvent>,
-    mut block_event: EventWriter<SpawnEdgeBlockEvent>,
     asset_server: Res<AssetServer>,
 ) {
     let mut camera = Camera2dBundle::default();
@@ -124,35 +187,14 @@ pub fn game_setup(
         },
         block_texture: asset_server.load("pixel-block.png"),
         edge_texture: asset_server.load("edge-block.png"),
-        how_to_play_1_texture: asset_server.load("how-to-play1.png"),
-        how_to_play_2_texture: asset_server.load("how-to-play2.png"),
     });
 
-    // No re-scaling on windows resize
+    // Camera view is fitted to the window by the `ViewPlugin`
     camera.projection.scaling_mode = ScalingMode::None;
-    camera.projection.scale = WORLD_HEIGHT / 1.5;
-
-    // Fix the aspect ratio
-    camera.projection.top = 1.0;
-    camera.projection.bottom = -1.0;
-    camera.projection.left = -1.0 * ASPECT_RATIO;
-    camera.projection.right = 1.0 * ASPECT_RATIO;
 
     // Spawn the camera
     commands.spawn_bundle(camera);
 
-    // Initialize the game area (edges)
-    for y in -1..=BOARD_SIZE.height as i32 {
-        for x in -1..=BOARD_SIZE.width as i32 {
-            if y < 0 || y >= BOARD_SIZE.height as i32 || x < 0 || x >= BOARD_SIZE.width as i32 {
-                let coords = Coords::new(x, y);
-                block_event.send(SpawnEdgeBlockEvent {
-                    position: coords.clone(),
-                });
-            }
-        }
-    }
-
     launch_event.send(LaunchMenuEvent);
 }
 
@@ -166,23 +208,3 @@ fn launch_menu(
             .expect("Failed to set GameState::Menu");
     }
 }
-
-/// System for spawning edge blocks
-fn spawn_edge_block(
-    mut commands: Commands,
-    mut event_reader: EventReader<SpawnEdgeBlockEvent>,
-    win_size: Res<WindowSize>,
-    my_assets: Res<MyAssets>,
-) {
-    for ev in event_reader.iter() {
-        let _ = commands
-            .spawn_bundle(SpriteBundle {
-                texture: my_assets.edge_texture.clone(),
-                transform: Transform::from_translation(get_translation(&win_size.0, &ev.position)),
-                ..default()
-            })
-            .insert(EdgeBlock)
-            .insert(BlockPosition(ev.position))
-            .id();
-    }
-}
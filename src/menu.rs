@@ -1,10 +1,65 @@
+use crate::audio::{PlaySfxEvent, Sfx};
+use crate::controls::MenuAction;
+use crate::how_to_play::AfterHowToPlay;
+use crate::localization::{Language, TextKey};
 use crate::prelude::*;
+use crate::rules::{Adjacency, GameMode, Resolution, Versus};
+use crate::view::BoardArea;
+use bevy::app::AppExit;
 use bevy::prelude::*;
-use bevy::window::close_on_esc;
 
-/// Identifier for the Press Start
+/// Color of the focused menu entry
+const FOCUS_COLOR: Color = Color::rgb(0.75, 0.1, 0.3);
+
+/// Color of the other menu entries
+const ENTRY_COLOR: Color = Color::BLACK;
+
+/// Entry of a navigable menu screen. Entries are focused in the order of their index with Up /
+/// Down keys.
 #[derive(Component)]
-struct PressStartText;
+pub struct FocusEntry(pub usize);
+
+/// This resource holds the index of the focused entry on the current menu screen
+#[derive(Default)]
+pub struct MenuFocus(pub usize);
+
+/// Focus of the menu entries is moved by the system labeled with this. Systems consuming the
+/// menu actions for other purposes should be run before it.
+#[derive(SystemLabel, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct MenuNavigationLabel;
+
+/// Entries of the main menu
+#[derive(Component, Copy, Clone, PartialEq, Eq, Debug)]
+enum MainEntry {
+    Play,
+    Versus,
+    Adjacency,
+    Resolution,
+    Options,
+    HighScores,
+    Stats,
+    Achievements,
+    HowToPlay,
+    Credits,
+    Quit,
+}
+
+impl MainEntry {
+    /// All the entries in the order they are shown in the menu
+    const ALL: [MainEntry; 11] = [
+        MainEntry::Play,
+        MainEntry::Versus,
+        MainEntry::Adjacency,
+        MainEntry::Resolution,
+        MainEntry::Options,
+        MainEntry::HighScores,
+        MainEntry::Stats,
+        MainEntry::Achievements,
+        MainEntry::HowToPlay,
+        MainEntry::Credits,
+        MainEntry::Quit,
+    ];
+}
 
 /// Bevy Plugin for handling the game Main Menu
 pub struct MenuPlugin;
@@ -13,51 +68,87 @@ impl Plugin for MenuPlugin {
     fn build(&self, app: &mut App) {
         app.add_system_set(SystemSet::on_enter(GameState::Menu).with_system(on_enter))
             .add_system_set(SystemSet::on_exit(GameState::Menu).with_system(on_exit))
-            .add_system_set(SystemSet::on_update(GameState::Menu).with_system(start_game_on_enter))
-            .add_system_set(SystemSet::on_update(GameState::Menu).with_system(close_on_esc))
-            .add_system_set(SystemSet::on_update(GameState::Menu).with_system(blink_text));
+            .add_system_set(
+                SystemSet::on_update(GameState::Menu)
+                    .with_system(change_setting)
+                    .with_system(select_entry)
+                    .with_system(update_entry_texts),
+            )
+            .add_system(move_focus.label(MenuNavigationLabel))
+            .add_system(highlight_focus.after(MenuNavigationLabel))
+            .insert_resource(MenuFocus::default());
     }
 }
 
+/// Helper function to spawn a focusable text entry of a menu screen
+pub fn spawn_menu_entry(
+    commands: &mut Commands,
+    font: Handle<Font>,
+    value: String,
+    font_size: f32,
+    index: usize,
+) -> Entity {
+    commands
+        .spawn_bundle(
+            TextBundle::from_section(
+                value,
+                TextStyle {
+                    font,
+                    font_size,
+                    color: ENTRY_COLOR,
+                },
+            )
+            .with_text_alignment(TextAlignment::CENTER)
+            .with_style(Style {
+                margin: UiRect::new(Val::Px(0.0), Val::Px(0.0), Val::Px(5.0), Val::Px(5.0)),
+                ..default()
+            }),
+        )
+        .insert(FocusEntry(index))
+        .id()
+}
+
+/// Helper function to find the focused entry of the menu screen
+pub fn get_focused<T: Component + Copy>(
+    focus: &MenuFocus,
+    query: &Query<(&FocusEntry, &T)>,
+) -> Option<T> {
+    query
+        .iter()
+        .find(|(entry, _)| entry.0 == focus.0)
+        .map(|(_, value)| *value)
+}
+
 /// Called once when switching to `GameState::Menu`
-fn on_enter(mut commands: Commands, asset_server: Res<AssetServer>) {
+fn on_enter(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut focus: ResMut<MenuFocus>,
+    mode: Res<GameMode>,
+    adjacency: Res<Adjacency>,
+    resolution: Res<Resolution>,
+    versus: Res<Versus>,
+    language: Res<Language>,
+) {
     println!("Enter GameState::Menu");
+    focus.0 = 0;
 
+    // Logo and the entries are stacked from top to bottom
     let menu = commands
         .spawn_bundle(NodeBundle {
             style: Style {
                 size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
                 position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::ColumnReverse,
                 justify_content: JustifyContent::Center,
-                align_items: AlignItems::FlexEnd,
+                align_items: AlignItems::Center,
                 ..default()
             },
             color: UiColor(Color::NONE),
             ..default()
         })
         .insert(MenuNode)
-        .id();
-
-    // Press Start text
-    let text = commands
-        .spawn_bundle(
-            TextBundle::from_section(
-                "Press Start",
-                TextStyle {
-                    font: asset_server.load("fonts/04b_30.ttf"),
-                    font_size: 32.0,
-                    color: Color::BLACK,
-                },
-            )
-            .with_text_alignment(TextAlignment::CENTER)
-            .with_style(Style {
-                align_items: AlignItems::Center,
-                align_self: AlignSelf::Center,
-                position_type: PositionType::Relative,
-                ..default()
-            }),
-        )
-        .insert(PressStartText)
+        .insert(BoardArea)
         .id();
 
     // Logo picture
@@ -65,21 +156,32 @@ fn on_enter(mut commands: Commands, asset_server: Res<AssetServer>) {
         .spawn_bundle(ImageBundle {
             style: Style {
                 size: Size::new(Val::Px(400.0), Val::Auto),
-                position_type: PositionType::Absolute,
-                position: UiRect {
-                    left: Val::Auto,
-                    right: Val::Auto,
-                    top: Val::Px(150.0),
-                    bottom: Val::Auto,
-                },
-                align_self: AlignSelf::Center,
+                margin: UiRect::new(Val::Px(0.0), Val::Px(0.0), Val::Px(20.0), Val::Px(20.0)),
                 ..default()
             },
             image: asset_server.load("logo.png").into(),
             ..default()
         })
         .id();
-    commands.entity(menu).push_children(&[logo, text]);
+
+    let mut children = vec![logo];
+    for (index, entry) in MainEntry::ALL.iter().enumerate() {
+        let font_size = if *entry == MainEntry::Play {
+            24.0
+        } else {
+            16.0
+        };
+        let text = spawn_menu_entry(
+            &mut commands,
+            asset_server.load("fonts/04b_30.ttf"),
+            get_entry_text(*entry, *mode, *adjacency, *resolution, *versus, *language),
+            font_size,
+            index,
+        );
+        commands.entity(text).insert(*entry);
+        children.push(text);
+    }
+    commands.entity(menu).push_children(&children);
 }
 
 /// Called once when switching from `GameState::Menu`
@@ -91,23 +193,199 @@ fn on_exit(mut commands: Commands, query: Query<Entity, With<MenuNode>>) {
     }
 }
 
-/// Switch to `GameState::InGame` if Return key is pressed
-fn start_game_on_enter(
-    mut input: ResMut<Input<KeyCode>>,
-    mut game_state: ResMut<State<GameState>>,
+/// Move the focus with Up / Down keys (wraps around)
+fn move_focus(
+    actions: Res<Input<MenuAction>>,
+    mut focus: ResMut<MenuFocus>,
+    query: Query<&FocusEntry>,
+    mut audio_events: EventWriter<PlaySfxEvent>,
+) {
+    let count = query.iter().count();
+    if count == 0 {
+        return;
+    }
+
+    if actions.just_pressed(MenuAction::Up) {
+        focus.0 = (focus.0 + count - 1) % count;
+        audio_events.send(PlaySfxEvent(Sfx::MenuMove));
+    } else if actions.just_pressed(MenuAction::Down) {
+        focus.0 = (focus.0 + 1) % count;
+        audio_events.send(PlaySfxEvent(Sfx::MenuMove));
+    }
+}
+
+/// Highlight the focused entry
+fn highlight_focus(
+    focus: Res<MenuFocus>,
+    mut query: Query<(&mut Text, ChangeTrackers<FocusEntry>, &FocusEntry)>,
+) {
+    for (mut text, tracker, entry) in query.iter_mut() {
+        if focus.is_changed() || tracker.is_added() {
+            let color = if entry.0 == focus.0 {
+                FOCUS_COLOR
+            } else {
+                ENTRY_COLOR
+            };
+            for section in text.sections.iter_mut() {
+                section.style.color = color;
+            }
+        }
+    }
+}
+
+/// Helper function to get the text of the main menu entry
+fn get_entry_text(
+    entry: MainEntry,
+    mode: GameMode,
+    adjacency: Adjacency,
+    resolution: Resolution,
+    versus: Versus,
+    language: Language,
+) -> String {
+    match entry {
+        MainEntry::Play => format!("< {} >", language.get(TextKey::GameMode(mode))),
+        MainEntry::Versus => language.format(
+            TextKey::VersusOption,
+            &[&language.get(TextKey::Versus(versus))],
+        ),
+        MainEntry::Adjacency => language.format(
+            TextKey::AdjacencyOption,
+            &[&language.get(TextKey::Adjacency(adjacency))],
+        ),
+        MainEntry::Resolution => language.format(
+            TextKey::ResolutionOption,
+            &[&language.get(TextKey::Resolution(resolution))],
+        ),
+        MainEntry::Options => language.get(TextKey::Options),
+        MainEntry::HighScores => language.get(TextKey::HighScores),
+        MainEntry::Stats => language.get(TextKey::Statistics),
+        MainEntry::Achievements => language.get(TextKey::AchievementsEntry),
+        MainEntry::HowToPlay => language.get(TextKey::HowToPlay),
+        MainEntry::Credits => language.get(TextKey::Credits),
+        MainEntry::Quit => language.get(TextKey::Quit),
+    }
+}
+
+/// Update the entry texts whenever the settings or the language change
+fn update_entry_texts(
+    mut query: Query<(&mut Text, &MainEntry)>,
+    mode: Res<GameMode>,
+    adjacency: Res<Adjacency>,
+    resolution: Res<Resolution>,
+    versus: Res<Versus>,
+    language: Res<Language>,
 ) {
-    if input.just_pressed(KeyCode::Return) {
-        game_state
-            .set(GameState::HowToPlay)
-            .expect("Failed to change GameState:InGame");
-        input.reset(KeyCode::Return);
+    if !mode.is_changed()
+        && !adjacency.is_changed()
+        && !resolution.is_changed()
+        && !versus.is_changed()
+        && !language.is_changed()
+    {
+        return;
+    }
+
+    for (mut text, entry) in query.iter_mut() {
+        text.sections[0].value =
+            get_entry_text(*entry, *mode, *adjacency, *resolution, *versus, *language);
     }
 }
 
-/// Blink Press Start text
-fn blink_text(time: Res<Time>, mut query: Query<&mut Text, With<PressStartText>>) {
-    for mut text in query.iter_mut() {
-        let seconds = time.seconds_since_startup() as f32;
-        text.sections[0].style.color = Color::rgba(0., 0., 0., seconds.sin().abs());
+/// Change the setting of the focused entry with Left / Right keys
+fn change_setting(
+    actions: Res<Input<MenuAction>>,
+    focus: Res<MenuFocus>,
+    query: Query<(&FocusEntry, &MainEntry)>,
+    mut mode: ResMut<GameMode>,
+    mut adjacency: ResMut<Adjacency>,
+    mut resolution: ResMut<Resolution>,
+    mut versus: ResMut<Versus>,
+    mut audio_events: EventWriter<PlaySfxEvent>,
+) {
+    let forward = actions.just_pressed(MenuAction::Right);
+    if !forward && !actions.just_pressed(MenuAction::Left) {
+        return;
+    }
+
+    match get_focused(&focus, &query) {
+        Some(MainEntry::Play) if forward => *mode = mode.next(),
+        Some(MainEntry::Play) => *mode = mode.previous(),
+        Some(MainEntry::Versus) if forward => *versus = versus.next(),
+        Some(MainEntry::Versus) => *versus = versus.previous(),
+        Some(MainEntry::Adjacency) if forward => *adjacency = adjacency.next(),
+        Some(MainEntry::Adjacency) => *adjacency = adjacency.previous(),
+        Some(MainEntry::Resolution) if forward => *resolution = resolution.next(),
+        Some(MainEntry::Resolution) => *resolution = resolution.previous(),
+        _ => return,
     }
+    audio_events.send(PlaySfxEvent(Sfx::MenuMove));
+}
+
+/// Activate the focused entry with Return key. Settings step to their next value. Esc moves the
+/// focus to Quit.
+fn select_entry(
+    mut actions: ResMut<Input<MenuAction>>,
+    mut focus: ResMut<MenuFocus>,
+    query: Query<(&FocusEntry, &MainEntry)>,
+    mut game_state: ResMut<State<GameState>>,
+    mut after_how_to_play: ResMut<AfterHowToPlay>,
+    mut adjacency: ResMut<Adjacency>,
+    mut resolution: ResMut<Resolution>,
+    mut versus: ResMut<Versus>,
+    mut exit_events: EventWriter<AppExit>,
+    mut audio_events: EventWriter<PlaySfxEvent>,
+) {
+    if actions.just_pressed(MenuAction::Back) {
+        if let Some(index) = MainEntry::ALL.iter().position(|e| *e == MainEntry::Quit) {
+            focus.0 = index;
+            audio_events.send(PlaySfxEvent(Sfx::MenuMove));
+        }
+        return;
+    }
+    if !actions.just_pressed(MenuAction::Select) {
+        return;
+    }
+
+    // Network game is set up in the lobby
+    let next_state = match get_focused(&focus, &query) {
+        Some(MainEntry::Play) if *versus == Versus::Network => GameState::Lobby,
+        Some(MainEntry::Play) => {
+            after_how_to_play.0 = GameState::InGame;
+            GameState::HowToPlay
+        }
+        Some(MainEntry::Versus) => {
+            *versus = versus.next();
+            audio_events.send(PlaySfxEvent(Sfx::MenuMove));
+            return;
+        }
+        Some(MainEntry::Adjacency) => {
+            *adjacency = adjacency.next();
+            audio_events.send(PlaySfxEvent(Sfx::MenuMove));
+            return;
+        }
+        Some(MainEntry::Resolution) => {
+            *resolution = resolution.next();
+            audio_events.send(PlaySfxEvent(Sfx::MenuMove));
+            return;
+        }
+        Some(MainEntry::Options) => GameState::Options,
+        Some(MainEntry::HighScores) => GameState::HighScores,
+        Some(MainEntry::Stats) => GameState::Stats,
+        Some(MainEntry::Achievements) => GameState::Achievements,
+        Some(MainEntry::HowToPlay) => {
+            after_how_to_play.0 = GameState::Menu;
+            GameState::HowToPlay
+        }
+        Some(MainEntry::Credits) => GameState::Credits,
+        Some(MainEntry::Quit) => {
+            exit_events.send(AppExit);
+            return;
+        }
+        None => return,
+    };
+
+    audio_events.send(PlaySfxEvent(Sfx::MenuSelect));
+    game_state
+        .set(next_state)
+        .expect("Failed to change GameState");
+    actions.reset(MenuAction::Select);
 }
@@ -0,0 +1,1085 @@
+//!
+//! Versus game against another instance of the game over a direct TCP connection. The host
+//! waits for the opponent in the lobby and decides the seed and the rules of the game.
+//!
+//! Both instances simulate both boards in lockstep. The boards start from the seed shared by the
+//! host, the game advances by a fixed time step on each tick, and a tick is simulated only once
+//! the inputs of both players for it have arrived. Inputs are sent `INPUT_DELAY` ticks ahead so
+//! that the latency of the connection doesn't stall the game. Checksums of the boards are sent
+//! along with the inputs every now and then, and the game is ended if the boards differ.
+//!
+//! Messages are lines of space separated words:
+//! - `HELLO <version>`: Sent by both once connected
+//! - `START <seed> <mode> <adjacency> <resolution>`: Host starts the game
+//! - `INPUT <tick> <pressed> <just pressed> [<checksum> <checksum>]`: Actions of the sender on the
+//!   tick, and every `CHECKSUM_INTERVAL` ticks the checksums of the sender's and receiver's boards
+//! - `LOST`: Sender has left the game
+//!
+use crate::board::{BoardId, BoardPreset, PerBoard};
+use crate::controls::{KeyBindings, MenuAction, PlayerAction, PlayerInputLabel, BINDABLE_ACTIONS};
+use crate::in_game::{
+    on_simulation_update, BlockColor, BlockKind, BlockPosition, DroppingBlock, GameSeed, Level,
+    Number, SimulationStep, SimulationStepLabel, SolidBlock,
+};
+use crate::localization::{Language, TextKey};
+use crate::prelude::*;
+use crate::rules::{Adjacency, GameMode, Resolution, Versus};
+use crate::toast::ShowToastEvent;
+use crate::versus::{Outcome, VersusResult};
+use crate::view::BoardArea;
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+use bevy::window::ReceivedCharacter;
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+/// Port the host listens to (unless given in the address)
+const DEFAULT_PORT: u16 = 7878;
+
+/// Version of the message protocol. Both instances need to speak the same version.
+const PROTOCOL_VERSION: u32 = 4;
+
+/// Number of ticks the inputs are sent ahead of the tick they are used on
+const INPUT_DELAY: u64 = 6;
+
+/// Number of ticks between the board checksums
+const CHECKSUM_INTERVAL: u64 = 60;
+
+/// Offset basis and prime of the 64-bit FNV-1a hash used for the board checksums
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Maximum length of the typed address
+const MAX_ADDRESS_LENGTH: usize = 64;
+
+/// Role of this instance in the network game
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum Role {
+    Host,
+    Join,
+}
+
+/// Events from the connection thread
+enum NetEvent {
+    Connected,
+    Message(String),
+    Disconnected(String),
+}
+
+/// Channels to the connection thread. The connection is closed once this is dropped.
+struct Connection {
+    events: Mutex<Receiver<NetEvent>>,
+    outgoing: Mutex<Sender<String>>,
+}
+
+impl Connection {
+    /// Sends a message to the opponent
+    fn send(&self, message: String) {
+        if let Ok(outgoing) = self.outgoing.lock() {
+            let _ = outgoing.send(message);
+        }
+    }
+
+    /// Returns the next event from the connection, if any
+    fn poll(&self) -> Option<NetEvent> {
+        self.events.lock().ok()?.try_recv().ok()
+    }
+}
+
+/// This resource holds the state of the network session
+pub struct NetSession {
+    role: Option<Role>,
+    connection: Option<Connection>,
+    /// Address of the host typed by the joining player
+    address: String,
+    status: String,
+}
+
+impl Default for NetSession {
+    fn default() -> Self {
+        NetSession {
+            role: None,
+            connection: None,
+            address: "127.0.0.1".to_string(),
+            status: String::new(),
+        }
+    }
+}
+
+impl NetSession {
+    /// Sends a message to the opponent if connected
+    fn send(&self, message: String) {
+        if let Some(connection) = &self.connection {
+            connection.send(message);
+        }
+    }
+
+    /// Closes the connection
+    fn close(&mut self) {
+        self.role = None;
+        self.connection = None;
+    }
+}
+
+/// Player actions of a board on one tick of the game
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+struct TickInput {
+    /// Actions held down at the end of the tick
+    pressed: Vec<PlayerAction>,
+    /// Actions pressed during the tick, including the ones already released again
+    just_pressed: Vec<PlayerAction>,
+}
+
+impl TickInput {
+    /// Replays the input as the actions of a board
+    fn apply(&self, actions: &mut Input<PlayerAction>) {
+        actions.clear();
+        for action in BINDABLE_ACTIONS {
+            if self.just_pressed.contains(&action) || self.pressed.contains(&action) {
+                actions.press(action);
+            }
+            if !self.pressed.contains(&action) {
+                actions.release(action);
+            }
+        }
+    }
+}
+
+/// Helper function to encode the actions as a comma separated list of their indices in
+/// `BINDABLE_ACTIONS` (which lists every player action). Empty list is sent as `-`.
+fn encode_actions(actions: &[PlayerAction]) -> String {
+    if actions.is_empty() {
+        return "-".to_string();
+    }
+    actions
+        .iter()
+        .filter_map(|action| BINDABLE_ACTIONS.iter().position(|a| a == action))
+        .map(|index| index.to_string())
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+/// Helper function to decode the actions encoded with `encode_actions`
+fn decode_actions(word: &str) -> Option<Vec<PlayerAction>> {
+    if word == "-" {
+        return Some(vec![]);
+    }
+    word.split(',')
+        .map(|index| {
+            let index = index.parse::<usize>().ok()?;
+            BINDABLE_ACTIONS.get(index).copied()
+        })
+        .collect()
+}
+
+/// Helper function to calculate a checksum of a board. The board is described as lines of text,
+/// which are sorted first since the blocks are queried in a different order on each instance.
+/// The lines are hashed with FNV-1a, which unlike the hasher of the standard library gives the
+/// same result on every build.
+fn checksum(mut lines: Vec<String>) -> u64 {
+    lines.sort();
+    lines
+        .iter()
+        .flat_map(|line| line.bytes().chain(std::iter::once(b'\n')))
+        .fold(FNV_OFFSET_BASIS, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+        })
+}
+
+/// This resource holds the inputs of both players for the upcoming ticks of the game. Both
+/// players see their own board as the first one, so the inputs and checksums of the opponent
+/// are for the boards in the opposite order.
+#[derive(Default)]
+struct Lockstep {
+    /// Next tick to be simulated
+    tick: u64,
+    /// Inputs of the local player starting from the next tick
+    local: VecDeque<TickInput>,
+    /// Inputs of the opponent starting from the next tick
+    remote: VecDeque<TickInput>,
+    /// Local actions pressed since the previous input was sent
+    just_pressed: Vec<PlayerAction>,
+    /// Board checksums sent by the local player, waiting for the ones of the opponent
+    local_checksums: VecDeque<(u64, [u64; 2])>,
+    /// Board checksums received from the opponent, waiting for the local ones
+    remote_checksums: VecDeque<(u64, [u64; 2])>,
+    /// Inputs of the tick to be replayed on this frame (local, remote)
+    replay: Option<(TickInput, TickInput)>,
+}
+
+impl Lockstep {
+    /// Resets the lockstep for a new game. Nothing is pressed on the first `INPUT_DELAY` ticks.
+    fn start(&mut self) {
+        *self = Lockstep {
+            local: vec![TickInput::default(); INPUT_DELAY as usize].into(),
+            remote: vec![TickInput::default(); INPUT_DELAY as usize].into(),
+            ..default()
+        };
+    }
+
+    /// Returns the tick of the next input of the local player
+    fn next_local_tick(&self) -> u64 {
+        self.tick + self.local.len() as u64
+    }
+
+    /// Returns `true` if the next input of the local player should be sent. Inputs are sent
+    /// `INPUT_DELAY` ticks ahead, and no further while waiting for the opponent.
+    fn needs_local(&self) -> bool {
+        self.local.len() as u64 <= INPUT_DELAY
+    }
+
+    /// Returns `true` if the next input of the local player carries the board checksums
+    fn needs_checksum(&self) -> bool {
+        self.next_local_tick().is_multiple_of(CHECKSUM_INTERVAL)
+    }
+
+    /// Records the local actions pressed on the keyboard on this frame
+    fn record(&mut self, keys: &Input<KeyCode>, key_map: &[(KeyCode, PlayerAction)]) {
+        for (key, action) in key_map {
+            if keys.just_pressed(*key) && !self.just_pressed.contains(action) {
+                self.just_pressed.push(*action);
+            }
+        }
+    }
+
+    /// Takes the input of the local player from the keyboard and the recorded actions
+    fn take_input(
+        &mut self,
+        keys: &Input<KeyCode>,
+        key_map: &[(KeyCode, PlayerAction)],
+    ) -> TickInput {
+        let mut pressed = vec![];
+        for (key, action) in key_map {
+            if keys.pressed(*key) && !pressed.contains(action) {
+                pressed.push(*action);
+            }
+        }
+        TickInput {
+            pressed,
+            just_pressed: std::mem::take(&mut self.just_pressed),
+        }
+    }
+
+    /// Queues the input of the local player for the next free tick. Returns the `INPUT` message
+    /// for the opponent.
+    fn queue_local(&mut self, input: TickInput, checksums: Option<[u64; 2]>) -> String {
+        let tick = self.next_local_tick();
+        let mut message = format!(
+            "INPUT {} {} {}",
+            tick,
+            encode_actions(&input.pressed),
+            encode_actions(&input.just_pressed)
+        );
+        if let Some(checksums) = checksums {
+            message.push_str(&format!(" {} {}", checksums[0], checksums[1]));
+            self.local_checksums.push_back((tick, checksums));
+        }
+        self.local.push_back(input);
+        message
+    }
+
+    /// Queues the input of the opponent from the words following `INPUT`. Inputs arrive in the
+    /// order of their ticks.
+    fn receive(&mut self, words: &[&str]) -> Result<(), String> {
+        let invalid = || format!("Invalid input: {}", words.join(" "));
+        let (tick, pressed, just_pressed, checksums) = match words {
+            [tick, pressed, just_pressed] => (tick, pressed, just_pressed, None),
+            [tick, pressed, just_pressed, own, other] => {
+                (tick, pressed, just_pressed, Some((own, other)))
+            }
+            _ => return Err(invalid()),
+        };
+        let tick = tick.parse::<u64>().map_err(|_| invalid())?;
+        let input = TickInput {
+            pressed: decode_actions(pressed).ok_or_else(invalid)?,
+            just_pressed: decode_actions(just_pressed).ok_or_else(invalid)?,
+        };
+
+        let expected = self.tick + self.remote.len() as u64;
+        if tick != expected {
+            return Err(format!(
+                "Expected input for tick {}, got {}",
+                expected, tick
+            ));
+        }
+        if let Some((own, other)) = checksums {
+            let own = own.parse::<u64>().map_err(|_| invalid())?;
+            let other = other.parse::<u64>().map_err(|_| invalid())?;
+            self.remote_checksums.push_back((tick, [own, other]));
+        }
+        self.remote.push_back(input);
+        Ok(())
+    }
+
+    /// Compares the board checksums of both players once they have arrived for the same tick
+    fn verify(&mut self) -> Result<(), String> {
+        while let (Some((tick, local)), Some((remote_tick, remote))) = (
+            self.local_checksums.front().copied(),
+            self.remote_checksums.front().copied(),
+        ) {
+            if tick != remote_tick || local[0] != remote[1] || local[1] != remote[0] {
+                return Err(format!("Boards differ on tick {}", tick));
+            }
+            self.local_checksums.pop_front();
+            self.remote_checksums.pop_front();
+        }
+        Ok(())
+    }
+
+    /// Advances to the next tick if the inputs of both players have arrived. Returns the inputs
+    /// of the tick (local, remote).
+    fn advance(&mut self) -> Option<(TickInput, TickInput)> {
+        if self.local.is_empty() || self.remote.is_empty() {
+            return None;
+        }
+        self.tick += 1;
+        Some((self.local.pop_front()?, self.remote.pop_front()?))
+    }
+}
+
+/// Identifier for the lobby status text
+#[derive(Component)]
+struct LobbyText;
+
+/// Bevy Plugin for the network game
+pub struct NetPlugin;
+impl Plugin for NetPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(GameState::Lobby).with_system(on_enter_lobby))
+            .add_system_set(SystemSet::on_exit(GameState::Lobby).with_system(on_exit_lobby))
+            .add_system_set(
+                SystemSet::on_update(GameState::Lobby)
+                    .with_system(lobby_input)
+                    .with_system(lobby_connection)
+                    .with_system(update_lobby_text),
+            )
+            .add_system_set(SystemSet::on_enter(GameState::InGame).with_system(start_lockstep))
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                exchange_inputs
+                    .after(InputSystem)
+                    .after(SimulationStepLabel),
+            )
+            .add_system_set(
+                on_simulation_update().with_system(replay_inputs.label(PlayerInputLabel)),
+            )
+            .add_system_set(SystemSet::on_enter(GameState::GameOver).with_system(send_result))
+            .add_system_set(SystemSet::on_enter(GameState::Menu).with_system(close_connection))
+            .insert_resource(NetSession::default())
+            .insert_resource(Lockstep::default());
+    }
+}
+
+/// Helper function to add the default port into the address if it's missing
+fn get_socket_address(address: &str) -> String {
+    if address.contains(':') {
+        address.to_string()
+    } else {
+        format!("{}:{}", address, DEFAULT_PORT)
+    }
+}
+
+/// Waits for the opponent to connect. Waiting is cancelled once the `Connection` is dropped.
+fn accept_opponent(
+    listener: TcpListener,
+    outgoing: &Receiver<String>,
+) -> Result<TcpStream, String> {
+    listener
+        .set_nonblocking(true)
+        .map_err(|error| error.to_string())?;
+    loop {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                stream
+                    .set_nonblocking(false)
+                    .map_err(|error| error.to_string())?;
+                return Ok(stream);
+            }
+            Err(error) if error.kind() == ErrorKind::WouldBlock => {
+                if let Err(TryRecvError::Disconnected) = outgoing.try_recv() {
+                    return Err("Cancelled".to_string());
+                }
+                thread::sleep(Duration::from_millis(100));
+            }
+            Err(error) => return Err(error.to_string()),
+        }
+    }
+}
+
+/// Opens the connection in a background thread. Host waits for the opponent and the joining
+/// player connects to the given address.
+fn open_connection(role: Role, address: String) -> Connection {
+    match role {
+        Role::Host => spawn_connection(|outgoing| {
+            let listener =
+                TcpListener::bind(("0.0.0.0", DEFAULT_PORT)).map_err(|error| error.to_string())?;
+            accept_opponent(listener, outgoing)
+        }),
+        Role::Join => spawn_connection(move |_| {
+            TcpStream::connect(get_socket_address(&address)).map_err(|error| error.to_string())
+        }),
+    }
+}
+
+/// Runs the connection in a background thread. The stream is opened by the given function,
+/// which gets the outgoing messages for noticing when the `Connection` is dropped.
+fn spawn_connection(
+    connect: impl FnOnce(&Receiver<String>) -> Result<TcpStream, String> + Send + 'static,
+) -> Connection {
+    let (event_sender, events) = mpsc::channel();
+    let (outgoing, outgoing_receiver) = mpsc::channel::<String>();
+
+    thread::spawn(move || {
+        let mut stream = match connect(&outgoing_receiver) {
+            Ok(stream) => stream,
+            Err(error) => {
+                let _ = event_sender.send(NetEvent::Disconnected(error));
+                return;
+            }
+        };
+        let _ = stream.set_nodelay(true);
+        let _ = event_sender.send(NetEvent::Connected);
+
+        // Messages are read in their own thread
+        match stream.try_clone() {
+            Ok(reader) => {
+                let event_sender = event_sender.clone();
+                thread::spawn(move || {
+                    for line in BufReader::new(reader).lines() {
+                        match line {
+                            Ok(line) => {
+                                if event_sender.send(NetEvent::Message(line)).is_err() {
+                                    return;
+                                }
+                            }
+                            Err(_) => break,
+                        }
+                    }
+                    let _ =
+                        event_sender.send(NetEvent::Disconnected("Connection closed".to_string()));
+                });
+            }
+            Err(error) => {
+                let _ = event_sender.send(NetEvent::Disconnected(error.to_string()));
+                return;
+            }
+        }
+
+        for message in outgoing_receiver.iter() {
+            if writeln!(stream, "{}", message).is_err() {
+                break;
+            }
+        }
+        let _ = stream.shutdown(Shutdown::Both);
+    });
+
+    Connection {
+        events: Mutex::new(events),
+        outgoing: Mutex::new(outgoing),
+    }
+}
+
+/// Called once when switching to `GameState::Lobby`
+fn on_enter_lobby(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut session: ResMut<NetSession>,
+) {
+    println!("Enter GameState::Lobby");
+    session.close();
+
+    let node = commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            color: UiColor(Color::NONE),
+            ..default()
+        })
+        .insert(MenuNode)
+        .insert(BoardArea)
+        .id();
+
+    let text = commands
+        .spawn_bundle(
+            TextBundle::from_section(
+                "",
+                TextStyle {
+                    font: asset_server.load("fonts/04b_30.ttf"),
+                    font_size: 16.0,
+                    color: Color::BLACK,
+                },
+            )
+            .with_text_alignment(TextAlignment::CENTER),
+        )
+        .insert(LobbyText)
+        .id();
+
+    commands.entity(node).push_children(&[text]);
+}
+
+/// Called once when switching from `GameState::Lobby`
+fn on_exit_lobby(mut commands: Commands, query: Query<Entity, With<MenuNode>>) {
+    println!("Exit GameState::Lobby");
+
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// System for choosing to host or join, and for typing the address of the host
+fn lobby_input(
+    mut input: ResMut<Input<KeyCode>>,
+    mut actions: ResMut<Input<MenuAction>>,
+    mut characters: EventReader<ReceivedCharacter>,
+    mut session: ResMut<NetSession>,
+    mut game_state: ResMut<State<GameState>>,
+    language: Res<Language>,
+) {
+    if actions.just_pressed(MenuAction::Back) {
+        session.close();
+        game_state
+            .set(GameState::Menu)
+            .expect("Failed to change GameState::Menu");
+        actions.reset(MenuAction::Back);
+        return;
+    }
+
+    match (session.role, session.connection.is_some()) {
+        (None, _) => {
+            if input.just_pressed(KeyCode::H) {
+                session.role = Some(Role::Host);
+                session.connection = Some(open_connection(Role::Host, String::new()));
+                session.status = language.format(TextKey::WaitingForOpponent, &[&DEFAULT_PORT]);
+            } else if input.just_pressed(KeyCode::J) {
+                session.role = Some(Role::Join);
+                session.status = String::new();
+            }
+            // Key which chose the role is not a part of the address
+            characters.clear();
+        }
+        (Some(Role::Join), false) => {
+            for ev in characters.iter() {
+                if (ev.char.is_ascii_alphanumeric() || ".:-[]".contains(ev.char))
+                    && session.address.len() < MAX_ADDRESS_LENGTH
+                {
+                    session.address.push(ev.char);
+                }
+            }
+            if input.just_pressed(KeyCode::Back) {
+                session.address.pop();
+            }
+            if input.just_pressed(KeyCode::Return) && !session.address.is_empty() {
+                let address = session.address.clone();
+                session.status =
+                    language.format(TextKey::ConnectingTo, &[&get_socket_address(&address)]);
+                session.connection = Some(open_connection(Role::Join, address));
+                input.reset(KeyCode::Return);
+            }
+        }
+        _ => characters.clear(),
+    }
+}
+
+/// System for handling the connection in the lobby. Host starts the game once the opponent has
+/// connected.
+fn lobby_connection(
+    mut session: ResMut<NetSession>,
+    mut game_state: ResMut<State<GameState>>,
+    mut seed: ResMut<GameSeed>,
+    mut mode: ResMut<GameMode>,
+    mut adjacency: ResMut<Adjacency>,
+    mut resolution: ResMut<Resolution>,
+    mut board_preset: ResMut<BoardPreset>,
+    language: Res<Language>,
+) {
+    while let Some(ev) = session.connection.as_ref().and_then(|c| c.poll()) {
+        match ev {
+            NetEvent::Connected => {
+                session.send(format!("HELLO {}", PROTOCOL_VERSION));
+                session.status = language.get(TextKey::Connected);
+            }
+            NetEvent::Message(message) => {
+                let words: Vec<&str> = message.split_whitespace().collect();
+                match words.as_slice() {
+                    ["HELLO", version] if version.parse::<u32>() == Ok(PROTOCOL_VERSION) => {
+                        if session.role == Some(Role::Host) {
+                            // Host decides the blocks, the rules and the board size
+                            let game_seed: u64 = rand::random();
+                            seed.0 = Some(game_seed);
+                            session.send(format!(
+                                "START {} {} {} {} {}",
+                                game_seed,
+                                GameMode::ALL.iter().position(|m| *m == *mode).unwrap_or(0),
+                                Adjacency::ALL
+                                    .iter()
+                                    .position(|a| *a == *adjacency)
+                                    .unwrap_or(0),
+                                Resolution::ALL
+                                    .iter()
+                                    .position(|r| *r == *resolution)
+                                    .unwrap_or(0),
+                                BoardPreset::ALL
+                                    .iter()
+                                    .position(|p| *p == *board_preset)
+                                    .unwrap_or(0),
+                            ));
+                            game_state
+                                .set(GameState::InGame)
+                                .expect("Failed to change GameState::InGame");
+                            return;
+                        }
+                    }
+                    ["HELLO", _] => {
+                        session.close();
+                        session.status = language.get(TextKey::VersionMismatch);
+                        return;
+                    }
+                    ["START", game_seed, mode_index, adjacency_index, resolution_index, preset_index] =>
+                    {
+                        let options = (
+                            game_seed.parse::<u64>(),
+                            mode_index.parse::<usize>(),
+                            adjacency_index.parse::<usize>(),
+                            resolution_index.parse::<usize>(),
+                            preset_index.parse::<usize>(),
+                        );
+                        if let (Ok(game_seed), Ok(m), Ok(a), Ok(r), Ok(p)) = options {
+                            seed.0 = Some(game_seed);
+                            *mode = GameMode::ALL[m.min(GameMode::ALL.len() - 1)];
+                            *adjacency = Adjacency::ALL[a.min(Adjacency::ALL.len() - 1)];
+                            *resolution = Resolution::ALL[r.min(Resolution::ALL.len() - 1)];
+                            *board_preset = BoardPreset::ALL[p.min(BoardPreset::ALL.len() - 1)];
+                            game_state
+                                .set(GameState::InGame)
+                                .expect("Failed to change GameState::InGame");
+                            return;
+                        }
+                    }
+                    _ => println!("Unexpected message in lobby: {}", message),
+                }
+            }
+            NetEvent::Disconnected(reason) => {
+                session.close();
+                session.status = language.format(TextKey::NoConnection, &[&reason]);
+            }
+        }
+    }
+}
+
+/// Update the lobby text
+fn update_lobby_text(
+    session: Res<NetSession>,
+    language: Res<Language>,
+    mut query: Query<&mut Text, With<LobbyText>>,
+) {
+    if !session.is_changed() {
+        return;
+    }
+
+    let mut lines = vec![language.get(TextKey::NetworkGame), String::new()];
+    match session.role {
+        None => {
+            lines.push(language.get(TextKey::HostGame));
+            lines.push(language.get(TextKey::JoinGame));
+        }
+        Some(Role::Host) => {}
+        Some(Role::Join) => {
+            lines.push(language.get(TextKey::HostAddress));
+            if session.connection.is_some() {
+                lines.push(session.address.clone());
+            } else {
+                lines.push(format!("{}_", session.address));
+                lines.push(language.get(TextKey::Connect));
+            }
+        }
+    }
+    lines.push(String::new());
+    lines.push(session.status.clone());
+    lines.push(language.get(TextKey::Back));
+
+    for mut text in query.iter_mut() {
+        text.sections[0].value = lines.join("\n");
+    }
+}
+
+/// Called once as the game is started
+fn start_lockstep(mut lockstep: ResMut<Lockstep>) {
+    lockstep.start();
+}
+
+/// System for exchanging the inputs with the opponent. The game advances one tick once the
+/// inputs of both players for the tick have arrived, and is held until then.
+fn exchange_inputs(
+    keys: Res<Input<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut session: ResMut<NetSession>,
+    mut lockstep: ResMut<Lockstep>,
+    mut step: ResMut<SimulationStep>,
+    mut result: ResMut<VersusResult>,
+    mut game_state: ResMut<State<GameState>>,
+    mut toast_events: EventWriter<ShowToastEvent>,
+    block_query: Query<
+        (
+            &BlockPosition,
+            &Number,
+            &BlockColor,
+            &BlockKind,
+            &BoardId,
+            Option<&DroppingBlock>,
+        ),
+        Or<(With<SolidBlock>, With<DroppingBlock>)>,
+    >,
+    scores: Res<PerBoard<Score>>,
+    levels: Res<PerBoard<Level>>,
+    versus: Res<Versus>,
+    language: Res<Language>,
+) {
+    if *versus != Versus::Network || *game_state.current() != GameState::InGame {
+        return;
+    }
+
+    let mut error = None;
+    while let Some(ev) = session.connection.as_ref().and_then(|c| c.poll()) {
+        match ev {
+            NetEvent::Message(message) => {
+                let words: Vec<&str> = message.split_whitespace().collect();
+                match words.as_slice() {
+                    ["INPUT", input @ ..] => {
+                        if let Err(reason) = lockstep.receive(input) {
+                            println!("Lockstep failed: {}", reason);
+                            error = Some(TextKey::OutOfSync);
+                            break;
+                        }
+                    }
+                    ["LOST"] => {
+                        result.outcome = Some(Outcome::Lost(1));
+                        game_state
+                            .overwrite_set(GameState::GameOver)
+                            .expect("Failed to change GameState::GameOver");
+                        return;
+                    }
+                    _ => println!("Unexpected message in game: {}", message),
+                }
+            }
+            NetEvent::Disconnected(reason) => {
+                println!("Connection lost: {}", reason);
+                session.close();
+                error = Some(TextKey::ConnectionLost);
+                break;
+            }
+            NetEvent::Connected => {}
+        }
+    }
+
+    // Input of the local player is sent ahead of the tick it's used on. Checksums are taken from
+    // the boards at the same tick on both instances.
+    lockstep.record(&keys, &bindings.0);
+    if error.is_none() && lockstep.needs_local() {
+        let checksums = lockstep.needs_checksum().then(|| {
+            let mut boards = [vec![], vec![]];
+            for (pos, number, color, kind, board, dropping) in block_query.iter() {
+                boards[board.0].push(format!(
+                    "{},{},{},{:?},{:?},{}",
+                    pos.0.x,
+                    pos.0.y,
+                    number.0,
+                    color,
+                    kind,
+                    dropping.is_some()
+                ));
+            }
+            for (board, lines) in boards.iter_mut().enumerate() {
+                lines.push(format!(
+                    "{},{},{}",
+                    scores[board].0, levels[board].number, levels[board].cleared_blocks
+                ));
+            }
+            let [own, other] = boards;
+            [checksum(own), checksum(other)]
+        });
+        let input = lockstep.take_input(&keys, &bindings.0);
+        let message = lockstep.queue_local(input, checksums);
+        session.send(message);
+    }
+    if let Err(reason) = lockstep.verify() {
+        println!("Lockstep failed: {}", reason);
+        error = Some(TextKey::OutOfSync);
+    }
+
+    if let Some(error) = error {
+        toast_events.send(ShowToastEvent(language.get(error)));
+        game_state
+            .overwrite_set(GameState::GameOver)
+            .expect("Failed to change GameState::GameOver");
+        return;
+    }
+
+    lockstep.replay = lockstep.advance();
+    step.ready = lockstep.replay.is_some();
+}
+
+/// System for replaying the inputs of the tick as the actions of both boards
+fn replay_inputs(
+    mut lockstep: ResMut<Lockstep>,
+    mut actions: ResMut<PerBoard<Input<PlayerAction>>>,
+) {
+    if let Some((local, remote)) = lockstep.replay.take() {
+        local.apply(&mut actions[0]);
+        remote.apply(&mut actions[1]);
+    }
+}
+
+/// Called once when the game is over. Tells the opponent that the game has ended here without a
+/// loser, e.g. when the player has left the game. Both instances find the loser on their own.
+fn send_result(session: Res<NetSession>, result: Res<VersusResult>) {
+    if result.outcome.is_none() {
+        session.send("LOST".to_string());
+    }
+}
+
+/// Called once when returning to the main menu
+fn close_connection(mut session: ResMut<NetSession>) {
+    session.close();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::prelude::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use std::time::Instant;
+
+    /// Number of ticks played in the loopback game
+    const TICKS: u64 = 300;
+
+    /// Board of the loopback game. The player moves the dropping column and drops a random number
+    /// on top of it.
+    #[derive(Clone, PartialEq, Eq, Debug)]
+    struct TestBoard {
+        column: usize,
+        columns: Vec<Vec<u32>>,
+    }
+
+    impl TestBoard {
+        fn new() -> Self {
+            TestBoard {
+                column: 0,
+                columns: vec![vec![]; MAX_BOARD_SIZE.width as usize],
+            }
+        }
+
+        fn step(&mut self, actions: &Input<PlayerAction>, rng: &mut StdRng) {
+            if actions.just_pressed(PlayerAction::MoveLeft) {
+                self.column = self.column.saturating_sub(1);
+            }
+            if actions.just_pressed(PlayerAction::MoveRight) {
+                self.column = (self.column + 1).min(self.columns.len() - 1);
+            }
+            if actions.pressed(PlayerAction::SoftDrop) {
+                self.columns[self.column].push(rng.gen_range(1..=9));
+            }
+        }
+
+        fn lines(&self) -> Vec<String> {
+            let mut lines = vec![self.column.to_string()];
+            for (x, column) in self.columns.iter().enumerate() {
+                lines.push(format!("{}: {:?}", x, column));
+            }
+            lines
+        }
+    }
+
+    /// One player of the loopback game
+    struct Endpoint {
+        connection: Connection,
+        connected: bool,
+        lockstep: Lockstep,
+        actions: [Input<PlayerAction>; 2],
+        boards: [TestBoard; 2],
+        rngs: [StdRng; 2],
+        player: StdRng,
+    }
+
+    impl Endpoint {
+        fn new(connection: Connection, seed: u64, player_seed: u64) -> Self {
+            let mut lockstep = Lockstep::default();
+            lockstep.start();
+            Endpoint {
+                connection,
+                connected: false,
+                lockstep,
+                actions: Default::default(),
+                boards: [TestBoard::new(), TestBoard::new()],
+                rngs: [StdRng::seed_from_u64(seed), StdRng::seed_from_u64(seed)],
+                player: StdRng::seed_from_u64(player_seed),
+            }
+        }
+
+        /// Random input of the local player
+        fn play(&mut self) -> TickInput {
+            let mut input = TickInput::default();
+            for action in BINDABLE_ACTIONS {
+                if self.player.gen::<f32>() < 0.2 {
+                    input.just_pressed.push(action);
+                }
+                if self.player.gen::<f32>() < 0.2 {
+                    input.pressed.push(action);
+                }
+            }
+            input
+        }
+
+        /// Same as one frame of the game: handles the messages, sends the next input and
+        /// advances a tick if possible
+        fn update(&mut self) {
+            while let Some(ev) = self.connection.poll() {
+                match ev {
+                    NetEvent::Connected => self.connected = true,
+                    NetEvent::Message(message) => {
+                        let words: Vec<&str> = message.split_whitespace().collect();
+                        match words.as_slice() {
+                            ["INPUT", input @ ..] => self.lockstep.receive(input).unwrap(),
+                            _ => panic!("Unexpected message: {}", message),
+                        }
+                    }
+                    NetEvent::Disconnected(reason) => panic!("Disconnected: {}", reason),
+                }
+            }
+
+            // Game starts once connected, like after the handshake in the lobby
+            if !self.connected {
+                return;
+            }
+
+            if self.lockstep.needs_local() && self.lockstep.next_local_tick() < TICKS {
+                let checksums = self.lockstep.needs_checksum().then(|| {
+                    [
+                        checksum(self.boards[0].lines()),
+                        checksum(self.boards[1].lines()),
+                    ]
+                });
+                let input = self.play();
+                let message = self.lockstep.queue_local(input, checksums);
+                self.connection.send(message);
+            }
+            self.lockstep.verify().unwrap();
+
+            if let Some((local, remote)) = self.lockstep.advance() {
+                local.apply(&mut self.actions[0]);
+                remote.apply(&mut self.actions[1]);
+                for board in 0..2 {
+                    self.boards[board].step(&self.actions[board], &mut self.rngs[board]);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn loopback_game_ends_with_identical_boards() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let host = spawn_connection(move |outgoing| accept_opponent(listener, outgoing));
+        let join = open_connection(Role::Join, address);
+        let mut endpoints = [Endpoint::new(host, 42, 1), Endpoint::new(join, 42, 2)];
+
+        let started = Instant::now();
+        while endpoints
+            .iter()
+            .any(|endpoint| endpoint.lockstep.tick < TICKS)
+        {
+            assert!(started.elapsed() < Duration::from_secs(30), "Game stalled");
+            for endpoint in endpoints.iter_mut() {
+                endpoint.update();
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        // Both see their own board first
+        let [host, join] = &endpoints;
+        assert_eq!(host.lockstep.tick, TICKS);
+        assert_eq!(join.lockstep.tick, TICKS);
+        assert_eq!(host.boards[0], join.boards[1]);
+        assert_eq!(host.boards[1], join.boards[0]);
+        assert_ne!(host.boards[0], host.boards[1]);
+    }
+
+    #[test]
+    fn checksum_is_fixed() {
+        assert_eq!(checksum(vec![]), FNV_OFFSET_BASIS);
+        assert_eq!(
+            checksum(vec!["b".to_string(), "a".to_string()]),
+            0x78ed_6781_f136_a14e
+        );
+    }
+
+    #[test]
+    fn input_is_replayed() {
+        let input = TickInput {
+            pressed: vec![PlayerAction::SoftDrop],
+            just_pressed: vec![PlayerAction::SoftDrop, PlayerAction::SwitchColor],
+        };
+        let mut lockstep = Lockstep::default();
+        lockstep.start();
+        let message = lockstep.queue_local(input.clone(), None);
+        assert_eq!(message, format!("INPUT {} 2 2,3", INPUT_DELAY));
+
+        let mut remote = Lockstep::default();
+        remote.start();
+        let words: Vec<&str> = message.split_whitespace().skip(1).collect();
+        remote.receive(&words).unwrap();
+        assert_eq!(remote.remote.back(), Some(&input));
+
+        // Color was switched and released within the tick
+        let mut actions = Input::default();
+        input.apply(&mut actions);
+        assert!(actions.just_pressed(PlayerAction::SoftDrop));
+        assert!(actions.pressed(PlayerAction::SoftDrop));
+        assert!(actions.just_pressed(PlayerAction::SwitchColor));
+        assert!(!actions.pressed(PlayerAction::SwitchColor));
+    }
+
+    #[test]
+    fn desync_is_detected() {
+        let mut lockstep = Lockstep::default();
+        lockstep.start();
+        let message = lockstep.queue_local(TickInput::default(), Some([1, 2]));
+        assert_eq!(message, format!("INPUT {} - - 1 2", INPUT_DELAY));
+
+        // Opponent sees the boards in the opposite order
+        let tick = INPUT_DELAY.to_string();
+        lockstep
+            .receive(&[tick.as_str(), "-", "-", "2", "1"])
+            .unwrap();
+        assert!(lockstep.verify().is_ok());
+
+        lockstep.queue_local(TickInput::default(), Some([3, 4]));
+        let tick = (INPUT_DELAY + 1).to_string();
+        lockstep
+            .receive(&[tick.as_str(), "-", "-", "3", "4"])
+            .unwrap();
+        assert!(lockstep.verify().is_err());
+    }
+
+    #[test]
+    fn inputs_arrive_in_order() {
+        let mut lockstep = Lockstep::default();
+        lockstep.start();
+        let tick = INPUT_DELAY.to_string();
+        let later = (INPUT_DELAY + 1).to_string();
+        assert!(lockstep.receive(&[later.as_str(), "-", "-"]).is_err());
+        assert!(lockstep.receive(&["x", "-", "-"]).is_err());
+        assert!(lockstep.receive(&[tick.as_str(), "99", "-"]).is_err());
+        assert!(lockstep.receive(&[tick.as_str(), "-", "-"]).is_ok());
+    }
+}
@@ -0,0 +1,609 @@
+//!
+//! Options screen for the settings which don't change the rules of the game: volumes, keys,
+//! colors, effects, starting level, board size, window scale and language. Options are reachable
+//! from the main menu and from the pause overlay. Changes are applied as soon as they are made,
+//! except for the board size which is taken into use as the next game starts. The settings are
+//! saved to disk as the options screen is closed.
+//!
+use crate::audio::{AudioHotkeysLabel, PlaySfxEvent, Sfx, Volumes};
+use crate::board::BoardPreset;
+use crate::constants::prelude::*;
+use crate::controls::{KeyBindings, MenuAction, PlayerAction, BINDABLE_ACTIONS, BINDABLE_KEYS};
+use crate::effects::Effects;
+use crate::in_game::{Animations, BlockColor, StartLevel};
+use crate::localization::{Language, TextKey};
+use crate::menu::{get_focused, spawn_menu_entry, FocusEntry, MenuFocus, MenuNavigationLabel};
+use crate::palette::{BlockSymbols, Palette};
+use crate::prelude::*;
+use crate::storage::{load_values, save_values};
+use crate::view::{BoardArea, WindowScale};
+use bevy::prelude::*;
+use std::collections::HashMap;
+use std::fmt::Debug;
+
+/// Name of the file holding the settings
+const SETTINGS_FILE: &str = "settings.txt";
+
+/// Highest level the games can be started from
+const MAX_START_LEVEL: u32 = 10;
+
+/// Volumes are changed in steps of this many percents
+const VOLUME_STEP: u32 = 10;
+
+/// Entries of the options screen
+#[derive(Component, Copy, Clone, PartialEq, Eq, Debug)]
+enum OptionEntry {
+    MusicVolume,
+    SfxVolume,
+    Key(PlayerAction),
+    Palette,
+    BlockSymbols,
+    Effects,
+    Animations,
+    StartLevel,
+    BoardSize,
+    WindowScale,
+    Language,
+    Back,
+}
+
+impl OptionEntry {
+    /// All the entries in the order they are shown on the screen
+    const ALL: [OptionEntry; 20] = [
+        OptionEntry::MusicVolume,
+        OptionEntry::SfxVolume,
+        OptionEntry::Key(PlayerAction::MoveLeft),
+        OptionEntry::Key(PlayerAction::MoveRight),
+        OptionEntry::Key(PlayerAction::SoftDrop),
+        OptionEntry::Key(PlayerAction::SwitchColor),
+        OptionEntry::Key(PlayerAction::SelectColor(BlockColor::BLUE)),
+        OptionEntry::Key(PlayerAction::SelectColor(BlockColor::PINK)),
+        OptionEntry::Key(PlayerAction::SelectColor(BlockColor::YELLOW)),
+        OptionEntry::Key(PlayerAction::SelectColor(BlockColor::GREEN)),
+        OptionEntry::Key(PlayerAction::Hold),
+        OptionEntry::Palette,
+        OptionEntry::BlockSymbols,
+        OptionEntry::Effects,
+        OptionEntry::Animations,
+        OptionEntry::StartLevel,
+        OptionEntry::BoardSize,
+        OptionEntry::WindowScale,
+        OptionEntry::Language,
+        OptionEntry::Back,
+    ];
+}
+
+/// Identifier for the title of the options screen
+#[derive(Component)]
+struct OptionsTitle;
+
+/// This resource holds the action waiting for a new key to be pressed
+#[derive(Default)]
+struct Rebinding(Option<PlayerAction>);
+
+/// Current values of the settings, for showing them on the options screen
+struct Settings<'a> {
+    volumes: Volumes,
+    bindings: &'a KeyBindings,
+    rebinding: Option<PlayerAction>,
+    palette: Palette,
+    symbols: bool,
+    effects: Effects,
+    animations: bool,
+    start_level: u32,
+    board_preset: BoardPreset,
+    window_scale: WindowScale,
+    language: Language,
+}
+
+/// Bevy Plugin for the options screen
+pub struct OptionsPlugin;
+impl Plugin for OptionsPlugin {
+    fn build(&self, app: &mut App) {
+        let values = load_values(SETTINGS_FILE);
+        app.add_system_set(SystemSet::on_enter(GameState::Options).with_system(on_enter))
+            .add_system_set(SystemSet::on_exit(GameState::Options).with_system(on_exit))
+            .add_system_set(
+                SystemSet::on_update(GameState::Options)
+                    .with_system(
+                        capture_key
+                            .before(MenuNavigationLabel)
+                            .before(AudioHotkeysLabel),
+                    )
+                    .with_system(change_option.after(capture_key))
+                    .with_system(back_to_menu.after(capture_key))
+                    .with_system(update_option_texts.after(change_option)),
+            )
+            .insert_resource(Rebinding::default())
+            .insert_resource(load_volumes(&values))
+            .insert_resource(load_key_bindings(&values))
+            .insert_resource(
+                load_choice(&values, "palette", &Palette::ALL).unwrap_or(Palette::Default),
+            )
+            .insert_resource(BlockSymbols(load_flag(&values, "symbols").unwrap_or(false)))
+            .insert_resource(load_choice(&values, "effects", &Effects::ALL).unwrap_or(Effects::All))
+            .insert_resource(Animations(load_flag(&values, "animations").unwrap_or(true)))
+            .insert_resource(StartLevel(
+                values
+                    .get("start_level")
+                    .and_then(|value| value.parse().ok())
+                    .map_or(1, |level: u32| level.clamp(1, MAX_START_LEVEL)),
+            ))
+            .insert_resource(
+                load_choice(&values, "board_size", &BoardPreset::ALL)
+                    .unwrap_or(BoardPreset::Classic),
+            )
+            .insert_resource(
+                load_choice(&values, "window_scale", &WindowScale::ALL)
+                    .unwrap_or(WindowScale::Medium),
+            )
+            .insert_resource(
+                load_choice(&values, "language", &Language::ALL).unwrap_or(Language::English),
+            );
+    }
+}
+
+/// Helper function to find the saved choice among the possible values
+fn load_choice<T: Copy + Debug>(
+    values: &HashMap<String, String>,
+    key: &str,
+    choices: &[T],
+) -> Option<T> {
+    let value = values.get(key)?;
+    choices
+        .iter()
+        .copied()
+        .find(|choice| format!("{:?}", choice) == *value)
+}
+
+/// Helper function to read the saved on / off setting
+fn load_flag(values: &HashMap<String, String>, key: &str) -> Option<bool> {
+    values.get(key).and_then(|value| value.parse().ok())
+}
+
+/// Helper function to read the saved volumes
+fn load_volumes(values: &HashMap<String, String>) -> Volumes {
+    let volume = |key: &str| {
+        values
+            .get(key)
+            .and_then(|value| value.parse().ok())
+            .map_or(100, |volume: u32| volume.min(100))
+    };
+    Volumes {
+        music: volume("music_volume"),
+        sfx: volume("sfx_volume"),
+    }
+}
+
+/// Helper function to read the saved key bindings. Actions without saved keys keep the default
+/// keys.
+fn load_key_bindings(values: &HashMap<String, String>) -> KeyBindings {
+    let mut bindings = KeyBindings::default();
+    for action in BINDABLE_ACTIONS {
+        if let Some(value) = values.get(&get_action_key(action)) {
+            let keys: Vec<KeyCode> = value
+                .split(',')
+                .filter_map(|name| {
+                    BINDABLE_KEYS
+                        .iter()
+                        .copied()
+                        .find(|key| format!("{:?}", key) == name)
+                })
+                .collect();
+            if !keys.is_empty() {
+                bindings.set_keys(action, &keys);
+            }
+        }
+    }
+    bindings
+}
+
+/// Helper function to get the key of the action in the saved file
+fn get_action_key(action: PlayerAction) -> String {
+    format!("key_{:?}", action).to_lowercase()
+}
+
+/// Saves the settings to disk
+fn save_settings(settings: &Settings) {
+    let mut values = vec![
+        (
+            "music_volume".to_string(),
+            settings.volumes.music.to_string(),
+        ),
+        ("sfx_volume".to_string(), settings.volumes.sfx.to_string()),
+        ("palette".to_string(), format!("{:?}", settings.palette)),
+        ("symbols".to_string(), settings.symbols.to_string()),
+        ("effects".to_string(), format!("{:?}", settings.effects)),
+        ("animations".to_string(), settings.animations.to_string()),
+        ("start_level".to_string(), settings.start_level.to_string()),
+        (
+            "board_size".to_string(),
+            format!("{:?}", settings.board_preset),
+        ),
+        (
+            "window_scale".to_string(),
+            format!("{:?}", settings.window_scale),
+        ),
+        ("language".to_string(), format!("{:?}", settings.language)),
+    ];
+    for action in BINDABLE_ACTIONS {
+        let keys: Vec<String> = settings
+            .bindings
+            .keys(action)
+            .iter()
+            .map(|key| format!("{:?}", key))
+            .collect();
+        values.push((get_action_key(action), keys.join(",")));
+    }
+    save_values(SETTINGS_FILE, &values);
+}
+
+/// Helper function to get the text of the options screen entry
+fn get_option_text(entry: OptionEntry, settings: &Settings) -> String {
+    let language = settings.language;
+    let on_off = |value: bool| language.get(if value { TextKey::On } else { TextKey::Off });
+    match entry {
+        OptionEntry::MusicVolume => {
+            language.format(TextKey::MusicVolumeOption, &[&settings.volumes.music])
+        }
+        OptionEntry::SfxVolume => {
+            language.format(TextKey::SfxVolumeOption, &[&settings.volumes.sfx])
+        }
+        OptionEntry::Key(action) => {
+            let keys = if settings.rebinding == Some(action) {
+                language.get(TextKey::PressKey)
+            } else {
+                let keys: Vec<String> = settings
+                    .bindings
+                    .keys(action)
+                    .iter()
+                    .map(|key| format!("{:?}", key))
+                    .collect();
+                keys.join(" / ")
+            };
+            language.format(
+                TextKey::KeyOption,
+                &[&language.get(TextKey::Action(action)), &keys],
+            )
+        }
+        OptionEntry::Palette => language.format(
+            TextKey::PaletteOption,
+            &[&language.get(TextKey::Palette(settings.palette))],
+        ),
+        OptionEntry::BlockSymbols => {
+            language.format(TextKey::SymbolsOption, &[&on_off(settings.symbols)])
+        }
+        OptionEntry::Effects => language.format(
+            TextKey::EffectsOption,
+            &[&language.get(TextKey::Effects(settings.effects))],
+        ),
+        OptionEntry::Animations => {
+            language.format(TextKey::AnimationsOption, &[&on_off(settings.animations)])
+        }
+        OptionEntry::StartLevel => {
+            language.format(TextKey::StartLevelOption, &[&settings.start_level])
+        }
+        OptionEntry::BoardSize => {
+            let size = settings.board_preset.size();
+            language.format(
+                TextKey::BoardSizeOption,
+                &[
+                    &language.get(TextKey::BoardPreset(settings.board_preset)),
+                    &size.width,
+                    &size.height,
+                ],
+            )
+        }
+        OptionEntry::WindowScale => language.format(
+            TextKey::WindowScaleOption,
+            &[&language.get(TextKey::WindowScale(settings.window_scale))],
+        ),
+        OptionEntry::Language => language.format(TextKey::LanguageOption, &[&language.name()]),
+        OptionEntry::Back => language.get(TextKey::Back),
+    }
+}
+
+/// Called once when switching to `GameState::Options`
+fn on_enter(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut focus: ResMut<MenuFocus>,
+    mut rebinding: ResMut<Rebinding>,
+    volumes: Res<Volumes>,
+    bindings: Res<KeyBindings>,
+    palette: Res<Palette>,
+    symbols: Res<BlockSymbols>,
+    effects: Res<Effects>,
+    animations: Res<Animations>,
+    start_level: Res<StartLevel>,
+    board_preset: Res<BoardPreset>,
+    window_scale: Res<WindowScale>,
+    language: Res<Language>,
+) {
+    println!("Enter GameState::Options");
+    focus.0 = 0;
+    rebinding.0 = None;
+
+    let settings = Settings {
+        volumes: *volumes,
+        bindings: &bindings,
+        rebinding: None,
+        palette: *palette,
+        symbols: symbols.0,
+        effects: *effects,
+        animations: animations.0,
+        start_level: start_level.0,
+        board_preset: *board_preset,
+        window_scale: *window_scale,
+        language: *language,
+    };
+
+    // Background covers the boards when the options are opened during a game
+    let node = commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::ColumnReverse,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            color: UiColor(BACKGROUND_COLOR),
+            ..default()
+        })
+        .insert(MenuNode)
+        .insert(BoardArea)
+        .id();
+
+    let title = commands
+        .spawn_bundle(
+            TextBundle::from_section(
+                language.get(TextKey::Options),
+                TextStyle {
+                    font: asset_server.load("fonts/04b_30.ttf"),
+                    font_size: 24.0,
+                    color: Color::BLACK,
+                },
+            )
+            .with_style(Style {
+                margin: UiRect::new(Val::Px(0.0), Val::Px(0.0), Val::Px(20.0), Val::Px(20.0)),
+                ..default()
+            }),
+        )
+        .insert(OptionsTitle)
+        .id();
+
+    let mut children = vec![title];
+    for (index, entry) in OptionEntry::ALL.iter().enumerate() {
+        let text = spawn_menu_entry(
+            &mut commands,
+            asset_server.load("fonts/04b_30.ttf"),
+            get_option_text(*entry, &settings),
+            16.0,
+            index,
+        );
+        commands.entity(text).insert(*entry);
+        children.push(text);
+    }
+    commands.entity(node).push_children(&children);
+}
+
+/// Called once when switching from `GameState::Options`. Settings are saved at this point.
+fn on_exit(
+    mut commands: Commands,
+    query: Query<Entity, With<MenuNode>>,
+    volumes: Res<Volumes>,
+    bindings: Res<KeyBindings>,
+    palette: Res<Palette>,
+    symbols: Res<BlockSymbols>,
+    effects: Res<Effects>,
+    animations: Res<Animations>,
+    start_level: Res<StartLevel>,
+    board_preset: Res<BoardPreset>,
+    window_scale: Res<WindowScale>,
+    language: Res<Language>,
+) {
+    println!("Exit GameState::Options");
+
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    save_settings(&Settings {
+        volumes: *volumes,
+        bindings: &bindings,
+        rebinding: None,
+        palette: *palette,
+        symbols: symbols.0,
+        effects: *effects,
+        animations: animations.0,
+        start_level: start_level.0,
+        board_preset: *board_preset,
+        window_scale: *window_scale,
+        language: *language,
+    });
+}
+
+/// Bind the next pressed key to the action waiting for it. Esc cancels the binding. Menu actions
+/// are consumed while waiting, so that the pressed key doesn't also move the focus.
+fn capture_key(
+    mut rebinding: ResMut<Rebinding>,
+    mut input: ResMut<Input<KeyCode>>,
+    mut actions: ResMut<Input<MenuAction>>,
+    mut bindings: ResMut<KeyBindings>,
+    mut audio_events: EventWriter<PlaySfxEvent>,
+) {
+    let action = match rebinding.0 {
+        Some(action) => action,
+        None => return,
+    };
+
+    let key = input
+        .get_just_pressed()
+        .find(|key| BINDABLE_KEYS.contains(*key))
+        .copied();
+    if actions.just_pressed(MenuAction::Back) {
+        rebinding.0 = None;
+    } else if let Some(key) = key {
+        // Key which would leave another action without keys is refused
+        input.reset(key);
+        if bindings.bind(action, key) {
+            rebinding.0 = None;
+            audio_events.send(PlaySfxEvent(Sfx::MenuSelect));
+        } else {
+            audio_events.send(PlaySfxEvent(Sfx::MoveBlocked));
+        }
+    }
+
+    for menu_action in [
+        MenuAction::Up,
+        MenuAction::Down,
+        MenuAction::Left,
+        MenuAction::Right,
+        MenuAction::Select,
+        MenuAction::Back,
+    ] {
+        actions.reset(menu_action);
+    }
+}
+
+/// Change the focused option with Left / Right keys. Return key steps to the next value, or
+/// starts waiting for a new key of the focused action.
+fn change_option(
+    actions: Res<Input<MenuAction>>,
+    focus: Res<MenuFocus>,
+    query: Query<(&FocusEntry, &OptionEntry)>,
+    mut rebinding: ResMut<Rebinding>,
+    mut volumes: ResMut<Volumes>,
+    mut palette: ResMut<Palette>,
+    mut symbols: ResMut<BlockSymbols>,
+    mut effects: ResMut<Effects>,
+    mut animations: ResMut<Animations>,
+    mut start_level: ResMut<StartLevel>,
+    mut board_preset: ResMut<BoardPreset>,
+    mut window_scale: ResMut<WindowScale>,
+    mut language: ResMut<Language>,
+    mut audio_events: EventWriter<PlaySfxEvent>,
+) {
+    let forward =
+        actions.just_pressed(MenuAction::Right) || actions.just_pressed(MenuAction::Select);
+    if !forward && !actions.just_pressed(MenuAction::Left) {
+        return;
+    }
+
+    let step_volume = |volume: u32| {
+        if forward {
+            (volume + VOLUME_STEP).min(100)
+        } else {
+            volume.saturating_sub(VOLUME_STEP)
+        }
+    };
+
+    match get_focused(&focus, &query) {
+        Some(OptionEntry::MusicVolume) => volumes.music = step_volume(volumes.music),
+        Some(OptionEntry::SfxVolume) => volumes.sfx = step_volume(volumes.sfx),
+        Some(OptionEntry::Key(action)) if actions.just_pressed(MenuAction::Select) => {
+            rebinding.0 = Some(action)
+        }
+        Some(OptionEntry::Palette) if forward => *palette = palette.next(),
+        Some(OptionEntry::Palette) => *palette = palette.previous(),
+        Some(OptionEntry::BlockSymbols) => symbols.0 = !symbols.0,
+        Some(OptionEntry::Effects) if forward => *effects = effects.next(),
+        Some(OptionEntry::Effects) => *effects = effects.previous(),
+        Some(OptionEntry::Animations) => animations.0 = !animations.0,
+        Some(OptionEntry::StartLevel) if forward => {
+            start_level.0 = start_level.0 % MAX_START_LEVEL + 1
+        }
+        Some(OptionEntry::StartLevel) => {
+            start_level.0 = (start_level.0 + MAX_START_LEVEL - 2) % MAX_START_LEVEL + 1
+        }
+        Some(OptionEntry::BoardSize) if forward => *board_preset = board_preset.next(),
+        Some(OptionEntry::BoardSize) => *board_preset = board_preset.previous(),
+        Some(OptionEntry::WindowScale) if forward => *window_scale = window_scale.next(),
+        Some(OptionEntry::WindowScale) => *window_scale = window_scale.previous(),
+        Some(OptionEntry::Language) if forward => *language = language.next(),
+        Some(OptionEntry::Language) => *language = language.previous(),
+        _ => return,
+    }
+    audio_events.send(PlaySfxEvent(Sfx::MenuMove));
+}
+
+/// Update the entry texts whenever the options change
+fn update_option_texts(
+    mut query: Query<(&mut Text, &OptionEntry)>,
+    mut title_query: Query<&mut Text, (With<OptionsTitle>, Without<OptionEntry>)>,
+    rebinding: Res<Rebinding>,
+    volumes: Res<Volumes>,
+    bindings: Res<KeyBindings>,
+    palette: Res<Palette>,
+    symbols: Res<BlockSymbols>,
+    effects: Res<Effects>,
+    animations: Res<Animations>,
+    start_level: Res<StartLevel>,
+    board_preset: Res<BoardPreset>,
+    window_scale: Res<WindowScale>,
+    language: Res<Language>,
+) {
+    if !rebinding.is_changed()
+        && !volumes.is_changed()
+        && !bindings.is_changed()
+        && !palette.is_changed()
+        && !symbols.is_changed()
+        && !effects.is_changed()
+        && !animations.is_changed()
+        && !start_level.is_changed()
+        && !board_preset.is_changed()
+        && !window_scale.is_changed()
+        && !language.is_changed()
+    {
+        return;
+    }
+
+    let settings = Settings {
+        volumes: *volumes,
+        bindings: &bindings,
+        rebinding: rebinding.0,
+        palette: *palette,
+        symbols: symbols.0,
+        effects: *effects,
+        animations: animations.0,
+        start_level: start_level.0,
+        board_preset: *board_preset,
+        window_scale: *window_scale,
+        language: *language,
+    };
+    for (mut text, entry) in query.iter_mut() {
+        text.sections[0].value = get_option_text(*entry, &settings);
+    }
+    for mut text in title_query.iter_mut() {
+        text.sections[0].value = language.get(TextKey::Options);
+    }
+}
+
+/// Leave the options if Esc key is pressed or Back is selected. Options opened from the pause
+/// overlay return to it, otherwise the main menu is shown.
+fn back_to_menu(
+    mut actions: ResMut<Input<MenuAction>>,
+    focus: Res<MenuFocus>,
+    query: Query<(&FocusEntry, &OptionEntry)>,
+    mut game_state: ResMut<State<GameState>>,
+    mut audio_events: EventWriter<PlaySfxEvent>,
+) {
+    let back_selected = actions.just_pressed(MenuAction::Select)
+        && get_focused(&focus, &query) == Some(OptionEntry::Back);
+    if actions.just_pressed(MenuAction::Back) || back_selected {
+        audio_events.send(PlaySfxEvent(Sfx::MenuSelect));
+        if game_state.inactives().is_empty() {
+            game_state
+                .set(GameState::Menu)
+                .expect("Failed to change GameState::Menu");
+        } else {
+            game_state
+                .pop()
+                .expect("Failed to return to GameState::Paused");
+        }
+        actions.reset(MenuAction::Back);
+        actions.reset(MenuAction::Select);
+    }
+}
@@ -0,0 +1,187 @@
+//!
+//! Block color palettes for color vision deficiencies, and symbols which tell the block colors
+//! apart without relying on the color alone
+//!
+use crate::constants::prelude::*;
+use crate::in_game::{BlockColor, BlockKind};
+use bevy::prelude::*;
+
+/// Size of the block symbol (in pixels)
+const SYMBOL_SIZE: f32 = 12.0;
+
+/// Distance of the block symbol from the corner of the block (in pixels)
+const SYMBOL_MARGIN: f32 = 12.0;
+
+/// Color of the block symbols
+const SYMBOL_COLOR: Color = Color::rgba(0.0, 0.0, 0.0, 0.6);
+
+/// Defines the colors of the blocks. Selected in the options.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Palette {
+    /// The original colors of the game
+    Default,
+    /// Colors distinguishable without green sensitive cones
+    Deuteranopia,
+    /// Colors distinguishable without red sensitive cones
+    Protanopia,
+    /// Colors distinguishable without blue sensitive cones
+    Tritanopia,
+    /// Saturated colors with large differences in brightness
+    HighContrast,
+}
+
+impl Palette {
+    /// All the palettes in the order they are shown in the menu
+    pub const ALL: [Palette; 5] = [
+        Palette::Default,
+        Palette::Deuteranopia,
+        Palette::Protanopia,
+        Palette::Tritanopia,
+        Palette::HighContrast,
+    ];
+
+    /// Returns the name of the palette
+    pub fn name(&self) -> &'static str {
+        match self {
+            Palette::Default => "Default",
+            Palette::Deuteranopia => "Deuteranopia",
+            Palette::Protanopia => "Protanopia",
+            Palette::Tritanopia => "Tritanopia",
+            Palette::HighContrast => "High contrast",
+        }
+    }
+
+    /// Returns the next palette (wraps around)
+    pub fn next(&self) -> Palette {
+        let index = Palette::ALL.iter().position(|p| p == self).unwrap_or(0);
+        Palette::ALL[(index + 1) % Palette::ALL.len()]
+    }
+
+    /// Returns the previous palette (wraps around)
+    pub fn previous(&self) -> Palette {
+        let index = Palette::ALL.iter().position(|p| p == self).unwrap_or(0);
+        Palette::ALL[(index + Palette::ALL.len() - 1) % Palette::ALL.len()]
+    }
+
+    /// Translates the `BlockColor` into actual RGB color of the palette. Deuteranopia and
+    /// protanopia palettes are picked from the Okabe-Ito palette, and tritanopia palette from
+    /// the IBM color blind safe palette.
+    pub fn color(&self, block_color: BlockColor) -> Color {
+        if block_color == BlockColor::NONE {
+            return Color::NONE;
+        }
+        let hex = match (self, block_color) {
+            (Palette::Default, _) => {
+                return match block_color {
+                    BlockColor::BLUE => Color::CYAN,
+                    BlockColor::YELLOW => Color::GOLD,
+                    BlockColor::PINK => Color::ORANGE_RED,
+                    _ => Color::LIME_GREEN,
+                }
+            }
+            (Palette::Deuteranopia, BlockColor::BLUE) => "0072B2",
+            (Palette::Deuteranopia, BlockColor::YELLOW) => "F0E442",
+            (Palette::Deuteranopia, BlockColor::PINK) => "D55E00",
+            (Palette::Deuteranopia, _) => "CC79A7",
+            (Palette::Protanopia, BlockColor::BLUE) => "56B4E9",
+            (Palette::Protanopia, BlockColor::YELLOW) => "F0E442",
+            (Palette::Protanopia, BlockColor::PINK) => "E69F00",
+            (Palette::Protanopia, _) => "0072B2",
+            (Palette::Tritanopia, BlockColor::BLUE) => "648FFF",
+            (Palette::Tritanopia, BlockColor::YELLOW) => "FFB000",
+            (Palette::Tritanopia, BlockColor::PINK) => "DC267F",
+            (Palette::Tritanopia, _) => "FE6100",
+            (Palette::HighContrast, BlockColor::BLUE) => "00BFFF",
+            (Palette::HighContrast, BlockColor::YELLOW) => "FFFF00",
+            (Palette::HighContrast, BlockColor::PINK) => "FF00FF",
+            (Palette::HighContrast, _) => "00FF00",
+        };
+        Color::hex(hex).unwrap_or(Color::WHITE)
+    }
+
+    /// Returns the color of the block sprite. Wildcard and stone blocks have no `BlockColor`
+    /// of their own.
+    pub fn sprite_color(&self, block_color: BlockColor, kind: BlockKind) -> Color {
+        match kind {
+            BlockKind::WILDCARD => Color::WHITE,
+            BlockKind::STONE => Color::GRAY,
+            _ => self.color(block_color),
+        }
+    }
+}
+
+/// This resource defines whether the colored blocks show a symbol of their color. Selected in
+/// the options.
+pub struct BlockSymbols(pub bool);
+
+/// Symbol overlay of a block is tagged with this `Component`
+#[derive(Component)]
+struct BlockSymbol;
+
+/// Bevy Plugin for the block palettes and symbols
+pub struct PalettePlugin;
+impl Plugin for PalettePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(update_block_symbols);
+    }
+}
+
+/// Helper function to get the symbol sprite and its rotation for the block color. Each color has
+/// its own shape: square, diamond, horizontal bar and vertical bar.
+fn get_symbol(block_color: BlockColor) -> Option<(Vec2, f32)> {
+    match block_color {
+        BlockColor::NONE => None,
+        BlockColor::BLUE => Some((Vec2::splat(SYMBOL_SIZE), 0.0)),
+        BlockColor::YELLOW => Some((Vec2::splat(SYMBOL_SIZE), std::f32::consts::FRAC_PI_4)),
+        BlockColor::PINK => Some((Vec2::new(SYMBOL_SIZE * 1.5, SYMBOL_SIZE / 2.0), 0.0)),
+        BlockColor::GREEN => Some((Vec2::new(SYMBOL_SIZE / 2.0, SYMBOL_SIZE * 1.5), 0.0)),
+    }
+}
+
+/// System for updating the symbol overlays of the blocks whenever their color changes or the
+/// symbols are toggled
+fn update_block_symbols(
+    mut commands: Commands,
+    symbols: Res<BlockSymbols>,
+    query: Query<(Entity, &BlockColor, &BlockKind, Option<&Children>)>,
+    changed_query: Query<Entity, Changed<BlockColor>>,
+    symbol_query: Query<Entity, With<BlockSymbol>>,
+) {
+    for (entity, block_color, kind, children) in query.iter() {
+        if !symbols.is_changed() && !changed_query.contains(entity) {
+            continue;
+        }
+
+        // Replace the old symbol
+        for child in children.iter().flat_map(|children| children.iter()) {
+            if symbol_query.contains(*child) {
+                commands.entity(*child).despawn_recursive();
+            }
+        }
+
+        let symbol = match kind {
+            BlockKind::NORMAL | BlockKind::BOMB => get_symbol(*block_color),
+            BlockKind::WILDCARD | BlockKind::STONE => None,
+        };
+        if let (true, Some((size, rotation))) = (symbols.0, symbol) {
+            let symbol = commands
+                .spawn_bundle(SpriteBundle {
+                    sprite: Sprite {
+                        color: SYMBOL_COLOR,
+                        custom_size: Some(size),
+                        ..default()
+                    },
+                    transform: Transform::from_xyz(
+                        -HALF_BLOCK + SYMBOL_MARGIN,
+                        HALF_BLOCK - SYMBOL_MARGIN,
+                        5.0,
+                    )
+                    .with_rotation(Quat::from_rotation_z(rotation)),
+                    ..default()
+                })
+                .insert(BlockSymbol)
+                .id();
+            commands.entity(entity).push_children(&[symbol]);
+        }
+    }
+}
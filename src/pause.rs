@@ -0,0 +1,188 @@
+//!
+//! Pause overlay of the game. The game is paused with Esc or P key, and the options can be changed
+//! from the overlay without ending the game. Network game can't be paused, since the opponent
+//! keeps playing, so Esc ends it instead.
+//!
+use crate::ai::AiPlayer;
+use crate::audio::{PlaySfxEvent, Sfx};
+use crate::controls::MenuAction;
+use crate::localization::{Language, TextKey};
+use crate::menu::{get_focused, spawn_menu_entry, FocusEntry, MenuFocus};
+use crate::prelude::*;
+use crate::rules::Versus;
+use crate::view::BoardArea;
+use bevy::prelude::*;
+
+/// Background of the overlay. The boards are dimmed, but still visible.
+const OVERLAY_COLOR: Color = Color::rgba(0.55, 0.78, 0.9, 0.85);
+
+/// All nodes of the pause overlay are tagged with this
+#[derive(Component)]
+struct PauseNode;
+
+/// Entries of the pause overlay
+#[derive(Component, Copy, Clone, PartialEq, Eq, Debug)]
+enum PauseEntry {
+    Resume,
+    Options,
+    EndGame,
+}
+
+impl PauseEntry {
+    /// All the entries in the order they are shown on the overlay
+    const ALL: [PauseEntry; 3] = [PauseEntry::Resume, PauseEntry::Options, PauseEntry::EndGame];
+}
+
+/// Bevy Plugin for pausing the game
+pub struct PausePlugin;
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_update(GameState::InGame).with_system(pause_game))
+            .add_system_set(SystemSet::on_enter(GameState::Paused).with_system(on_enter))
+            .add_system_set(SystemSet::on_resume(GameState::Paused).with_system(on_enter))
+            .add_system_set(SystemSet::on_exit(GameState::Paused).with_system(on_exit))
+            .add_system_set(SystemSet::on_pause(GameState::Paused).with_system(on_exit))
+            .add_system_set(SystemSet::on_update(GameState::Paused).with_system(select_entry));
+    }
+}
+
+/// Helper function to get the text of the pause overlay entry
+fn get_entry_text(entry: PauseEntry, language: Language) -> String {
+    match entry {
+        PauseEntry::Resume => language.get(TextKey::Resume),
+        PauseEntry::Options => language.get(TextKey::Options),
+        PauseEntry::EndGame => language.get(TextKey::EndGame),
+    }
+}
+
+/// System for pausing the game in case Esc or P key is pressed. Network game is ended with Esc
+/// key instead. Demo game is left for the AI to handle (any key ends it).
+fn pause_game(
+    mut input: ResMut<Input<KeyCode>>,
+    mut actions: ResMut<Input<MenuAction>>,
+    mut game_state: ResMut<State<GameState>>,
+    versus: Res<Versus>,
+    ai: Res<AiPlayer>,
+) {
+    if ai.demo {
+        return;
+    }
+
+    if *versus == Versus::Network {
+        if actions.just_pressed(MenuAction::Back) {
+            game_state
+                .set(GameState::GameOver)
+                .expect("Failed to change GameState::GameOver");
+            actions.reset(MenuAction::Back);
+        }
+        return;
+    }
+
+    if actions.just_pressed(MenuAction::Back) || input.just_pressed(KeyCode::P) {
+        game_state
+            .push(GameState::Paused)
+            .expect("Failed to push GameState::Paused");
+        input.reset(KeyCode::P);
+        actions.reset(MenuAction::Back);
+    }
+}
+
+/// Called when switching to `GameState::Paused` and when returning to it from the options
+fn on_enter(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut focus: ResMut<MenuFocus>,
+    language: Res<Language>,
+) {
+    println!("Enter GameState::Paused");
+    focus.0 = 0;
+
+    let node = commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::ColumnReverse,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            color: UiColor(OVERLAY_COLOR),
+            ..default()
+        })
+        .insert(PauseNode)
+        .insert(BoardArea)
+        .id();
+
+    let title = commands
+        .spawn_bundle(
+            TextBundle::from_section(
+                language.get(TextKey::Paused),
+                TextStyle {
+                    font: asset_server.load("fonts/04b_30.ttf"),
+                    font_size: 24.0,
+                    color: Color::BLACK,
+                },
+            )
+            .with_style(Style {
+                margin: UiRect::new(Val::Px(0.0), Val::Px(0.0), Val::Px(20.0), Val::Px(20.0)),
+                ..default()
+            }),
+        )
+        .id();
+
+    let mut children = vec![title];
+    for (index, entry) in PauseEntry::ALL.iter().enumerate() {
+        let text = spawn_menu_entry(
+            &mut commands,
+            asset_server.load("fonts/04b_30.ttf"),
+            get_entry_text(*entry, *language),
+            16.0,
+            index,
+        );
+        commands.entity(text).insert(*entry);
+        children.push(text);
+    }
+    commands.entity(node).push_children(&children);
+}
+
+/// Called when switching from `GameState::Paused` and when the options are opened over it
+fn on_exit(mut commands: Commands, query: Query<Entity, With<PauseNode>>) {
+    println!("Exit GameState::Paused");
+
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Activate the focused entry with Return key. Esc or P key resumes the game.
+fn select_entry(
+    mut input: ResMut<Input<KeyCode>>,
+    mut actions: ResMut<Input<MenuAction>>,
+    focus: Res<MenuFocus>,
+    query: Query<(&FocusEntry, &PauseEntry)>,
+    mut game_state: ResMut<State<GameState>>,
+    mut audio_events: EventWriter<PlaySfxEvent>,
+) {
+    let entry = if actions.just_pressed(MenuAction::Back) || input.just_pressed(KeyCode::P) {
+        Some(PauseEntry::Resume)
+    } else if actions.just_pressed(MenuAction::Select) {
+        get_focused(&focus, &query)
+    } else {
+        None
+    };
+
+    // Ending the game exits both the pause and the game itself
+    let result = match entry {
+        Some(PauseEntry::Resume) => game_state.pop(),
+        Some(PauseEntry::Options) => game_state.push(GameState::Options),
+        Some(PauseEntry::EndGame) => game_state.replace(GameState::GameOver),
+        None => return,
+    };
+    result.expect("Failed to change GameState");
+
+    audio_events.send(PlaySfxEvent(Sfx::MenuSelect));
+    input.reset(KeyCode::P);
+    actions.reset(MenuAction::Back);
+    actions.reset(MenuAction::Select);
+}
@@ -0,0 +1,734 @@
+//!
+//! Game modes and the gameplay rules they define
+//!
+use crate::fraction::Fraction;
+use crate::in_game::{BlockKind, Operation};
+use crate::localization::{Language, TextKey};
+use rand::distributions::WeightedIndex;
+use rand::Rng;
+
+/// Defines the selectable game modes
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum GameMode {
+    /// The original game with the four basic operations
+    Classic,
+    /// New operations are introduced as the level advances
+    Advanced,
+    /// Division is exact and blocks hold fractions
+    Fractions,
+    /// Block numbers are not limited and larger numbers are dropped
+    Unbounded,
+    /// Casual game with the classic rules. Blocks don't speed up and drops can be undone.
+    Zen,
+}
+
+impl GameMode {
+    /// All the game modes in the order they are shown in the menu
+    pub const ALL: [GameMode; 5] = [
+        GameMode::Classic,
+        GameMode::Advanced,
+        GameMode::Fractions,
+        GameMode::Unbounded,
+        GameMode::Zen,
+    ];
+
+    /// Returns the name of the game mode
+    pub fn name(&self) -> &'static str {
+        match self {
+            GameMode::Classic => "Classic",
+            GameMode::Advanced => "Advanced",
+            GameMode::Fractions => "Fractions",
+            GameMode::Unbounded => "Unbounded",
+            GameMode::Zen => "Zen",
+        }
+    }
+
+    /// Returns the next game mode (wraps around)
+    pub fn next(&self) -> GameMode {
+        let index = GameMode::ALL.iter().position(|m| m == self).unwrap_or(0);
+        GameMode::ALL[(index + 1) % GameMode::ALL.len()]
+    }
+
+    /// Returns the previous game mode (wraps around)
+    pub fn previous(&self) -> GameMode {
+        let index = GameMode::ALL.iter().position(|m| m == self).unwrap_or(0);
+        GameMode::ALL[(index + GameMode::ALL.len() - 1) % GameMode::ALL.len()]
+    }
+}
+
+/// Defines which blocks are affected when a block is dropped next to same colored blocks
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Adjacency {
+    /// Whole same colored region connected to the dropped block (left, right, above and below)
+    Flood,
+    /// Only the blocks directly next to the dropped block
+    Direct,
+    /// Whole same colored region connected also diagonally
+    EightWay,
+    /// Unbroken same colored lines in the row and column of the dropped block
+    LineOfSight,
+}
+
+impl Adjacency {
+    /// All the adjacency rules in the order they are shown in the menu
+    pub const ALL: [Adjacency; 4] = [
+        Adjacency::Flood,
+        Adjacency::Direct,
+        Adjacency::EightWay,
+        Adjacency::LineOfSight,
+    ];
+
+    /// Returns the name of the adjacency rule
+    pub fn name(&self) -> &'static str {
+        match self {
+            Adjacency::Flood => "Flood",
+            Adjacency::Direct => "Direct",
+            Adjacency::EightWay => "8-way",
+            Adjacency::LineOfSight => "Line of sight",
+        }
+    }
+
+    /// Returns the next adjacency rule (wraps around)
+    pub fn next(&self) -> Adjacency {
+        let index = Adjacency::ALL.iter().position(|a| a == self).unwrap_or(0);
+        Adjacency::ALL[(index + 1) % Adjacency::ALL.len()]
+    }
+
+    /// Returns the previous adjacency rule (wraps around)
+    pub fn previous(&self) -> Adjacency {
+        let index = Adjacency::ALL.iter().position(|a| a == self).unwrap_or(0);
+        Adjacency::ALL[(index + Adjacency::ALL.len() - 1) % Adjacency::ALL.len()]
+    }
+}
+
+/// Defines whether the game is played alone or against an opponent on a second board
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Versus {
+    /// Single player game
+    Off,
+    /// Two players sharing the keyboard
+    TwoPlayers,
+    /// Player against the AI
+    Computer,
+    /// Player against an opponent over the network
+    Network,
+}
+
+impl Versus {
+    /// All the versus options in the order they are shown in the menu
+    #[cfg(not(target_arch = "wasm32"))]
+    pub const ALL: [Versus; 4] = [
+        Versus::Off,
+        Versus::TwoPlayers,
+        Versus::Computer,
+        Versus::Network,
+    ];
+
+    /// All the versus options in the order they are shown in the menu. Browser can't open TCP
+    /// sockets, so the network game is left out of the wasm build.
+    #[cfg(target_arch = "wasm32")]
+    pub const ALL: [Versus; 3] = [Versus::Off, Versus::TwoPlayers, Versus::Computer];
+
+    /// Returns the name of the versus option
+    pub fn name(&self) -> &'static str {
+        match self {
+            Versus::Off => "Off",
+            Versus::TwoPlayers => "2 players",
+            Versus::Computer => "vs AI",
+            Versus::Network => "Network",
+        }
+    }
+
+    /// Returns the next versus option (wraps around)
+    pub fn next(&self) -> Versus {
+        let index = Versus::ALL.iter().position(|v| v == self).unwrap_or(0);
+        Versus::ALL[(index + 1) % Versus::ALL.len()]
+    }
+
+    /// Returns the previous versus option (wraps around)
+    pub fn previous(&self) -> Versus {
+        let index = Versus::ALL.iter().position(|v| v == self).unwrap_or(0);
+        Versus::ALL[(index + Versus::ALL.len() - 1) % Versus::ALL.len()]
+    }
+
+    /// Returns the number of boards in the game
+    pub fn boards(&self) -> usize {
+        match self {
+            Versus::Off => 1,
+            Versus::TwoPlayers | Versus::Computer | Versus::Network => 2,
+        }
+    }
+
+    /// Returns the name of the player of the given board
+    pub fn player_name(&self, board: usize, language: Language) -> String {
+        match (self, board) {
+            (Versus::Computer, 0) | (Versus::Network, 0) => language.get(TextKey::You),
+            (Versus::Computer, _) => language.get(TextKey::Ai),
+            (Versus::Network, _) => language.get(TextKey::Opponent),
+            _ => language.format(TextKey::Player, &[&(board + 1)]),
+        }
+    }
+}
+
+/// Defines which number the dropped block gets when it is combined with several neighbors.
+///
+/// Neighbors are always processed in the order of their distance from the dropped block, and
+/// blocks at the same distance from bottom to top and from left to right (see
+/// `board::order_neighbors`). Dropped block without any neighbors always keeps its number.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Resolution {
+    /// Dropped block takes the result of the first neighbor
+    First,
+    /// Dropped block takes the result of the last neighbor
+    Last,
+    /// Dropped block takes the sum of all the results
+    Sum,
+    /// Dropped block keeps its own number
+    Keep,
+}
+
+impl Resolution {
+    /// All the resolution policies in the order they are shown in the menu
+    pub const ALL: [Resolution; 4] = [
+        Resolution::First,
+        Resolution::Last,
+        Resolution::Sum,
+        Resolution::Keep,
+    ];
+
+    /// Returns the name of the resolution policy
+    pub fn name(&self) -> &'static str {
+        match self {
+            Resolution::First => "First result",
+            Resolution::Last => "Last result",
+            Resolution::Sum => "Sum of results",
+            Resolution::Keep => "Own number",
+        }
+    }
+
+    /// Returns the next resolution policy (wraps around)
+    pub fn next(&self) -> Resolution {
+        let index = Resolution::ALL.iter().position(|r| r == self).unwrap_or(0);
+        Resolution::ALL[(index + 1) % Resolution::ALL.len()]
+    }
+
+    /// Returns the previous resolution policy (wraps around)
+    pub fn previous(&self) -> Resolution {
+        let index = Resolution::ALL.iter().position(|r| r == self).unwrap_or(0);
+        Resolution::ALL[(index + Resolution::ALL.len() - 1) % Resolution::ALL.len()]
+    }
+
+    /// Returns the new number of the dropped block from the results of its neighbors (in
+    /// processing order). `None` if the dropped block keeps its number.
+    pub fn resolve(&self, results: &[Fraction]) -> Option<Fraction> {
+        match self {
+            Resolution::First => results.first().copied(),
+            Resolution::Last => results.last().copied(),
+            Resolution::Sum => results.iter().copied().reduce(|sum, result| sum + result),
+            Resolution::Keep => None,
+        }
+    }
+}
+
+/// Defines which block numbers reach the target and clear the block
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum TargetRule {
+    /// Multiples of the given number (including zero)
+    MultipleOf(i32),
+    /// Exactly the given number
+    Value(i32),
+    /// Prime numbers
+    Prime,
+    /// Perfect squares (including zero)
+    Square,
+    /// Number equal to the current level
+    LevelNumber,
+}
+
+impl TargetRule {
+    /// Returns `true` if the number reaches the target on the given level. Only whole numbers
+    /// can reach the target.
+    pub fn is_target(&self, number: Fraction, level: u32) -> bool {
+        match number.to_integer() {
+            Some(number) => self.is_integer_target(number, level),
+            None => false,
+        }
+    }
+
+    /// Returns `true` if the whole number reaches the target on the given level
+    fn is_integer_target(&self, number: i64, level: u32) -> bool {
+        match *self {
+            TargetRule::MultipleOf(n) => n != 0 && number % n as i64 == 0,
+            TargetRule::Value(value) => number == value as i64,
+            TargetRule::Prime => is_prime(number),
+            TargetRule::Square => {
+                // Floating point square root is only used as a starting point
+                let root = (number.max(0) as f64).sqrt() as i64;
+                number >= 0
+                    && (root.max(1) - 1..=root + 1).any(|r| r.checked_mul(r) == Some(number))
+            }
+            TargetRule::LevelNumber => number == level as i64,
+        }
+    }
+
+    /// Returns how close (0.0 - 1.0) the number is to the nearest target number. Numbers further
+    /// than five away from any target are considered as far as possible and fractions are
+    /// considered half as close as their integer part.
+    pub fn closeness(&self, number: Fraction, level: u32) -> f32 {
+        const MAX_DISTANCE: i64 = 5;
+        let integer = number.trunc();
+        let closeness = (0..=MAX_DISTANCE)
+            .find(|d| {
+                self.is_integer_target(integer.saturating_sub(*d), level)
+                    || self.is_integer_target(integer.saturating_add(*d), level)
+            })
+            .map(|d| 1.0 - d as f32 / MAX_DISTANCE as f32)
+            .unwrap_or(0.0);
+        if number.is_integer() {
+            closeness
+        } else {
+            closeness / 2.0
+        }
+    }
+
+    /// Returns the description of the target shown to the player
+    pub fn description(&self, level: u32, language: Language) -> String {
+        match *self {
+            TargetRule::MultipleOf(n) => language.format(TextKey::TargetMultipleOf, &[&n]),
+            TargetRule::Value(value) => language.format(TextKey::TargetValue, &[&value]),
+            TargetRule::Prime => language.get(TextKey::TargetPrime),
+            TargetRule::Square => language.get(TextKey::TargetSquare),
+            TargetRule::LevelNumber => language.format(TextKey::TargetValue, &[&level]),
+        }
+    }
+}
+
+/// Returns `true` if the number is a prime. Uses Miller-Rabin test which is deterministic with
+/// these bases for all 64-bit numbers.
+fn is_prime(number: i64) -> bool {
+    if number < 2 {
+        return false;
+    }
+    let n = number as u128;
+    let bases = [2u128, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+    if let Some(base) = bases.iter().find(|b| n.is_multiple_of(**b)) {
+        return n == *base;
+    }
+
+    let pow_mod = |mut base: u128, mut exp: u128| {
+        let mut result = 1u128;
+        base %= n;
+        while exp > 0 {
+            if exp & 1 == 1 {
+                result = result * base % n;
+            }
+            base = base * base % n;
+            exp >>= 1;
+        }
+        result
+    };
+
+    // n - 1 = d * 2^s
+    let s = (n - 1).trailing_zeros();
+    let d = (n - 1) >> s;
+    bases.iter().all(|base| {
+        let mut x = pow_mod(*base, d);
+        if x == 1 || x == n - 1 {
+            return true;
+        }
+        for _ in 1..s {
+            x = x * x % n;
+            if x == n - 1 {
+                return true;
+            }
+        }
+        false
+    })
+}
+
+/// Defines the distribution of the dropping block numbers
+#[derive(Clone, PartialEq, Debug)]
+pub struct SpawnDistribution {
+    /// Smallest number (inclusive)
+    pub min: i32,
+    /// Largest number (inclusive)
+    pub max: i32,
+    /// Weight of each number from `min` to `max`. Empty for uniform distribution.
+    pub weights: Vec<u32>,
+}
+
+impl SpawnDistribution {
+    /// Returns uniform distribution of numbers between `min` and `max` (inclusive)
+    pub fn uniform(min: i32, max: i32) -> SpawnDistribution {
+        SpawnDistribution {
+            min,
+            max,
+            weights: vec![],
+        }
+    }
+
+    /// Draws a random number from the distribution
+    pub fn sample<R: Rng>(&self, rng: &mut R) -> i32 {
+        match WeightedIndex::new(&self.weights) {
+            Ok(weighted) => self.min + rng.sample(weighted) as i32,
+            Err(_) => rng.gen_range(self.min..=self.max),
+        }
+    }
+}
+
+/// Resource holding the rules of the current game. Created from the selected `GameMode`,
+/// `Adjacency` and `Resolution` when the game starts.
+pub struct GameRules {
+    pub adjacency: Adjacency,
+    pub resolution: Resolution,
+    /// Operations enabled in this game and the level from which on each of them is used
+    pub operations: Vec<(Operation, u32)>,
+    /// Special blocks and the chance (0.0 - 1.0) of each dropping block being one
+    pub special_blocks: Vec<(BlockKind, f32)>,
+    /// Target rules and the level from which on each of them is used
+    pub targets: Vec<(TargetRule, u32)>,
+    /// Division results are kept as exact fractions instead of rounding them away from zero
+    pub exact_fractions: bool,
+    /// Block numbers are clamped between -limit and limit. `None` for no limit.
+    pub number_limit: Option<i64>,
+    /// Distributions of the dropping block numbers and the level from which on each of them is
+    /// used
+    pub spawn_numbers: Vec<(SpawnDistribution, u32)>,
+    /// Drop speed is multiplied by this each time a block is dropped
+    pub drop_speed_factor: f32,
+    /// Landed blocks can be undone
+    pub undo: bool,
+}
+
+impl GameRules {
+    /// Returns the rules for given game mode, adjacency and resolution policy
+    pub fn new(mode: GameMode, adjacency: Adjacency, resolution: Resolution) -> GameRules {
+        let operations = match mode {
+            GameMode::Classic | GameMode::Fractions | GameMode::Unbounded | GameMode::Zen => vec![
+                (Operation::ADD, 1),
+                (Operation::SUBTRACT, 1),
+                (Operation::MULTIPLY, 1),
+                (Operation::DIVIDE, 1),
+            ],
+            GameMode::Advanced => vec![
+                (Operation::ADD, 1),
+                (Operation::SUBTRACT, 1),
+                (Operation::MULTIPLY, 1),
+                (Operation::DIVIDE, 1),
+                (Operation::MODULO, 2),
+                (Operation::NEGATE, 3),
+                (Operation::SWAP, 4),
+                (Operation::SQUARE, 5),
+                (Operation::SET, 6),
+            ],
+        };
+        let special_blocks = match mode {
+            GameMode::Classic | GameMode::Fractions | GameMode::Unbounded | GameMode::Zen => vec![],
+            GameMode::Advanced => vec![
+                (BlockKind::BOMB, 0.03),
+                (BlockKind::WILDCARD, 0.04),
+                (BlockKind::STONE, 0.05),
+            ],
+        };
+        let targets = match mode {
+            GameMode::Classic | GameMode::Fractions | GameMode::Unbounded | GameMode::Zen => {
+                vec![(TargetRule::MultipleOf(10), 1)]
+            }
+            GameMode::Advanced => vec![
+                (TargetRule::MultipleOf(10), 1),
+                (TargetRule::Square, 3),
+                (TargetRule::MultipleOf(7), 5),
+                (TargetRule::Prime, 7),
+                (TargetRule::LevelNumber, 9),
+                (TargetRule::Value(42), 12),
+            ],
+        };
+        GameRules {
+            adjacency,
+            resolution,
+            operations,
+            special_blocks,
+            targets,
+            exact_fractions: mode == GameMode::Fractions,
+            number_limit: match mode {
+                GameMode::Unbounded => None,
+                _ => Some(99),
+            },
+            drop_speed_factor: if mode == GameMode::Zen { 1.0 } else { 0.99 },
+            undo: mode == GameMode::Zen,
+            spawn_numbers: match mode {
+                GameMode::Classic | GameMode::Fractions | GameMode::Zen => {
+                    vec![(SpawnDistribution::uniform(0, 9), 1)]
+                }
+                GameMode::Advanced => vec![
+                    (SpawnDistribution::uniform(0, 9), 1),
+                    (SpawnDistribution::uniform(1, 15), 8),
+                ],
+                GameMode::Unbounded => vec![
+                    (SpawnDistribution::uniform(0, 9), 1),
+                    // Small numbers are still more common than the large ones
+                    (
+                        SpawnDistribution {
+                            min: 0,
+                            max: 20,
+                            weights: [vec![4; 10], vec![1; 11]].concat(),
+                        },
+                        4,
+                    ),
+                ],
+            },
+        }
+    }
+
+    /// Returns the operations which are in use on the given level
+    pub fn available_operations(&self, level: u32) -> Vec<Operation> {
+        self.operations
+            .iter()
+            .filter(|(_, unlock_level)| *unlock_level <= level)
+            .map(|(op, _)| *op)
+            .collect()
+    }
+
+    /// Returns the target rule which is in use on the given level
+    pub fn target_rule(&self, level: u32) -> TargetRule {
+        self.targets
+            .iter()
+            .rev()
+            .find(|(_, from_level)| *from_level <= level)
+            .map(|(rule, _)| *rule)
+            .unwrap_or(TargetRule::MultipleOf(10))
+    }
+
+    /// Returns the distribution of the dropping block numbers on the given level
+    pub fn spawn_distribution(&self, level: u32) -> SpawnDistribution {
+        self.spawn_numbers
+            .iter()
+            .rev()
+            .find(|(_, from_level)| *from_level <= level)
+            .map(|(distribution, _)| distribution.clone())
+            .unwrap_or_else(|| SpawnDistribution::uniform(0, 9))
+    }
+
+    /// Returns the result of applying the dropped block's operation and number on a neighbor
+    pub fn calculate(&self, number: Fraction, operation: Operation, operand: Fraction) -> Fraction {
+        let result = match operation {
+            Operation::ADD => number + operand,
+            Operation::SUBTRACT => number - operand,
+            Operation::MULTIPLY => number * operand,
+            Operation::DIVIDE => {
+                // Result is rounded away from zero unless exact fractions are in use
+                let result = number / operand;
+                if self.exact_fractions {
+                    result
+                } else {
+                    result.round_away_from_zero()
+                }
+            }
+            Operation::MODULO => number.rem_euclid(operand),
+            Operation::SQUARE => number * number,
+            Operation::NEGATE => -number,
+            Operation::SWAP => match number.to_integer() {
+                // Single digit has no tens digit to swap with
+                Some(value) if value.abs() < 10 => number,
+                Some(value) => {
+                    let digits = value.abs();
+                    let swapped = digits / 100 * 100 + digits % 10 * 10 + digits / 10 % 10;
+                    Fraction::from(value.signum() * swapped)
+                }
+                // Digits of a fraction are left as they are
+                None => number,
+            },
+            Operation::SET => operand,
+        };
+        self.clamp(result)
+    }
+
+    /// Clamps the number between the number limits of the game
+    pub fn clamp(&self, number: Fraction) -> Fraction {
+        match self.number_limit {
+            Some(limit) => number.clamp(-Fraction::from(limit), Fraction::from(limit)),
+            None => number,
+        }
+    }
+
+    /// Returns the new numbers of the neighbors (in the same order) and the new number of the
+    /// dropped block, if it changes, once the dropped block is combined with the neighbors.
+    pub fn combine(
+        &self,
+        number: Fraction,
+        operation: Operation,
+        neighbors: &[Fraction],
+    ) -> (Vec<Fraction>, Option<Fraction>) {
+        let results: Vec<Fraction> = neighbors
+            .iter()
+            .map(|neighbor| self.calculate(*neighbor, operation, number))
+            .collect();
+        let dropped = self.resolution.resolve(&results).map(|n| self.clamp(n));
+        (results, dropped)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn number(value: i64) -> Fraction {
+        Fraction::from(value)
+    }
+
+    fn numbers(values: &[i64]) -> Vec<Fraction> {
+        values.iter().map(|value| number(*value)).collect()
+    }
+
+    #[test]
+    fn swap_tens_and_ones_digits() {
+        let rules = GameRules::new(GameMode::Classic, Adjacency::Flood, Resolution::First);
+        let swap = |value| rules.calculate(number(value), Operation::SWAP, Fraction::ZERO);
+        assert_eq!(swap(7), number(7));
+        assert_eq!(swap(12), number(21));
+        assert_eq!(swap(-34), number(-43));
+    }
+
+    #[test]
+    fn resolve_two_results() {
+        let results = numbers(&[3, 5]);
+        assert_eq!(Resolution::First.resolve(&results), Some(number(3)));
+        assert_eq!(Resolution::Last.resolve(&results), Some(number(5)));
+        assert_eq!(Resolution::Sum.resolve(&results), Some(number(8)));
+        assert_eq!(Resolution::Keep.resolve(&results), None);
+    }
+
+    #[test]
+    fn resolve_three_results() {
+        let results = numbers(&[3, 5, 8]);
+        assert_eq!(Resolution::First.resolve(&results), Some(number(3)));
+        assert_eq!(Resolution::Last.resolve(&results), Some(number(8)));
+        assert_eq!(Resolution::Sum.resolve(&results), Some(number(16)));
+        assert_eq!(Resolution::Keep.resolve(&results), None);
+    }
+
+    #[test]
+    fn resolve_without_results() {
+        for resolution in Resolution::ALL {
+            assert_eq!(resolution.resolve(&[]), None);
+        }
+    }
+
+    #[test]
+    fn combine_two_neighbors() {
+        let expected = [
+            (Resolution::First, Some(number(6))),
+            (Resolution::Last, Some(number(12))),
+            (Resolution::Sum, Some(number(18))),
+            (Resolution::Keep, None),
+        ];
+        for (resolution, dropped) in expected {
+            let rules = GameRules::new(GameMode::Classic, Adjacency::Flood, resolution);
+            let (results, new_number) =
+                rules.combine(number(2), Operation::MULTIPLY, &numbers(&[3, 6]));
+            assert_eq!(results, numbers(&[6, 12]));
+            assert_eq!(new_number, dropped, "{:?}", resolution);
+        }
+    }
+
+    #[test]
+    fn combine_three_neighbors() {
+        let expected = [
+            (Resolution::First, Some(number(5))),
+            (Resolution::Last, Some(number(7))),
+            (Resolution::Sum, Some(number(18))),
+            (Resolution::Keep, None),
+        ];
+        for (resolution, dropped) in expected {
+            let rules = GameRules::new(GameMode::Classic, Adjacency::Flood, resolution);
+            let (results, new_number) =
+                rules.combine(number(4), Operation::ADD, &numbers(&[1, 2, 3]));
+            assert_eq!(results, numbers(&[5, 6, 7]));
+            assert_eq!(new_number, dropped, "{:?}", resolution);
+        }
+    }
+
+    #[test]
+    fn combine_clamps_sum_to_number_limit() {
+        let rules = GameRules::new(GameMode::Classic, Adjacency::Flood, Resolution::Sum);
+        let (results, new_number) = rules.combine(number(10), Operation::ADD, &numbers(&[60, 70]));
+        assert_eq!(results, numbers(&[70, 80]));
+        assert_eq!(new_number, Some(number(99)));
+    }
+
+    #[test]
+    fn prime_numbers() {
+        let primes = [2, 3, 5, 37, 97, 1_000_000_007, 9_223_372_036_854_775_783];
+        for n in primes {
+            assert!(is_prime(n), "{}", n);
+        }
+
+        // Carmichael number, strong pseudoprimes to the first bases and large composites
+        let composites = [
+            -7,
+            0,
+            1,
+            4,
+            561,
+            2047,
+            3_215_031_751,
+            4_294_967_297,
+            1_000_000_007 * 3,
+            i64::MAX,
+        ];
+        for n in composites {
+            assert!(!is_prime(n), "{}", n);
+        }
+    }
+
+    #[test]
+    fn square_numbers() {
+        let square = |n| TargetRule::Square.is_target(number(n), 1);
+        for n in [0, 1, 4, 16, 3_037_000_499 * 3_037_000_499] {
+            assert!(square(n), "{}", n);
+        }
+        for n in [-4, -1, 2, 15, 3_037_000_499 * 3_037_000_499 + 1, i64::MAX] {
+            assert!(!square(n), "{}", n);
+        }
+    }
+
+    #[test]
+    fn value_and_level_number_targets() {
+        assert!(TargetRule::Value(10).is_target(number(10), 1));
+        assert!(!TargetRule::Value(10).is_target(number(-10), 1));
+        assert!(TargetRule::LevelNumber.is_target(number(3), 3));
+        assert!(!TargetRule::LevelNumber.is_target(number(3), 4));
+    }
+
+    #[test]
+    fn fractions_never_reach_target() {
+        let half = number(1) / number(2);
+        let rules = [
+            TargetRule::MultipleOf(10),
+            TargetRule::Value(0),
+            TargetRule::Prime,
+            TargetRule::Square,
+            TargetRule::LevelNumber,
+        ];
+        for rule in rules {
+            assert!(!rule.is_target(number(4) + half, 4), "{:?}", rule);
+            assert!(!rule.is_target(-half, 0), "{:?}", rule);
+        }
+    }
+
+    #[test]
+    fn closeness_by_distance() {
+        let rule = TargetRule::MultipleOf(10);
+        for distance in 0..=5 {
+            let expected = 1.0 - distance as f32 / 5.0;
+            let above = rule.closeness(number(20 + distance), 1);
+            let below = rule.closeness(number(-20 - distance), 1);
+            assert!((above - expected).abs() < 1e-6, "{}", distance);
+            assert!((below - expected).abs() < 1e-6, "{}", distance);
+        }
+
+        // Fraction is half as close as its integer part
+        let closeness = rule.closeness(number(21) / number(2), 1);
+        assert!((closeness - 0.5).abs() < 1e-6);
+    }
+}
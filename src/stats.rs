@@ -0,0 +1,344 @@
+//!
+//! Statistics of the latest game and all the games played so far. Statistics are tracked for the
+//! player of the first board, so they are not kept in a local two player game. Lifetime
+//! statistics are saved to disk after each game which the AI has not played.
+//!
+use crate::ai::AiPlayer;
+use crate::board::PerBoard;
+use crate::controls::MenuAction;
+use crate::in_game::{
+    get_operator, BlockClearedEvent, Chain, ColorSwitchedEvent, LastDroppedBlock, Operation,
+    SpawnSolidBlockEvent,
+};
+use crate::localization::{Language, TextKey};
+use crate::prelude::*;
+use crate::rules::Versus;
+use crate::storage::{load_values, save_values};
+use crate::view::BoardArea;
+use bevy::prelude::*;
+
+/// Name of the file holding the lifetime statistics
+const STATS_FILE: &str = "stats.txt";
+
+/// Statistics of one or more games
+#[derive(Default, Clone)]
+pub struct Stats {
+    pub games: u32,
+    pub blocks_dropped: u32,
+    pub blocks_cleared: u32,
+    /// Cleared blocks by the operation of the dropped block (in the order of `Operation::ALL`)
+    pub clears_per_operation: [u32; Operation::ALL.len()],
+    /// Sum of the cleared block numbers
+    pub value_cleared: f64,
+    pub max_chain: u32,
+    pub color_switches: u32,
+    /// Time played in seconds
+    pub time_played: f32,
+}
+
+impl Stats {
+    /// Returns the average number of the cleared blocks
+    pub fn average_value_cleared(&self) -> f64 {
+        if self.blocks_cleared == 0 {
+            0.0
+        } else {
+            self.value_cleared / self.blocks_cleared as f64
+        }
+    }
+
+    /// Returns the number of dropped blocks per minute of play
+    pub fn drops_per_minute(&self) -> f32 {
+        if self.time_played < 1.0 {
+            0.0
+        } else {
+            self.blocks_dropped as f32 * 60.0 / self.time_played
+        }
+    }
+
+    /// Adds the statistics of a game into these
+    fn add(&mut self, other: &Stats) {
+        self.games += other.games;
+        self.blocks_dropped += other.blocks_dropped;
+        self.blocks_cleared += other.blocks_cleared;
+        for (clears, other_clears) in self
+            .clears_per_operation
+            .iter_mut()
+            .zip(other.clears_per_operation)
+        {
+            *clears += other_clears;
+        }
+        self.value_cleared += other.value_cleared;
+        self.max_chain = self.max_chain.max(other.max_chain);
+        self.color_switches += other.color_switches;
+        self.time_played += other.time_played;
+    }
+
+    /// Returns the statistics as lines of text
+    pub fn lines(&self, language: Language) -> Vec<String> {
+        let clears: Vec<String> = Operation::ALL
+            .iter()
+            .zip(self.clears_per_operation)
+            .filter(|(_, clears)| *clears > 0)
+            .map(|(operation, clears)| format!("{}:{}", get_operator(*operation), clears))
+            .collect();
+        let seconds = self.time_played as u32;
+
+        let clears = if clears.is_empty() {
+            "-".to_string()
+        } else {
+            clears.join(" ")
+        };
+        let time_played = format!(
+            "{}:{:02}:{:02}",
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        );
+
+        vec![
+            language.format(TextKey::BlocksDropped, &[&self.blocks_dropped]),
+            language.format(TextKey::BlocksCleared, &[&self.blocks_cleared]),
+            language.format(TextKey::ClearsPerOperation, &[&clears]),
+            language.format(
+                TextKey::AverageValue,
+                &[&format!("{:.1}", self.average_value_cleared())],
+            ),
+            language.format(TextKey::MaxChain, &[&self.max_chain]),
+            language.format(TextKey::ColorSwitches, &[&self.color_switches]),
+            language.format(TextKey::TimePlayed, &[&time_played]),
+            language.format(
+                TextKey::DropsPerMinute,
+                &[&format!("{:.1}", self.drops_per_minute())],
+            ),
+        ]
+    }
+
+    /// Loads the lifetime statistics from disk
+    fn load() -> Stats {
+        let values = load_values(STATS_FILE);
+        let get = |key: &str| values.get(key).and_then(|value| value.parse().ok());
+
+        let mut stats = Stats {
+            games: get("games").unwrap_or(0),
+            blocks_dropped: get("blocks_dropped").unwrap_or(0),
+            blocks_cleared: get("blocks_cleared").unwrap_or(0),
+            value_cleared: values
+                .get("value_cleared")
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(0.0),
+            max_chain: get("max_chain").unwrap_or(0),
+            color_switches: get("color_switches").unwrap_or(0),
+            time_played: values
+                .get("time_played")
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(0.0),
+            ..default()
+        };
+        for (i, operation) in Operation::ALL.iter().enumerate() {
+            stats.clears_per_operation[i] = get(&get_operation_key(*operation)).unwrap_or(0);
+        }
+        stats
+    }
+
+    /// Saves the lifetime statistics to disk
+    fn save(&self) {
+        let mut values = vec![
+            ("games".to_string(), self.games.to_string()),
+            (
+                "blocks_dropped".to_string(),
+                self.blocks_dropped.to_string(),
+            ),
+            (
+                "blocks_cleared".to_string(),
+                self.blocks_cleared.to_string(),
+            ),
+            ("value_cleared".to_string(), self.value_cleared.to_string()),
+            ("max_chain".to_string(), self.max_chain.to_string()),
+            (
+                "color_switches".to_string(),
+                self.color_switches.to_string(),
+            ),
+            ("time_played".to_string(), self.time_played.to_string()),
+        ];
+        for (operation, clears) in Operation::ALL.iter().zip(self.clears_per_operation) {
+            values.push((get_operation_key(*operation), clears.to_string()));
+        }
+        save_values(STATS_FILE, &values);
+    }
+}
+
+/// Helper function to get the key of the clears per operation in the saved file
+fn get_operation_key(operation: Operation) -> String {
+    format!("cleared_{:?}", operation).to_lowercase()
+}
+
+/// This resource holds the statistics of the current (or the latest) game
+#[derive(Default)]
+pub struct GameStats(pub Stats);
+
+/// This resource holds the statistics of all the games
+pub struct LifetimeStats(pub Stats);
+
+/// Bevy Plugin for tracking the statistics
+pub struct StatsPlugin;
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(GameState::InGame).with_system(on_game_start))
+            .add_system_set(SystemSet::on_exit(GameState::InGame).with_system(on_game_end))
+            .add_system_set(
+                SystemSet::on_update(GameState::InGame)
+                    .with_system(track_drops)
+                    .with_system(track_clears)
+                    .with_system(track_color_switches)
+                    .with_system(track_chain_and_time),
+            )
+            .add_system_set(SystemSet::on_enter(GameState::Stats).with_system(on_enter))
+            .add_system_set(SystemSet::on_exit(GameState::Stats).with_system(on_exit))
+            .add_system_set(SystemSet::on_update(GameState::Stats).with_system(back_to_menu))
+            .insert_resource(GameStats::default())
+            .insert_resource(LifetimeStats(Stats::load()));
+    }
+}
+
+/// Returns `true` if the statistics are tracked in the current game
+pub fn is_tracked(versus: Versus) -> bool {
+    versus != Versus::TwoPlayers
+}
+
+/// Called once as the game is started
+fn on_game_start(mut stats: ResMut<GameStats>) {
+    stats.0 = Stats {
+        games: 1,
+        ..default()
+    };
+}
+
+/// Called once after game has ended. Adds the game into the lifetime statistics.
+fn on_game_end(
+    stats: Res<GameStats>,
+    mut lifetime_stats: ResMut<LifetimeStats>,
+    ai: Res<AiPlayer>,
+    versus: Res<Versus>,
+) {
+    if is_tracked(*versus) && !ai.assisted {
+        lifetime_stats.0.add(&stats.0);
+        lifetime_stats.0.save();
+    }
+}
+
+/// System for counting the dropped blocks
+fn track_drops(mut events: EventReader<SpawnSolidBlockEvent>, mut stats: ResMut<GameStats>) {
+    for ev in events.iter() {
+        if ev.board == 0 && !ev.inserted {
+            stats.0.blocks_dropped += 1;
+        }
+    }
+}
+
+/// System for counting the cleared blocks and their values
+fn track_clears(
+    mut events: EventReader<BlockClearedEvent>,
+    last_dropped_blocks: Res<PerBoard<LastDroppedBlock>>,
+    mut stats: ResMut<GameStats>,
+) {
+    for ev in events.iter().filter(|ev| ev.board == 0) {
+        stats.0.blocks_cleared += 1;
+        stats.0.value_cleared += ev.number.to_f64();
+        if let Some(i) = last_dropped_blocks[0]
+            .operation
+            .and_then(|operation| Operation::ALL.iter().position(|op| *op == operation))
+        {
+            stats.0.clears_per_operation[i] += 1;
+        }
+    }
+}
+
+/// System for counting the color switches of the dropping block
+fn track_color_switches(mut events: EventReader<ColorSwitchedEvent>, mut stats: ResMut<GameStats>) {
+    for ev in events.iter() {
+        if ev.0 == 0 {
+            stats.0.color_switches += 1;
+        }
+    }
+}
+
+/// System for tracking the longest chain and the time played
+fn track_chain_and_time(
+    time: Res<Time>,
+    chains: Res<PerBoard<Chain>>,
+    mut stats: ResMut<GameStats>,
+) {
+    stats.0.max_chain = stats.0.max_chain.max(chains[0].length);
+    stats.0.time_played += time.delta_seconds();
+}
+
+/// Called once when switching to `GameState::Stats`
+fn on_enter(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    lifetime_stats: Res<LifetimeStats>,
+    language: Res<Language>,
+) {
+    println!("Enter GameState::Stats");
+
+    let node = commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            color: UiColor(Color::NONE),
+            ..default()
+        })
+        .insert(MenuNode)
+        .insert(BoardArea)
+        .id();
+
+    let mut lines = vec![
+        language.get(TextKey::Statistics),
+        String::new(),
+        language.format(TextKey::Games, &[&lifetime_stats.0.games]),
+    ];
+    lines.extend(lifetime_stats.0.lines(*language));
+    lines.push(String::new());
+    lines.push(language.get(TextKey::Back));
+
+    let text = commands
+        .spawn_bundle(
+            TextBundle::from_section(
+                lines.join("\n"),
+                TextStyle {
+                    font: asset_server.load("fonts/04b_30.ttf"),
+                    font_size: 16.0,
+                    color: Color::BLACK,
+                },
+            )
+            .with_text_alignment(TextAlignment::CENTER),
+        )
+        .id();
+
+    commands.entity(node).push_children(&[text]);
+}
+
+/// Called once when switching from `GameState::Stats`
+fn on_exit(mut commands: Commands, query: Query<Entity, With<MenuNode>>) {
+    println!("Exit GameState::Stats");
+
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Switch back to `GameState::Menu` if Esc or Return key is pressed
+fn back_to_menu(mut actions: ResMut<Input<MenuAction>>, mut game_state: ResMut<State<GameState>>) {
+    if actions.just_pressed(MenuAction::Back) || actions.just_pressed(MenuAction::Select) {
+        game_state
+            .set(GameState::Menu)
+            .expect("Failed to change GameState::Menu");
+        actions.reset(MenuAction::Back);
+        actions.reset(MenuAction::Select);
+    }
+}
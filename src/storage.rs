@@ -0,0 +1,50 @@
+//!
+//! Saving the persistent data of the game. Data is stored as plain text files with one
+//! `key value` pair per line in the `.combine` directory under the home directory of the user.
+//!
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+/// Name of the directory holding the saved files
+const SAVE_DIRECTORY: &str = ".combine";
+
+/// Returns the path of the saved file. Current directory is used if the home directory is not
+/// known.
+fn get_save_path(file_name: &str) -> PathBuf {
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .map(PathBuf::from)
+        .unwrap_or_default()
+        .join(SAVE_DIRECTORY)
+        .join(file_name)
+}
+
+/// Reads the key value pairs from the saved file. Returns an empty map if the file hasn't been
+/// saved yet.
+pub fn load_values(file_name: &str) -> HashMap<String, String> {
+    let contents = fs::read_to_string(get_save_path(file_name)).unwrap_or_default();
+    contents
+        .lines()
+        .filter_map(|line| line.split_once(' '))
+        .map(|(key, value)| (key.to_string(), value.trim().to_string()))
+        .collect()
+}
+
+/// Writes the key value pairs into the saved file
+pub fn save_values(file_name: &str, values: &[(String, String)]) {
+    let path = get_save_path(file_name);
+    let contents: String = values
+        .iter()
+        .map(|(key, value)| format!("{} {}\n", key, value))
+        .collect();
+
+    let result = match path.parent() {
+        Some(directory) => fs::create_dir_all(directory),
+        None => Ok(()),
+    }
+    .and_then(|_| fs::write(&path, contents));
+    if let Err(error) = result {
+        println!("Failed to save {}: {}", path.display(), error);
+    }
+}
@@ -0,0 +1,103 @@
+//!
+//! Sound effects synthesized from simple waveforms at startup. These are played with the
+//! `Audio<Tone>` resource just like the samples loaded from files.
+//!
+use bevy::audio::Decodable;
+use bevy::reflect::TypeUuid;
+use rodio::buffer::SamplesBuffer;
+use std::f32::consts::TAU;
+use std::sync::Arc;
+
+/// Sample rate of the synthesized sounds (in Hz)
+const SAMPLE_RATE: u32 = 44100;
+
+/// Peak amplitude of the synthesized sounds (0.0 - 1.0)
+const AMPLITUDE: f32 = 0.5;
+
+/// Length of the fade in at the start of each note (in seconds). Avoids a click.
+const ATTACK_TIME: f32 = 0.005;
+
+/// Shape of the synthesized wave
+#[derive(Copy, Clone)]
+pub enum Waveform {
+    Sine,
+    Square,
+    Triangle,
+}
+
+impl Waveform {
+    /// Returns the value (-1.0 - 1.0) of the wave at the given phase (0.0 - 1.0)
+    fn sample(&self, phase: f32) -> f32 {
+        match self {
+            Waveform::Sine => (phase * TAU).sin(),
+            Waveform::Square if phase < 0.5 => 1.0,
+            Waveform::Square => -1.0,
+            Waveform::Triangle => 1.0 - 4.0 * (phase - 0.5).abs(),
+        }
+    }
+}
+
+/// Note of a synthesized sound. The pitch slides from `from` to `to` (in Hz) and the volume
+/// fades out by the end of the note.
+pub struct Note {
+    pub from: f32,
+    pub to: f32,
+    /// Length of the note (in seconds)
+    pub duration: f32,
+}
+
+/// Audio asset holding a synthesized sound
+#[derive(TypeUuid, Clone)]
+#[uuid = "8a1d5e3c-2b7f-4c96-a0e4-6f9b3d2c1e7a"]
+pub struct Tone {
+    samples: Arc<[f32]>,
+}
+
+impl Tone {
+    /// Synthesizes the notes one after another
+    pub fn new(waveform: Waveform, notes: &[Note]) -> Tone {
+        let mut samples = Vec::new();
+        for note in notes {
+            let count = (note.duration * SAMPLE_RATE as f32) as usize;
+            let mut phase = 0.0;
+            for i in 0..count {
+                let time = i as f32 / SAMPLE_RATE as f32;
+                let progress = i as f32 / count as f32;
+                let envelope = (time / ATTACK_TIME).min(1.0) * (1.0 - progress).powi(2);
+                samples.push(AMPLITUDE * envelope * waveform.sample(phase));
+
+                let frequency = note.from + (note.to - note.from) * progress;
+                phase = (phase + frequency / SAMPLE_RATE as f32).fract();
+            }
+        }
+        Tone {
+            samples: samples.into(),
+        }
+    }
+}
+
+impl Decodable for Tone {
+    type Decoder = SamplesBuffer<f32>;
+    type DecoderItem = f32;
+
+    fn decoder(&self) -> Self::Decoder {
+        SamplesBuffer::new(1, SAMPLE_RATE, self.samples.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notes_are_played_one_after_another() {
+        let note = |duration| Note {
+            from: 440.0,
+            to: 880.0,
+            duration,
+        };
+        let tone = Tone::new(Waveform::Square, &[note(0.1), note(0.2)]);
+        assert_eq!(tone.samples.len(), (0.3 * SAMPLE_RATE as f32) as usize);
+        assert!(tone.samples.iter().all(|sample| sample.abs() <= AMPLITUDE));
+    }
+}
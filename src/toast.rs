@@ -0,0 +1,119 @@
+use crate::prelude::*;
+use crate::view::BoardArea;
+use bevy::prelude::*;
+
+/// Time how long a toast stays on screen (in seconds)
+const TOAST_TIME: f32 = 3.0;
+
+/// Time how long it takes for a toast to fade away (in seconds)
+const TOAST_FADE_TIME: f32 = 0.5;
+
+/// Send this `Event` to show a short notification on top of the screen
+pub struct ShowToastEvent(pub String);
+
+/// Identifier for the layer holding all the toasts
+#[derive(Component)]
+struct ToastLayer;
+
+/// Toast notification is tagged with this `Component`. Timer tells when it's despawned.
+#[derive(Component)]
+struct Toast(Timer);
+
+/// Bevy Plugin for showing toast notifications
+pub struct ToastPlugin;
+impl Plugin for ToastPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(setup)
+            .add_system(show_toast)
+            .add_system(fade_toasts)
+            .add_event::<ShowToastEvent>();
+    }
+}
+
+/// Startup system for spawning the toast layer
+fn setup(mut commands: Commands) {
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::ColumnReverse,
+                justify_content: JustifyContent::FlexStart,
+                align_items: AlignItems::Center,
+                padding: UiRect {
+                    top: Val::Px(48.0),
+                    ..default()
+                },
+                ..default()
+            },
+            color: UiColor(Color::NONE),
+            ..default()
+        })
+        .insert(ToastLayer)
+        .insert(BoardArea);
+}
+
+/// Spawns a new toast for each received `ShowToastEvent`
+fn show_toast(
+    mut commands: Commands,
+    mut events: EventReader<ShowToastEvent>,
+    layer: Query<Entity, With<ToastLayer>>,
+    my_assets: Res<MyAssets>,
+) {
+    if let Ok(layer) = layer.get_single() {
+        for ev in events.iter() {
+            let text = commands
+                .spawn_bundle(
+                    TextBundle::from_section(
+                        ev.0.clone(),
+                        TextStyle {
+                            font: my_assets.text_style.font.clone(),
+                            font_size: 16.0,
+                            color: Color::WHITE,
+                        },
+                    )
+                    .with_text_alignment(TextAlignment::CENTER),
+                )
+                .id();
+
+            let toast = commands
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        padding: UiRect::all(Val::Px(8.0)),
+                        margin: UiRect::all(Val::Px(4.0)),
+                        ..default()
+                    },
+                    color: UiColor(Color::rgba(0.0, 0.0, 0.0, 0.7)),
+                    ..default()
+                })
+                .insert(Toast(Timer::from_seconds(TOAST_TIME, false)))
+                .id();
+
+            commands.entity(toast).push_children(&[text]);
+            commands.entity(layer).push_children(&[toast]);
+        }
+    }
+}
+
+/// Fades out and despawns the toasts once their time is up
+fn fade_toasts(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut Toast, &mut UiColor, &Children)>,
+    mut text_query: Query<&mut Text>,
+) {
+    for (entity, mut toast, mut color, children) in query.iter_mut() {
+        if toast.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+
+        let alpha = (toast.0.percent_left() * TOAST_TIME / TOAST_FADE_TIME).min(1.0);
+        color.0.set_a(0.7 * alpha);
+        for child in children {
+            if let Ok(mut text) = text_query.get_mut(*child) {
+                text.sections[0].style.color.set_a(alpha);
+            }
+        }
+    }
+}
@@ -0,0 +1,234 @@
+//!
+//! Undoing the landed blocks in the casual game modes. Undo is available only in a single player
+//! game, so everything here is on the first board.
+//!
+use crate::board::{BlockMap, PerBoard};
+use crate::constants::prelude::*;
+use crate::fraction::Fraction;
+use crate::in_game::{
+    BlockColor, BlockKind, BlockLandingLabel, BlockParams, BlockPosition, Chain, DespawningBlocks,
+    DropSpeed, DropTimer, DroppingBlock, GameRng, HeldBlock, LastDroppedBlock, Level, NextBlock,
+    Number, SolidBlock, SpawnDroppingBlockEvent, SpawnSolidBlockEvent,
+};
+use crate::localization::{Language, TextKey};
+use crate::prelude::*;
+use crate::rules::GameRules;
+use crate::toast::ShowToastEvent;
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+
+/// Maximum number of drops which can be undone
+const MAX_UNDO: usize = 10;
+
+/// System taking the snapshots is labeled with this. New blocks are randomized only after the
+/// snapshot so that the random number generator is saved before it's used.
+#[derive(SystemLabel, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct UndoSnapshotLabel;
+
+/// State of the game just before a block landed
+struct Snapshot {
+    /// Position, number, color and kind of each solid block
+    blocks: Vec<(Coords, Fraction, BlockColor, BlockKind)>,
+    /// The block which landed
+    landed: BlockParams,
+    next_block: Option<BlockParams>,
+    held_block: Option<BlockParams>,
+    rng: StdRng,
+    score: i32,
+    level: Level,
+    chain: Chain,
+    drop_speed: f32,
+}
+
+/// This resource holds the snapshots of the latest drops (latest last)
+#[derive(Default)]
+pub struct UndoHistory(Vec<Snapshot>);
+
+/// This `Event` is sent when the game is restored into the snapshot
+struct RestoreSnapshotEvent(Snapshot);
+
+/// Bevy Plugin for undoing the landed blocks
+pub struct UndoPlugin;
+impl Plugin for UndoPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(GameState::InGame).with_system(on_enter))
+            .add_system_set(
+                SystemSet::on_update(GameState::InGame)
+                    .with_system(
+                        take_snapshot
+                            .label(UndoSnapshotLabel)
+                            .after(BlockLandingLabel),
+                    )
+                    .with_system(undo_on_key)
+                    .with_system(restore_board)
+                    .with_system(restore_state),
+            )
+            .add_event::<RestoreSnapshotEvent>()
+            .insert_resource(UndoHistory::default());
+    }
+}
+
+/// Called once as the game is started
+fn on_enter(mut history: ResMut<UndoHistory>) {
+    history.0.clear();
+}
+
+/// System for saving the state of the game each time a block lands
+fn take_snapshot(
+    mut events: EventReader<SpawnSolidBlockEvent>,
+    rules: Res<GameRules>,
+    last_dropped_blocks: Res<PerBoard<LastDroppedBlock>>,
+    query: Query<(Entity, &Number, &BlockPosition, &BlockColor, &BlockKind), With<SolidBlock>>,
+    despawning_blocks: Res<DespawningBlocks>,
+    next_blocks: Res<PerBoard<NextBlock>>,
+    held_blocks: Res<PerBoard<HeldBlock>>,
+    rngs: Res<PerBoard<GameRng>>,
+    scores: Res<PerBoard<Score>>,
+    levels: Res<PerBoard<Level>>,
+    chains: Res<PerBoard<Chain>>,
+    drop_speeds: Res<PerBoard<DropSpeed>>,
+    mut history: ResMut<UndoHistory>,
+) {
+    for ev in events.iter().filter(|ev| !ev.inserted) {
+        if !rules.undo {
+            continue;
+        }
+        let operation = match last_dropped_blocks[0].operation {
+            Some(operation) => operation,
+            None => continue,
+        };
+
+        // Blocks which are being cleared are already gone
+        let blocks = query
+            .iter()
+            .filter(|(entity, ..)| !despawning_blocks.contains(*entity))
+            .map(|(_, number, pos, color, kind)| (pos.0, number.0, *color, *kind))
+            .collect();
+
+        history.0.push(Snapshot {
+            blocks,
+            landed: BlockParams {
+                number: ev.number,
+                color: ev.color,
+                operation,
+                kind: ev.kind,
+            },
+            next_block: next_blocks[0].0,
+            held_block: held_blocks[0].block,
+            rng: rngs[0].0.clone(),
+            score: scores[0].0,
+            level: levels[0].clone(),
+            chain: chains[0].clone(),
+            drop_speed: drop_speeds[0].0,
+        });
+        if history.0.len() > MAX_UNDO {
+            history.0.remove(0);
+        }
+    }
+}
+
+/// System for undoing the latest drop when U key is pressed
+fn undo_on_key(
+    input: Res<Input<KeyCode>>,
+    rules: Res<GameRules>,
+    despawning_blocks: Res<DespawningBlocks>,
+    mut history: ResMut<UndoHistory>,
+    mut restore_events: EventWriter<RestoreSnapshotEvent>,
+    mut toast_events: EventWriter<ShowToastEvent>,
+    language: Res<Language>,
+) {
+    if !input.just_pressed(KeyCode::U) {
+        return;
+    }
+    if !rules.undo {
+        toast_events.send(ShowToastEvent(language.get(TextKey::UndoNotAvailable)));
+        return;
+    }
+
+    // Wait until the cleared blocks are gone
+    if !despawning_blocks.is_empty() {
+        return;
+    }
+
+    match history.0.pop() {
+        Some(snapshot) => {
+            toast_events.send(ShowToastEvent(
+                language.format(TextKey::UndoLeft, &[&history.0.len()]),
+            ));
+            restore_events.send(RestoreSnapshotEvent(snapshot));
+        }
+        None => toast_events.send(ShowToastEvent(language.get(TextKey::NothingToUndo))),
+    }
+}
+
+/// System for replacing the blocks on the board with the ones in the snapshot
+fn restore_board(
+    mut commands: Commands,
+    mut events: EventReader<RestoreSnapshotEvent>,
+    mut block_maps: ResMut<PerBoard<BlockMap>>,
+    solid_query: Query<(Entity, &BlockPosition), With<SolidBlock>>,
+    dropping_query: Query<Entity, With<DroppingBlock>>,
+    mut solid_events: EventWriter<SpawnSolidBlockEvent>,
+    mut dropping_events: EventWriter<SpawnDroppingBlockEvent>,
+) {
+    for ev in events.iter() {
+        for (entity, pos) in solid_query.iter() {
+            block_maps[0].set_block(&pos.0, None);
+            commands.entity(entity).despawn_recursive();
+        }
+        for entity in dropping_query.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+
+        for (position, number, color, kind) in ev.0.blocks.iter() {
+            solid_events.send(SpawnSolidBlockEvent {
+                board: 0,
+                number: *number,
+                position: *position,
+                color: *color,
+                kind: *kind,
+                inserted: true,
+            });
+        }
+
+        // The landed block starts dropping again from the top
+        let landed = &ev.0.landed;
+        dropping_events.send(SpawnDroppingBlockEvent {
+            board: 0,
+            number: landed.number,
+            position: block_maps[0].size().initial_position(),
+            color: landed.color,
+            operation: landed.operation,
+            kind: landed.kind,
+        });
+    }
+}
+
+/// System for restoring the score, level, speed, the upcoming and the held blocks from the
+/// snapshot
+fn restore_state(
+    mut events: EventReader<RestoreSnapshotEvent>,
+    mut next_blocks: ResMut<PerBoard<NextBlock>>,
+    mut held_blocks: ResMut<PerBoard<HeldBlock>>,
+    mut rngs: ResMut<PerBoard<GameRng>>,
+    mut scores: ResMut<PerBoard<Score>>,
+    mut levels: ResMut<PerBoard<Level>>,
+    mut chains: ResMut<PerBoard<Chain>>,
+    mut drop_speeds: ResMut<PerBoard<DropSpeed>>,
+    mut drop_timers: ResMut<PerBoard<DropTimer>>,
+) {
+    for ev in events.iter() {
+        next_blocks[0].0 = ev.0.next_block;
+        held_blocks[0] = HeldBlock::default();
+        held_blocks[0].block = ev.0.held_block;
+        rngs[0].0 = ev.0.rng.clone();
+        scores[0].0 = ev.0.score;
+        levels[0] = ev.0.level.clone();
+        chains[0] = ev.0.chain.clone();
+        drop_speeds[0].0 = ev.0.drop_speed;
+        drop_timers[0]
+            .0
+            .set_duration(std::time::Duration::from_secs_f32(ev.0.drop_speed));
+        drop_timers[0].0.reset();
+    }
+}
@@ -0,0 +1,435 @@
+//!
+//! Versus game on two boards side by side. Blocks cleared on one board are sent as garbage to
+//! the bottom of the opponent's board.
+//!
+use crate::audio::{PlaySfxEvent, Sfx};
+use crate::board::{BlockMap, BoardId, PerBoard};
+use crate::constants::prelude::*;
+use crate::fraction::Fraction;
+use crate::in_game::{
+    on_simulation_update, spawn_solid_block_entity, BlockColor, BlockKind, BlockLandingLabel,
+    BlockPosition, DespawningBlocks, DroppingBlock, Level, SolidBlock, SpawnSolidBlockEvent,
+};
+use crate::localization::{Language, TextKey};
+use crate::palette::Palette;
+use crate::prelude::*;
+use crate::rules::{GameRules, Versus};
+use crate::toast::ShowToastEvent;
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+/// Maximum number of garbage rows added at once
+const MAX_GARBAGE_ROWS: u32 = 4;
+
+/// Chance (0.0 - 1.0) of a garbage block being a stone instead of a numbered block
+const GARBAGE_STONE_CHANCE: f32 = 0.5;
+
+/// This `Event` is sent when blocks are cleared on a board of a versus game
+pub struct AttackEvent {
+    /// Board which cleared the blocks
+    pub board: usize,
+    /// Number of cleared blocks (crumbled stones are not counted)
+    pub cleared: u32,
+    /// Current chain length of the board
+    pub chain: u32,
+}
+
+/// Number of garbage blocks waiting to be added into the board
+#[derive(Default)]
+pub struct PendingGarbage(pub u32);
+
+/// Random number generator for the garbage of the board. Seeded at the start of each game along
+/// with `GameRng`.
+pub struct GarbageRng(pub StdRng);
+
+impl Default for GarbageRng {
+    fn default() -> Self {
+        GarbageRng(StdRng::from_entropy())
+    }
+}
+
+/// Outcome of the game
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Outcome {
+    /// The board filled up first
+    Lost(usize),
+    /// Several boards filled up on the same tick
+    Draw,
+}
+
+/// This resource holds the result of the game
+#[derive(Default)]
+pub struct VersusResult {
+    /// Outcome of the game, decided on the tick the first board fills up
+    pub outcome: Option<Outcome>,
+    /// Boards which filled up on the current tick
+    topped_out: Vec<usize>,
+}
+
+impl VersusResult {
+    /// Marks the board filled up on the current tick. The outcome is decided once every board has
+    /// been updated on the tick.
+    pub fn top_out(&mut self, board: usize) {
+        if !self.topped_out.contains(&board) {
+            self.topped_out.push(board);
+        }
+    }
+
+    /// Decides the outcome from the boards which filled up on the current tick. The outcome
+    /// doesn't depend on the order the boards were updated in. Returns `true` if the game ended on
+    /// this tick.
+    fn decide(&mut self) -> bool {
+        let topped_out = std::mem::take(&mut self.topped_out);
+        if self.outcome.is_some() {
+            return false;
+        }
+        self.outcome = match topped_out.as_slice() {
+            [] => return false,
+            [board] => Some(Outcome::Lost(*board)),
+            _ => Some(Outcome::Draw),
+        };
+        true
+    }
+}
+
+/// Bevy Plugin for the versus game
+pub struct VersusPlugin;
+impl Plugin for VersusPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(GameState::InGame).with_system(on_enter))
+            .add_system_set(
+                on_simulation_update()
+                    .with_system(receive_attacks)
+                    .with_system(add_garbage)
+                    .with_system(decide_outcome.after(BlockLandingLabel).after(add_garbage)),
+            )
+            .add_event::<AttackEvent>()
+            .insert_resource(PerBoard::<PendingGarbage>::default())
+            .insert_resource(PerBoard::<GarbageRng>::default())
+            .insert_resource(VersusResult::default());
+    }
+}
+
+/// Called once as the game is started
+fn on_enter(
+    versus: Res<Versus>,
+    mut pending: ResMut<PerBoard<PendingGarbage>>,
+    mut result: ResMut<VersusResult>,
+) {
+    pending.reset(versus.boards());
+    *result = VersusResult::default();
+}
+
+/// Returns the opponent of the given board
+fn opponent(board: usize) -> usize {
+    (board + 1) % MAX_BOARDS
+}
+
+/// Returns the number of garbage blocks sent by the attack. Clearing a single block sends
+/// nothing, every additional block and chain step sends one garbage block.
+pub fn get_garbage_amount(attack: &AttackEvent) -> u32 {
+    attack.cleared.saturating_sub(1) + attack.chain.saturating_sub(1)
+}
+
+/// System for turning the cleared blocks into garbage for the opponent
+fn receive_attacks(
+    mut events: EventReader<AttackEvent>,
+    mut pending: ResMut<PerBoard<PendingGarbage>>,
+    versus: Res<Versus>,
+    language: Res<Language>,
+    mut toast_events: EventWriter<ShowToastEvent>,
+) {
+    for ev in events.iter() {
+        let garbage = get_garbage_amount(ev);
+        if garbage > 0 {
+            pending[opponent(ev.board)].0 += garbage;
+            toast_events.send(ShowToastEvent(language.format(
+                TextKey::SendsBlocks,
+                &[&versus.player_name(ev.board, *language), &garbage],
+            )));
+        }
+    }
+}
+
+/// System for pushing the stack of the board up and adding the pending garbage rows under it.
+/// Garbage is added only while nothing else moves on the board, and the dropping block has left
+/// the top so that the latest landed block is already on the board. Garbage blocks are put into
+/// the `BlockMap` right away so that the lifted stack doesn't fall back down.
+fn add_garbage(
+    mut commands: Commands,
+    my_assets: Res<MyAssets>,
+    palette: Res<Palette>,
+    mut pending: ResMut<PerBoard<PendingGarbage>>,
+    mut rngs: ResMut<PerBoard<GarbageRng>>,
+    mut block_maps: ResMut<PerBoard<BlockMap>>,
+    mut solid_query: Query<
+        (Entity, &mut BlockPosition, &BoardId),
+        (With<SolidBlock>, Without<DroppingBlock>),
+    >,
+    mut dropping_query: Query<(&mut BlockPosition, &BoardId), With<DroppingBlock>>,
+    despawning_blocks: Res<DespawningBlocks>,
+    mut audio_events: EventWriter<PlaySfxEvent>,
+    mut result: ResMut<VersusResult>,
+    rules: Res<GameRules>,
+    levels: Res<PerBoard<Level>>,
+) {
+    for board in 0..pending.0.len() {
+        if pending[board].0 == 0 || result.outcome.is_some() {
+            continue;
+        }
+
+        let block_map = &block_maps[board];
+        let size = block_map.size();
+        let top = size.initial_position().y;
+        let dropping_left_top = dropping_query
+            .iter()
+            .any(|(pos, id)| id.0 == board && pos.0.y < top);
+        let settled =
+            solid_query
+                .iter()
+                .filter(|(_, _, id)| id.0 == board)
+                .all(|(entity, pos, _)| {
+                    !despawning_blocks.contains(entity)
+                        && block_map
+                            .get_block(&Coords::new(pos.0.x, pos.0.y - 1))
+                            .is_some()
+                });
+        if !dropping_left_top || !settled {
+            continue;
+        }
+
+        // Each garbage row has one gap so that it can be cleared through
+        let row_width = size.width - 1;
+        let rows = pending[board].0.div_ceil(row_width).min(MAX_GARBAGE_ROWS);
+        let count = pending[board].0.min(rows * row_width);
+        pending[board].0 -= count;
+
+        // Push the stack up
+        block_maps[board].shift_up(rows as i32);
+        let mut topped_out = false;
+        for (_, mut pos, id) in solid_query.iter_mut() {
+            if id.0 == board {
+                pos.0.y += rows as i32;
+                topped_out |= pos.0.y >= top;
+            }
+        }
+
+        // Fill the new rows from the bottom up. The blocks pushed over the top end the game.
+        let rng = &mut rngs[board].0;
+        let level = levels[board].number;
+        let mut remaining = count;
+        for y in 0..rows as i32 {
+            let gap = rng.gen_range(0..size.width as i32);
+            for x in (0..size.width as i32).filter(|x| *x != gap) {
+                if remaining == 0 {
+                    break;
+                }
+                remaining -= 1;
+
+                let (number, color, kind) = if rng.gen::<f32>() < GARBAGE_STONE_CHANCE {
+                    (Fraction::ZERO, BlockColor::NONE, BlockKind::STONE)
+                } else {
+                    let number = rules.spawn_distribution(level).sample(rng);
+                    let color = *[
+                        BlockColor::BLUE,
+                        BlockColor::YELLOW,
+                        BlockColor::PINK,
+                        BlockColor::GREEN,
+                    ]
+                    .choose(rng)
+                    .unwrap();
+                    (Fraction::from(number), color, BlockKind::NORMAL)
+                };
+                let position = Coords::new(x, y);
+                let entity = spawn_solid_block_entity(
+                    &mut commands,
+                    &my_assets,
+                    *palette,
+                    &SpawnSolidBlockEvent {
+                        board,
+                        number,
+                        position,
+                        color,
+                        kind,
+                        inserted: true,
+                    },
+                );
+                block_maps[board].set_block(&position, Some((entity, color, kind)));
+            }
+        }
+        audio_events.send(PlaySfxEvent(Sfx::BlockDropped));
+
+        // Dropping block is pushed up if it's in the way
+        for (mut pos, id) in dropping_query.iter_mut() {
+            if id.0 == board {
+                while !block_maps[board].is_none(&pos.0) && pos.0.y < top {
+                    pos.0.y += 1;
+                }
+                topped_out |= !block_maps[board].is_none(&pos.0);
+            }
+        }
+
+        if topped_out {
+            result.top_out(board);
+        }
+    }
+}
+
+/// System for ending the game once a board has filled up. Boards filling up on the same tick end
+/// the game in a draw, so that both instances of a network game come to the same outcome.
+fn decide_outcome(mut result: ResMut<VersusResult>, mut game_state: ResMut<State<GameState>>) {
+    if result.decide() {
+        println!("GAME OVER!");
+        game_state
+            .overwrite_set(GameState::GameOver)
+            .expect("Failed to change GameState::GameOver");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::headless::HeadlessGame;
+    use crate::rules::GameMode;
+    use std::collections::HashMap;
+
+    /// Longest time waited for something to happen on the board (in frames)
+    const MAX_FRAMES: u32 = 60 * 60;
+
+    /// Query filter for the blocks which have landed
+    type Landed = (With<SolidBlock>, Without<DroppingBlock>);
+
+    /// Returns the positions of the blocks which have landed on the board
+    fn landed_blocks(game: &mut HeadlessGame, board: usize) -> HashMap<Entity, (i32, i32)> {
+        game.app
+            .world
+            .query_filtered::<(Entity, &BlockPosition, &BoardId), Landed>()
+            .iter(&game.app.world)
+            .filter(|(_, _, id)| id.0 == board)
+            .map(|(entity, pos, _)| (entity, (pos.0.x, pos.0.y)))
+            .collect()
+    }
+
+    /// Returns the number of garbage blocks waiting to be added into the board
+    fn pending_garbage(game: &HeadlessGame, board: usize) -> u32 {
+        game.app.world.resource::<PerBoard<PendingGarbage>>()[board].0
+    }
+
+    /// Sends garbage to the board
+    fn send_garbage(game: &mut HeadlessGame, board: usize, amount: u32) {
+        game.app.world.resource_mut::<PerBoard<PendingGarbage>>()[board].0 = amount;
+    }
+
+    /// Runs the game until the first block has landed on the board
+    fn wait_for_landed_block(game: &mut HeadlessGame, board: usize) {
+        let mut frames = 0;
+        while landed_blocks(game, board).is_empty() {
+            assert!(frames < MAX_FRAMES, "No block landed");
+            game.update();
+            frames += 1;
+        }
+    }
+
+    #[test]
+    fn garbage_amount() {
+        let amount = |cleared, chain| {
+            get_garbage_amount(&AttackEvent {
+                board: 0,
+                cleared,
+                chain,
+            })
+        };
+        assert_eq!(amount(0, 0), 0);
+        assert_eq!(amount(1, 1), 0);
+        assert_eq!(amount(2, 1), 1);
+        assert_eq!(amount(1, 3), 2);
+        assert_eq!(amount(4, 2), 4);
+    }
+
+    #[test]
+    fn garbage_pushes_stack_up() {
+        let mut game = HeadlessGame::new(3, GameMode::Classic, Versus::TwoPlayers);
+        wait_for_landed_block(&mut game, 1);
+
+        // Two full garbage rows
+        let width = game.app.world.resource::<PerBoard<BlockMap>>()[1]
+            .size()
+            .width;
+        send_garbage(&mut game, 1, 2 * (width - 1));
+        let mut frames = 0;
+        let mut stack = landed_blocks(&mut game, 1);
+        while pending_garbage(&game, 1) > 0 {
+            assert!(frames < MAX_FRAMES, "Garbage was not added");
+            stack = landed_blocks(&mut game, 1);
+            game.update();
+            frames += 1;
+        }
+
+        // Stack is lifted by the garbage rows
+        let lifted = landed_blocks(&mut game, 1);
+        assert!(!stack.is_empty());
+        for (entity, (x, y)) in &stack {
+            assert_eq!(lifted[entity], (*x, y + 2));
+        }
+
+        // Each garbage row has exactly one gap
+        let block_map = &game.app.world.resource::<PerBoard<BlockMap>>()[1];
+        for y in 0..2 {
+            let gaps = (0..width as i32)
+                .filter(|x| block_map.get_block(&Coords::new(*x, y)).is_none())
+                .count();
+            assert_eq!(gaps, 1, "Row {}", y);
+        }
+
+        // Garbage was not sent to the other board
+        assert_eq!(pending_garbage(&game, 0), 0);
+    }
+
+    #[test]
+    fn garbage_over_the_top_loses_the_game() {
+        let mut game = HeadlessGame::new(3, GameMode::Classic, Versus::TwoPlayers);
+        wait_for_landed_block(&mut game, 1);
+
+        send_garbage(&mut game, 1, 1000);
+        let mut frames = 0;
+        while !game.is_over() {
+            assert!(frames < MAX_FRAMES, "Board didn't fill up");
+            game.update();
+            frames += 1;
+        }
+        assert_eq!(
+            game.app.world.resource::<VersusResult>().outcome,
+            Some(Outcome::Lost(1))
+        );
+    }
+
+    #[test]
+    fn outcome_of_boards_filling_up() {
+        let mut result = VersusResult::default();
+        assert!(!result.decide());
+        assert_eq!(result.outcome, None);
+
+        result.top_out(1);
+        assert!(result.decide());
+        assert_eq!(result.outcome, Some(Outcome::Lost(1)));
+
+        // Outcome is kept once decided
+        result.top_out(0);
+        assert!(!result.decide());
+        assert_eq!(result.outcome, Some(Outcome::Lost(1)));
+    }
+
+    #[test]
+    fn boards_filling_up_on_same_tick_draw() {
+        for order in [[0, 1], [1, 0]] {
+            let mut result = VersusResult::default();
+            for board in order {
+                result.top_out(board);
+            }
+            assert!(result.decide());
+            assert_eq!(result.outcome, Some(Outcome::Draw));
+        }
+    }
+}
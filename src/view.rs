@@ -0,0 +1,281 @@
+//!
+//! Fits the game into a window of any size. The boards are scaled to fit the window and centered
+//! so that the extra space is left empty on the sides or above and below the boards. The UI is
+//! designed for the default window size and scaled with the boards.
+//!
+use crate::constants::prelude::*;
+use bevy::prelude::*;
+use bevy::window::{WindowMode, WindowResized};
+
+/// This resource holds the number of boards shown side by side and the size of the window
+pub struct ViewLayout {
+    pub boards: usize,
+    /// Side panels are shown next to the boards. Without the panels a single board is centered.
+    pub panels: bool,
+    /// Size of the window in logical pixels
+    pub window: Vec2,
+}
+
+impl ViewLayout {
+    /// Returns the scale of the UI compared to the default window size of the boards
+    pub fn scale(&self) -> f32 {
+        (self.window.x / (WINDOW_WIDTH * self.boards as f32)).min(self.window.y / WINDOW_HEIGHT)
+    }
+
+    /// Returns the size of the area taken by the boards in the window (in logical pixels)
+    pub fn board_area(&self) -> Vec2 {
+        Vec2::new(WINDOW_WIDTH * self.boards as f32, WINDOW_HEIGHT) * self.scale()
+    }
+}
+
+/// This resource holds the offset of the camera from the center of the boards (in world units),
+/// e.g. for shaking the screen
+#[derive(Default)]
+pub struct CameraOffset(pub Vec2);
+
+/// Scale of the window in the windowed mode. Selected in the options.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum WindowScale {
+    Small,
+    Medium,
+    Large,
+}
+
+impl WindowScale {
+    /// All the window scales in the order they are shown in the options
+    pub const ALL: [WindowScale; 3] = [WindowScale::Small, WindowScale::Medium, WindowScale::Large];
+
+    /// Returns the name of the window scale
+    pub fn name(&self) -> &'static str {
+        match self {
+            WindowScale::Small => "Small",
+            WindowScale::Medium => "Medium",
+            WindowScale::Large => "Large",
+        }
+    }
+
+    /// Returns the next window scale (wraps around)
+    pub fn next(&self) -> WindowScale {
+        let index = WindowScale::ALL.iter().position(|s| s == self).unwrap_or(0);
+        WindowScale::ALL[(index + 1) % WindowScale::ALL.len()]
+    }
+
+    /// Returns the previous window scale (wraps around)
+    pub fn previous(&self) -> WindowScale {
+        let index = WindowScale::ALL.iter().position(|s| s == self).unwrap_or(0);
+        WindowScale::ALL[(index + WindowScale::ALL.len() - 1) % WindowScale::ALL.len()]
+    }
+
+    /// Returns the window size of each board compared to the default window size
+    fn factor(&self) -> f32 {
+        match self {
+            WindowScale::Small => 0.75,
+            WindowScale::Medium => 1.0,
+            WindowScale::Large => 1.25,
+        }
+    }
+}
+
+/// UI nodes covering the area of the boards are tagged with this `Component`. The node is resized
+/// and moved over the boards whenever the view layout changes.
+#[derive(Component)]
+pub struct BoardArea;
+
+/// Style and font sizes of a UI node before scaling
+#[derive(Component)]
+struct Unscaled {
+    style: Style,
+    font_sizes: Vec<f32>,
+}
+
+/// Bevy Plugin for fitting the game into the window
+pub struct ViewPlugin;
+impl Plugin for ViewPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(update_window_size)
+            .add_system(update_camera.after(update_window_size))
+            .add_system(scale_ui.after(update_window_size))
+            .add_system(fit_board_areas.after(scale_ui))
+            .add_system(toggle_fullscreen)
+            .add_system(apply_window_scale)
+            .insert_resource(CameraOffset::default())
+            .insert_resource(ViewLayout {
+                boards: 1,
+                panels: false,
+                window: Vec2::new(WINDOW_WIDTH, WINDOW_HEIGHT),
+            });
+    }
+}
+
+/// Helper function to scale the pixel value
+fn scale_val(val: Val, scale: f32) -> Val {
+    match val {
+        Val::Px(px) => Val::Px(px * scale),
+        _ => val,
+    }
+}
+
+/// Helper function to scale the pixel values of the rectangle
+fn scale_rect(rect: UiRect<Val>, scale: f32) -> UiRect<Val> {
+    UiRect {
+        left: scale_val(rect.left, scale),
+        right: scale_val(rect.right, scale),
+        top: scale_val(rect.top, scale),
+        bottom: scale_val(rect.bottom, scale),
+    }
+}
+
+/// Helper function to scale the pixel values of the size
+fn scale_size(size: Size<Val>, scale: f32) -> Size<Val> {
+    Size::new(scale_val(size.width, scale), scale_val(size.height, scale))
+}
+
+/// Helper function to scale the pixel values of the style
+fn scale_style(style: &Style, scale: f32) -> Style {
+    Style {
+        position: scale_rect(style.position, scale),
+        margin: scale_rect(style.margin, scale),
+        padding: scale_rect(style.padding, scale),
+        border: scale_rect(style.border, scale),
+        size: scale_size(style.size, scale),
+        min_size: scale_size(style.min_size, scale),
+        max_size: scale_size(style.max_size, scale),
+        ..style.clone()
+    }
+}
+
+/// System for keeping track of the window size
+fn update_window_size(
+    mut events: EventReader<WindowResized>,
+    windows: Res<Windows>,
+    mut view: ResMut<ViewLayout>,
+) {
+    for ev in events.iter() {
+        let is_primary = windows
+            .get_primary()
+            .is_some_and(|window| window.id() == ev.id);
+
+        // Minimized window has no size
+        if is_primary && ev.width > 0.0 && ev.height > 0.0 {
+            view.window = Vec2::new(ev.width, ev.height);
+        }
+    }
+}
+
+/// System for fitting the camera view to the window. The camera is centered between the boards.
+/// Space of the side panels is left empty if they are not shown.
+fn update_camera(
+    view: Res<ViewLayout>,
+    offset: Res<CameraOffset>,
+    mut query: Query<(&mut OrthographicProjection, &mut Transform), With<Camera2d>>,
+) {
+    if !view.is_changed() && !offset.is_changed() {
+        return;
+    }
+
+    // Window pixels per world unit
+    let pixels = view.scale() * WINDOW_HEIGHT / WORLD_HEIGHT;
+    let half_size = view.window / pixels / 2.0;
+    for (mut projection, mut transform) in query.iter_mut() {
+        projection.left = -half_size.x;
+        projection.right = half_size.x;
+        projection.bottom = -half_size.y;
+        projection.top = half_size.y;
+        let panel_offset = if view.panels { 0.0 } else { -PANEL_WIDTH / 2.0 };
+        transform.translation.x =
+            (view.boards - 1) as f32 * BOARD_AREA_WIDTH / 2.0 + panel_offset + offset.0.x;
+        transform.translation.y = offset.0.y;
+    }
+}
+
+/// System for moving the board area nodes over the boards. Runs after the scaling which resets
+/// the size and the position of the nodes.
+fn fit_board_areas(
+    view: Res<ViewLayout>,
+    mut query: Query<(&mut Style, ChangeTrackers<BoardArea>)>,
+) {
+    let area = view.board_area();
+    for (mut style, tracker) in query.iter_mut() {
+        if view.is_changed() || tracker.is_added() {
+            style.position_type = PositionType::Absolute;
+            style.size = Size::new(Val::Px(area.x), Val::Px(area.y));
+            style.position = UiRect {
+                left: Val::Px((view.window.x - area.x) / 2.0),
+                right: Val::Auto,
+                top: Val::Px((view.window.y - area.y) / 2.0),
+                bottom: Val::Auto,
+            };
+        }
+    }
+}
+
+/// System for scaling the pixel sizes and the font sizes of the UI nodes
+fn scale_ui(
+    mut commands: Commands,
+    view: Res<ViewLayout>,
+    mut query: Query<(Entity, &mut Style, Option<&mut Text>, Option<&Unscaled>), With<Node>>,
+) {
+    let scale = view.scale();
+    for (entity, mut style, text, unscaled) in query.iter_mut() {
+        let (unscaled_style, font_sizes) = match unscaled {
+            Some(_) if !view.is_changed() => continue,
+            Some(unscaled) => (unscaled.style.clone(), unscaled.font_sizes.clone()),
+            None => {
+                let font_sizes: Vec<f32> = text
+                    .as_ref()
+                    .map(|text| {
+                        text.sections
+                            .iter()
+                            .map(|section| section.style.font_size)
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                commands.entity(entity).insert(Unscaled {
+                    style: style.clone(),
+                    font_sizes: font_sizes.clone(),
+                });
+                (style.clone(), font_sizes)
+            }
+        };
+
+        *style = scale_style(&unscaled_style, scale);
+        if let Some(mut text) = text {
+            for (section, font_size) in text.sections.iter_mut().zip(font_sizes) {
+                section.style.font_size = font_size * scale;
+            }
+        }
+    }
+}
+
+/// Toggle between windowed and fullscreen mode with F11 key
+fn toggle_fullscreen(input: Res<Input<KeyCode>>, mut windows: ResMut<Windows>) {
+    if input.just_pressed(KeyCode::F11) {
+        if let Some(window) = windows.get_primary_mut() {
+            let mode = match window.mode() {
+                WindowMode::Windowed => WindowMode::BorderlessFullscreen,
+                _ => WindowMode::Windowed,
+            };
+            window.set_mode(mode);
+        }
+    }
+}
+
+/// Resize the window according to the selected window scale. Fullscreen window is left as it is.
+fn apply_window_scale(
+    scale: Res<WindowScale>,
+    view: Res<ViewLayout>,
+    mut windows: ResMut<Windows>,
+) {
+    if !scale.is_changed() {
+        return;
+    }
+
+    if let Some(window) = windows.get_primary_mut() {
+        if matches!(window.mode(), WindowMode::Windowed) {
+            window.set_resolution(
+                WINDOW_WIDTH * view.boards as f32 * scale.factor(),
+                WINDOW_HEIGHT * scale.factor(),
+            );
+        }
+    }
+}